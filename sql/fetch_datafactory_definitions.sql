@@ -0,0 +1,21 @@
+-- Data Factory definitions lookup query
+-- Input parameters (ODBC positional):
+--   1) workspace (DEFINITION_WORKSPACE, example: 'sales_factory')
+--   2) table LIKE pattern (example: 'dim\_project%')
+-- Expected columns in result:
+--   - column
+--   - definition
+-- Notes:
+--   - `?` is bound in Rust via ODBC parameter binding, in the order above.
+--   - Data Factory datasets are keyed by the lakehouse they land in, so the workspace is matched against [lakehouse].
+--   - `_`, `%`, `[`, `]` and `\` in the pipeline name are escaped with `\`, so the query must keep `ESCAPE '\'`.
+--   - Keep selected column order aligned with downstream markdown formatting.
+
+SELECT
+    [column]
+    , [definition]
+FROM
+    [database].[dbo].[definitions]
+WHERE
+    [lakehouse] = ?
+    AND [table] LIKE ? ESCAPE '\'
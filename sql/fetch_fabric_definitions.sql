@@ -1,11 +1,12 @@
 -- Fabric definitions lookup query
 -- Input parameter (ODBC positional):
---   1) table LIKE pattern (example: 'dim_project%')
+--   1) table LIKE pattern (example: 'dim\_project%')
 -- Expected columns in result:
 --   - column
 --   - definition
 -- Notes:
 --   - `?` is bound in Rust via ODBC parameter binding.
+--   - `_`, `%`, `[`, `]` and `\` in the notebook name are escaped with `\`, so the query must keep `ESCAPE '\'`.
 --   - Keep selected column order aligned with downstream markdown formatting.
 
 SELECT
@@ -14,4 +15,4 @@ SELECT
 FROM
     [database].[dbo].[definitions]
 WHERE
-    [table] LIKE ?
+    [table] LIKE ? ESCAPE '\'
@@ -0,0 +1,368 @@
+//////////////////////////////////////////////////////////
+// AUTHOR   : Stefan B. J. Meeuwessen
+// CREATION : 2026-10-16
+// VERSION  : 0.0.1
+//////////////////////////////////////////////////////////
+
+
+// ----------------------------
+// Compiler Directives
+// ----------------------------
+
+// #![allow(unused)]
+// #![allow(unused_doc_comments)]
+
+
+// ----------------------------
+// Imports
+// ----------------------------
+
+// Standard Libraries
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// External Libraries
+use indicatif::{ProgressBar, ProgressStyle};
+
+// Internal Modules
+use crate::notebook_formats;
+
+
+// ----------------------------
+// Static Batch Settings
+// ----------------------------
+
+pub const FAILURE_LIST_FILE: &str = "_failures.txt";
+
+static NOTEBOOK_EXTENSIONS: &[&str] = &["py", "ipynb", "sql", "scala", "r", "json"];
+
+/// `indicatif` template for `--progress`.
+pub const PROGRESS_TEMPLATE: &str = "[{elapsed_precise}] {bar:40} {pos}/{len} {msg}";
+
+
+// ----------------------------
+// Data Structures
+// ----------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BatchOrder
+{
+    /// Type: Enum.
+    /// Input:
+    /// - `--order` value from CLI.
+    ///
+    /// Output:
+    /// - Order in which batch notebooks are processed.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    SizeAsc,
+    SizeDesc,
+    Name,
+    Mtime,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PathInfo
+{
+    /// Type: Struct.
+    /// Input:
+    /// - File metadata of one batch input.
+    ///
+    /// Output:
+    /// - Sort keys used by [`sort_paths`].
+    ///
+    /// Exceptions:
+    /// - None.
+
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BatchInputs
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Files found below a batch directory.
+    ///
+    /// Output:
+    /// - Notebooks to process and internal (`_`-prefixed) notebooks that were skipped.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    pub notebooks: Vec<PathBuf>,
+    pub skipped: Vec<PathBuf>,
+}
+
+
+// ----------------------------
+// Batch Input Functions
+// ----------------------------
+
+pub fn is_batch_input(path: &Path) -> bool
+{
+    //! Returns `true` when a file should be processed in a batch run.
+    //!
+    //! # Notes
+    //! - Accepts notebook-like extensions (`.py`, `.ipynb`, `.sql`, `.scala`, `.r`, `.json`).
+    //! - Skips hidden (`.`) and internal (`_`) files such as the failure list.
+
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    if name.starts_with('.') || name.starts_with('_')
+    {
+        return false;
+    }
+
+    has_notebook_extension(path)
+}
+
+pub fn is_internal_notebook(path: &Path) -> bool
+{
+    //! Returns `true` for a `_`-prefixed notebook that batch runs skip on purpose.
+
+    let internal = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with('_'))
+        .unwrap_or(false);
+
+    internal && has_notebook_extension(path)
+}
+
+fn has_notebook_extension(path: &Path) -> bool
+{
+    //! Returns `true` when the extension is one of the notebook-like extensions (case-insensitive).
+
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| NOTEBOOK_EXTENSIONS.iter().any(|known| known.eq_ignore_ascii_case(ext)))
+        .unwrap_or(false)
+}
+
+pub fn collect_batch_inputs(batch_dir: &Path) -> io::Result<BatchInputs>
+{
+    //! Recursively collects notebook files below a batch directory.
+    //!
+    //! # Inputs
+    //! - `batch_dir`: Directory passed as the notebook path.
+    //!
+    //! # Returns
+    //! - Sorted notebook paths accepted by [`is_batch_input`]; hidden directories are skipped.
+    //! - A directory of part files (see [`notebook_formats::is_part_file_dir`]) is one input.
+    //! - Sorted `_`-prefixed notebooks in `skipped`, so the caller can report them.
+    //! - `Err(io::Error)` when a directory cannot be read.
+
+    let mut inputs = Vec::new();
+    let mut skipped = Vec::new();
+    let mut pending = vec![batch_dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop()
+    {
+        for entry in fs::read_dir(&dir)?
+        {
+            let path = entry?.path();
+            if path.is_dir()
+            {
+                let hidden = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map(|name| name.starts_with('.'))
+                    .unwrap_or(false);
+                if hidden
+                {
+                    continue;
+                }
+                if notebook_formats::is_part_file_dir(&path)
+                {
+                    inputs.push(path);
+                }
+                else
+                {
+                    pending.push(path);
+                }
+            }
+            else if is_batch_input(&path)
+            {
+                inputs.push(path);
+            }
+            else if is_internal_notebook(&path)
+            {
+                skipped.push(path);
+            }
+        }
+    }
+
+    inputs.sort();
+    skipped.sort();
+    Ok(BatchInputs { notebooks: inputs, skipped })
+}
+
+pub fn parse_batch_order(value: &str) -> Option<BatchOrder>
+{
+    //! Parses an `--order` value (case-insensitive).
+    //!
+    //! # Returns
+    //! - `Some(BatchOrder)` for `size-asc`, `size-desc`, `name` or `mtime`.
+    //! - `None` for any other value.
+
+    match value.trim().to_ascii_lowercase().as_str()
+    {
+        "size-asc" => Some(BatchOrder::SizeAsc),
+        "size-desc" => Some(BatchOrder::SizeDesc),
+        "name" => Some(BatchOrder::Name),
+        "mtime" => Some(BatchOrder::Mtime),
+        _ => None,
+    }
+}
+
+pub fn path_info(path: &Path) -> PathInfo
+{
+    //! Reads the size and modification time of a batch input.
+    //!
+    //! # Returns
+    //! - [`PathInfo`] from the file metadata; size `0` and no modification time when it cannot be read.
+
+    fs::metadata(path)
+        .map(|metadata| PathInfo { size: metadata.len(), modified: metadata.modified().ok() })
+        .unwrap_or_default()
+}
+
+pub fn sort_paths<F>(paths: Vec<PathBuf>, order: BatchOrder, info: F) -> Vec<PathBuf>
+where
+    F: Fn(&Path) -> PathInfo,
+{
+    //! Sorts batch inputs before processing.
+    //!
+    //! # Inputs
+    //! - `paths`: Collected notebook paths.
+    //! - `order`: Requested order.
+    //! - `info`: Metadata lookup ([`path_info`] outside tests).
+    //!
+    //! # Returns
+    //! - `size-asc`: smallest file first; `size-desc`: largest file first.
+    //! - `name`: by path; `mtime`: most recently modified first, unknown times last.
+    //! - Ties are ordered by path.
+
+    let mut keyed: Vec<(PathInfo, PathBuf)> = paths
+        .into_iter()
+        .map(|path| (info(&path), path))
+        .collect();
+
+    keyed.sort_by(|(a_info, a_path), (b_info, b_path)|
+    {
+        let primary = match order
+        {
+            BatchOrder::SizeAsc => a_info.size.cmp(&b_info.size),
+            BatchOrder::SizeDesc => b_info.size.cmp(&a_info.size),
+            BatchOrder::Name => std::cmp::Ordering::Equal,
+            BatchOrder::Mtime => b_info.modified.cmp(&a_info.modified),
+        };
+        primary.then_with(|| a_path.cmp(b_path))
+    });
+
+    keyed.into_iter().map(|(_, path)| path).collect()
+}
+
+
+// ----------------------------
+// Progress Functions
+// ----------------------------
+
+pub fn progress_bar(length: usize) -> ProgressBar
+{
+    //! Creates the `--progress` bar for a batch run.
+    //!
+    //! # Inputs
+    //! - `length`: Number of notebooks in the batch.
+    //!
+    //! # Returns
+    //! - Progress bar drawn to stderr with [`PROGRESS_TEMPLATE`].
+    //!
+    //! # Panics
+    //! - If [`PROGRESS_TEMPLATE`] is not a valid `indicatif` template.
+
+    let bar = ProgressBar::new(length as u64);
+    bar.set_style(
+        ProgressStyle::with_template(PROGRESS_TEMPLATE)
+            .expect("[ERR] - Invalid progress bar template"),
+    );
+    bar
+}
+
+
+// ----------------------------
+// Failure List Functions
+// ----------------------------
+
+pub fn failure_list_path(batch_dir: &Path) -> PathBuf
+{
+    //! Returns the failure list path for a batch directory.
+
+    batch_dir.join(FAILURE_LIST_FILE)
+}
+
+pub fn read_failure_list(list_path: &Path) -> io::Result<Vec<PathBuf>>
+{
+    //! Reads notebook paths from a failure list.
+    //!
+    //! # Inputs
+    //! - `list_path`: Path to `_failures.txt`.
+    //!
+    //! # Returns
+    //! - One path per non-blank line, in file order.
+    //! - Relative lines are resolved against the directory holding the list, not the working directory.
+    //! - An empty list when the file does not exist.
+
+    let list_dir = list_path.parent().unwrap_or(Path::new(""));
+    match fs::read_to_string(list_path)
+    {
+        Ok(content) => Ok(content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| list_dir.join(line))
+            .collect()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+pub fn write_failure_list(list_path: &Path, failures: &[PathBuf]) -> io::Result<()>
+{
+    //! Rewrites the failure list with the latest run's failures.
+    //!
+    //! # Inputs
+    //! - `list_path`: Path to `_failures.txt`.
+    //! - `failures`: Notebook paths that failed in this run.
+    //!
+    //! # Returns
+    //! - `Ok(())` after writing one path per line, or after removing the file when `failures` is empty.
+    //!
+    //! # Notes
+    //! - Paths below the list's directory are stored relative to it, so `--retry-failures` works from any
+    //!   working directory.
+
+    if failures.is_empty()
+    {
+        return match fs::remove_file(list_path)
+        {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        };
+    }
+
+    let list_dir = list_path.parent().unwrap_or(Path::new(""));
+    let content: String = failures
+        .iter()
+        .map(|path| format!("{}\n", path.strip_prefix(list_dir).unwrap_or(path).display()))
+        .collect();
+    fs::write(list_path, content)
+}
@@ -0,0 +1,330 @@
+//////////////////////////////////////////////////////////
+// AUTHOR   : Stefan B. J. Meeuwessen
+// CREATION : 2026-10-16
+// VERSION  : 0.0.1
+//////////////////////////////////////////////////////////
+
+
+// ----------------------------
+// Compiler Directives
+// ----------------------------
+
+// #![allow(unused)]
+// #![allow(unused_doc_comments)]
+
+
+// ----------------------------
+// Imports
+// ----------------------------
+
+// Standard Libraries
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+
+// ----------------------------
+// Data Enumerations
+// ----------------------------
+
+#[derive(Debug)]
+pub enum DoxcerError
+{
+    /// Type: Enum.
+    /// Input:
+    /// - Failure details captured at the error site, with the underlying error where there is one.
+    ///
+    /// Output:
+    /// - Typed application error with a user-facing message; [`Error::source`] returns the wrapped error.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    TemplateNotFound(PathBuf),
+    Io { context: String, source: io::Error },
+    Odbc { kind: OdbcErrorKind, message: String, source: Option<odbc_api::Error> },
+    KeyVault { context: String, source: Option<azure_core::Error> },
+    Api { context: String, source: Option<reqwest::Error> },
+    Task { context: String, source: tokio::task::JoinError },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OdbcErrorKind
+{
+    /// Type: Enum.
+    /// Input:
+    /// - Step that failed, refined by the driver's SQLSTATE, see [`OdbcErrorKind::classify`].
+    ///
+    /// Output:
+    /// - Failure category of a [`DoxcerError::Odbc`], so callers can pick a retry strategy per kind.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    Connection,
+    Authentication,
+    QueryExecution,
+    ResultFetch,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseError
+{
+    /// Type: Enum.
+    /// Input:
+    /// - The first invalid CLI argument found by `parse_cli_args`.
+    ///
+    /// Output:
+    /// - Typed CLI parse error; `Display` gives the `[ERR]` message printed before the usage text.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    MissingExecutableName,
+    MissingNotebookPath,
+    MultipleNotebookPaths { first: String, second: String },
+    ConflictingSelectors { first: String, second: String },
+    ConflictingCommands { arg: String },
+    UnknownSelector { arg: String },
+    UnknownFlag { arg: String },
+    MissingValue { flag: String },
+    InvalidValue { flag: String, value: String, expected: Option<String> },
+    BelowMinimum { flag: String, minimum: u64 },
+}
+
+
+// ----------------------------
+// Constructors
+// ----------------------------
+
+impl DoxcerError
+{
+    pub fn io(context: &str, source: io::Error) -> Self
+    {
+        //! Wraps an I/O error with a description of the failed step.
+
+        DoxcerError::Io { context: context.to_string(), source }
+    }
+
+    pub fn odbc(step_kind: OdbcErrorKind, message: &str, source: odbc_api::Error) -> Self
+    {
+        //! Wraps an ODBC driver error with a description of the failed step.
+        //!
+        //! # Inputs
+        //! - `step_kind`: Kind of the failed step, used when the SQLSTATE does not identify the failure.
+        //! - `message`: Description of the failed step.
+        //! - `source`: Driver error.
+
+        let kind = OdbcErrorKind::classify(&source, step_kind);
+        DoxcerError::Odbc { kind, message: message.to_string(), source: Some(source) }
+    }
+}
+
+impl ParseError
+{
+    pub fn invalid_value(flag: &str, value: &str, expected: Option<&str>) -> Self
+    {
+        //! Builds a [`ParseError::InvalidValue`] for a flag value that could not be parsed.
+        //!
+        //! # Inputs
+        //! - `flag`: Flag the value belongs to.
+        //! - `value`: Rejected value.
+        //! - `expected`: Accepted values for the message (e.g. `utf8 or utf16le`), if worth listing.
+
+        ParseError::InvalidValue
+        {
+            flag: flag.to_string(),
+            value: value.to_string(),
+            expected: expected.map(str::to_string),
+        }
+    }
+}
+
+impl OdbcErrorKind
+{
+    pub fn from_sqlstate(state: &[u8; 5]) -> Option<Self>
+    {
+        //! Maps a SQLSTATE to an error kind.
+        //!
+        //! # Returns
+        //! - `Some(Authentication)` for `28000` (invalid authorization, e.g. a wrong service principal secret).
+        //! - `Some(Connection)` for class `08` (e.g. `08001`, server unreachable) and `HYT01` (connection timeout).
+        //! - `None` for any other state.
+
+        match state
+        {
+            b"28000" => Some(OdbcErrorKind::Authentication),
+            [b'0', b'8', ..] | b"HYT01" => Some(OdbcErrorKind::Connection),
+            _ => None,
+        }
+    }
+
+    pub fn classify(error: &odbc_api::Error, step_kind: OdbcErrorKind) -> Self
+    {
+        //! Categorises an ODBC error.
+        //!
+        //! # Inputs
+        //! - `error`: Driver error.
+        //! - `step_kind`: Kind of the step that failed.
+        //!
+        //! # Returns
+        //! - The kind from [`OdbcErrorKind::from_sqlstate`] when the error carries a recognised SQLSTATE.
+        //! - `step_kind` otherwise.
+
+        match error
+        {
+            odbc_api::Error::Diagnostics { record, .. } => Self::from_sqlstate(&record.state.0).unwrap_or(step_kind),
+            _ => step_kind,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str
+    {
+        //! Returns the lower-case label used in error messages.
+
+        match self
+        {
+            OdbcErrorKind::Connection => "connection",
+            OdbcErrorKind::Authentication => "authentication",
+            OdbcErrorKind::QueryExecution => "query",
+            OdbcErrorKind::ResultFetch => "result fetch",
+        }
+    }
+}
+
+
+// ----------------------------
+// Trait Implementations
+// ----------------------------
+
+impl fmt::Display for DoxcerError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        //! Formats the error as a `[ERR]` log line.
+        //!
+        //! # Notes
+        //! - The wrapped error's message is included, because call sites log with `{}` rather than walking
+        //!   [`Error::source`].
+
+        match self
+        {
+            DoxcerError::TemplateNotFound(path) =>
+            {
+                write!(f, "[ERR] - Prompt template not found: {}", path.display())
+            }
+            DoxcerError::Io { context, source } =>
+            {
+                write!(f, "[ERR] - {}: {}", context, source)
+            }
+            DoxcerError::Odbc { kind, message, source } => match source
+            {
+                Some(source) => write!(f, "[ERR] - ODBC {} error: {}: {}", kind.as_str(), message, source),
+                None => write!(f, "[ERR] - ODBC {} error: {}", kind.as_str(), message),
+            },
+            DoxcerError::KeyVault { context, source } => match source
+            {
+                Some(source) => write!(f, "[ERR] - Key Vault error: {}: {}", context, source),
+                None => write!(f, "[ERR] - Key Vault error: {}", context),
+            },
+            DoxcerError::Api { context, source } => match source
+            {
+                Some(source) => write!(f, "[ERR] - API error: {}: {}", context, source),
+                None => write!(f, "[ERR] - API error: {}", context),
+            },
+            DoxcerError::Task { context, source } =>
+            {
+                write!(f, "[ERR] - {}: {}", context, source)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        //! Formats the error as a `[ERR]` log line.
+
+        match self
+        {
+            ParseError::MissingExecutableName => write!(f, "[ERR] - Missing executable name."),
+            ParseError::MissingNotebookPath => write!(f, "[ERR] - Missing required notebook path argument."),
+            ParseError::MultipleNotebookPaths { first, second } =>
+            {
+                write!(f, "[ERR] - Multiple input paths were provided: '{}' and '{}'.", first, second)
+            }
+            ParseError::ConflictingSelectors { first, second } =>
+            {
+                write!(f, "[ERR] - Conflicting selectors: both '{}' and '{}' were provided.", first, second)
+            }
+            ParseError::ConflictingCommands { arg } =>
+            {
+                write!(f, "[ERR] - Conflicting commands: '{}' cannot be combined with another command.", arg)
+            }
+            ParseError::UnknownSelector { arg } =>
+            {
+                write!(f, "[ERR] - Unknown selector '{}'. Supported selectors: {}.", arg, crate::supported_selector_list())
+            }
+            ParseError::UnknownFlag { arg } =>
+            {
+                write!(f, "[ERR] - Unknown flag '{}'. Run doxcer --help for the supported options.", arg)
+            }
+            ParseError::MissingValue { flag } => write!(f, "[ERR] - Missing value for '{}'.", flag),
+            ParseError::InvalidValue { flag, value, expected } => match expected
+            {
+                Some(expected) => write!(f, "[ERR] - Invalid value '{}' for '{}'. Expected {}.", value, flag, expected),
+                None => write!(f, "[ERR] - Invalid value '{}' for '{}'.", value, flag),
+            },
+            ParseError::BelowMinimum { flag, minimum } => write!(f, "[ERR] - '{}' must be at least {}.", flag, minimum),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl Error for DoxcerError
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)>
+    {
+        //! Returns the wrapped error, so callers can walk the error chain.
+        //!
+        //! # Returns
+        //! - The underlying I/O, ODBC, Key Vault, HTTP or task error.
+        //! - `None` for variants without an underlying error.
+
+        match self
+        {
+            DoxcerError::Io { source, .. } => Some(source),
+            DoxcerError::Odbc { source, .. } => source.as_ref().map(|err| err as &(dyn Error + 'static)),
+            DoxcerError::KeyVault { source, .. } => source.as_ref().map(|err| err as &(dyn Error + 'static)),
+            DoxcerError::Api { source, .. } => source.as_ref().map(|err| err as &(dyn Error + 'static)),
+            DoxcerError::Task { source, .. } => Some(source),
+            DoxcerError::TemplateNotFound(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for DoxcerError
+{
+    fn from(err: io::Error) -> Self
+    {
+        //! Converts a bare I/O error for the `?` operator; prefer [`DoxcerError::io`] to name the failed step.
+
+        DoxcerError::io("I/O error", err)
+    }
+}
+
+impl From<odbc_api::Error> for DoxcerError
+{
+    fn from(err: odbc_api::Error) -> Self
+    {
+        //! Converts a bare ODBC error for the `?` operator; prefer [`DoxcerError::odbc`] to name the failed step.
+        //!
+        //! # Notes
+        //! - Without a recognised SQLSTATE the kind is `QueryExecution`.
+
+        DoxcerError::odbc(OdbcErrorKind::QueryExecution, "ODBC call failed", err)
+    }
+}
@@ -18,13 +18,16 @@
 // ----------------------------
 
 // Standard Libraries
+use std::collections::{HashMap, HashSet};
 use std::ffi::CString;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 // External Libraries
-use anyhow::{Context, Result};
-use crate::fetch_secrets::get_secret_from_key_vault;
+use crate::errors::{DoxcerError, OdbcErrorKind};
+use crate::fetch_secrets::{get_secret_from_key_vault_on, KeyVaultOptions};
+use crate::io_utils::{substitute_placeholders, PlaceholderSyntax};
+use crate::logger::log_warn;
 use odbc_api::{buffers::TextRowSet, ConnectionOptions, Cursor, Environment, ResultSetMetadata};
 
 
@@ -37,8 +40,10 @@ struct DefinitionFabricDbCredentials
     /// Type: Struct.
     /// Input:
     /// - Secret values resolved from Azure Key Vault.
+    ///
     /// Output:
     /// - In-memory Fabric SQL credentials bundle.
+    ///
     /// Exceptions:
     /// - None.
 
@@ -52,8 +57,10 @@ struct DefinitionAzureDbCredentials
     /// Type: Struct.
     /// Input:
     /// - Secret values resolved from Azure Key Vault.
+    ///
     /// Output:
     /// - In-memory Azure SQL credentials bundle.
+    ///
     /// Exceptions:
     /// - None.
 
@@ -67,19 +74,32 @@ pub struct FabricDefinitionConfig<'a>
     /// Type: Struct.
     /// Input:
     /// - Values provided by runtime configuration in `main.rs` for Fabric SQL.
+    ///
     /// Output:
     /// - Settings required for definition lookup and formatting.
+    ///
     /// Exceptions:
     /// - None.
 
     pub repo_root: &'a Path,
+    pub profile_name: &'a str,
+    pub sql_params: &'a HashMap<String, String>,
     pub akv_base_url: &'a str,
+    pub akv_credential: &'a str,
+    pub akv_options: KeyVaultOptions,
+    pub akv_runtime: &'a tokio::runtime::Handle,
     pub definition_fabric_database: &'a str,
     pub akv_secret_definition_fabric_endpoint: &'a str,
     pub akv_secret_definition_fabric_client_id: &'a str,
     pub akv_secret_definition_fabric_password: &'a str,
     pub odbc_batch_size: usize,
     pub odbc_max_byte_size: usize,
+    pub definition_encrypt: OdbcEncrypt,
+    pub definition_trust_cert: bool,
+    pub odbc_timeout_secs: Option<u64>,
+    pub param_layout: DefinitionParamLayout,
+    pub workspace: &'a str,
+    pub allow_partial: bool,
 }
 
 pub struct AzureDefinitionConfig<'a>
@@ -87,19 +107,283 @@ pub struct AzureDefinitionConfig<'a>
     /// Type: Struct.
     /// Input:
     /// - Values provided by runtime configuration in `main.rs` for Azure SQL.
+    ///
     /// Output:
     /// - Settings required for Azure definition lookup and formatting.
+    ///
     /// Exceptions:
     /// - None.
 
     pub repo_root: &'a Path,
     pub akv_base_url: &'a str,
+    pub akv_credential: &'a str,
+    pub akv_options: KeyVaultOptions,
+    pub akv_runtime: &'a tokio::runtime::Handle,
     pub definition_azure_database: &'a str,
     pub akv_secret_definition_azure_endpoint: &'a str,
     pub akv_secret_definition_azure_client_id: &'a str,
     pub akv_secret_definition_azure_password: &'a str,
     pub odbc_batch_size: usize,
     pub odbc_max_byte_size: usize,
+    pub definition_encrypt: OdbcEncrypt,
+    pub definition_trust_cert: bool,
+    pub odbc_timeout_secs: Option<u64>,
+    pub allow_partial: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DefinitionQueryOptions
+{
+    /// Type: Struct.
+    /// Input:
+    /// - ODBC settings shared by the Fabric and Azure SQL definition configs.
+    ///
+    /// Output:
+    /// - Fetch settings for [`query_definitions`].
+    ///
+    /// Exceptions:
+    /// - None.
+
+    pub odbc_batch_size: usize,
+    pub odbc_max_byte_size: usize,
+    pub odbc_timeout_secs: Option<u64>,
+    pub allow_partial: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OdbcEncrypt
+{
+    /// Type: Enum.
+    /// Input:
+    /// - `--odbc-encrypt`, `ODBC_ENCRYPT` or `DEFINITION_ENCRYPT`, see [`parse_odbc_encrypt`].
+    ///
+    /// Output:
+    /// - Value of the `Encrypt` attribute in the ODBC connection string.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    Yes,
+    No,
+    Strict,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DefinitionParamLayout
+{
+    /// Type: Enum.
+    /// Input:
+    /// - Per-profile setting in `PROMPT_PROFILE_SPECS`.
+    ///
+    /// Output:
+    /// - Number and order of the `?` parameters bound to the definitions query, see [`build_definition_params`].
+    ///
+    /// Exceptions:
+    /// - None.
+    ///
+    /// One `?`: the table `LIKE` pattern.
+    #[default]
+    TablePrefix,
+    /// Two `?`: the `DEFINITION_WORKSPACE` value, then the table `LIKE` pattern.
+    /// Falls back to `TablePrefix` when `DEFINITION_WORKSPACE` is empty, see [`effective_param_layout`].
+    WorkspaceAndTablePrefix,
+}
+
+#[derive(Clone, Debug)]
+pub struct OdbcConnectionStringBuilder
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Server, database and service principal credentials plus TLS settings.
+    ///
+    /// Output:
+    /// - ODBC Driver 18 connection string for the definitions database.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    host: String,
+    database: String,
+    uid: String,
+    pwd: String,
+    encrypt: OdbcEncrypt,
+    trust_server_certificate: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DefinitionRow
+{
+    /// Type: Struct.
+    /// Input:
+    /// - One row read from the definitions query.
+    ///
+    /// Output:
+    /// - Cell values in column order; `None` for SQL `NULL`.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    pub cells: Vec<Option<String>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DefinitionTable
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Column names and rows returned by the definitions query.
+    ///
+    /// Output:
+    /// - Definitions result set, formatted as Markdown for the prompt.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    pub columns: Vec<String>,
+    pub rows: Vec<DefinitionRow>,
+}
+
+
+// ----------------------------
+// Definition Table
+// ----------------------------
+
+impl DefinitionTable
+{
+    pub fn filter_nulls(&mut self, column: &str) -> usize
+    {
+        //! Drops rows whose value in `column` is SQL `NULL`.
+        //!
+        //! # Inputs
+        //! - `column`: Column name, matched case-insensitively.
+        //!
+        //! # Returns
+        //! - Number of removed rows.
+        //! - `0` with a `[WRN]` when the table has no such column.
+
+        let Some(col_index) = self.columns.iter().position(|name| name.eq_ignore_ascii_case(column.trim()))
+        else
+        {
+            log_warn!("Definitions have no column '{}'; no NULL rows removed.", column.trim());
+            return 0;
+        };
+
+        let before = self.rows.len();
+        self.rows.retain(|row| !matches!(row.cells.get(col_index), Some(None)));
+        before - self.rows.len()
+    }
+
+    pub fn text_rows(&self) -> Vec<Vec<String>>
+    {
+        //! Returns the rows as text, with `NULL` cells as empty strings.
+
+        self.rows
+            .iter()
+            .map(|row| row.cells.iter().map(|cell| cell.clone().unwrap_or_default()).collect())
+            .collect()
+    }
+}
+
+
+// ----------------------------
+// ODBC Connection String
+// ----------------------------
+
+impl OdbcEncrypt
+{
+    pub fn as_str(self) -> &'static str
+    {
+        //! Returns the connection string value: `yes`, `no` or `strict`.
+
+        match self
+        {
+            OdbcEncrypt::Yes => "yes",
+            OdbcEncrypt::No => "no",
+            OdbcEncrypt::Strict => "strict",
+        }
+    }
+}
+
+pub fn parse_odbc_encrypt(value: &str) -> Option<OdbcEncrypt>
+{
+    //! Parses an `--odbc-encrypt` / `ODBC_ENCRYPT` value (case-insensitive).
+    //!
+    //! # Returns
+    //! - `Some(OdbcEncrypt)` for `yes`, `no` or `strict`.
+    //! - `None` for any other value.
+
+    match value.trim().to_ascii_lowercase().as_str()
+    {
+        "yes" => Some(OdbcEncrypt::Yes),
+        "no" => Some(OdbcEncrypt::No),
+        "strict" => Some(OdbcEncrypt::Strict),
+        _ => None,
+    }
+}
+
+impl OdbcConnectionStringBuilder
+{
+    pub fn new(host: &str, database: &str, uid: &str, pwd: &str) -> Self
+    {
+        //! Creates a builder with `Encrypt=yes` and `TrustServerCertificate=yes`.
+        //!
+        //! # Inputs
+        //! - `host`, `database`, `uid`, `pwd`: Connection values; surrounding whitespace is trimmed.
+
+        Self
+        {
+            host: host.trim().to_string(),
+            database: database.trim().to_string(),
+            uid: uid.trim().to_string(),
+            pwd: pwd.trim().to_string(),
+            encrypt: OdbcEncrypt::Yes,
+            trust_server_certificate: true,
+        }
+    }
+
+    pub fn with_encrypt(mut self, encrypt: OdbcEncrypt) -> Self
+    {
+        //! Sets the `Encrypt` attribute (`--odbc-encrypt`, `ODBC_ENCRYPT` or `DEFINITION_ENCRYPT`).
+
+        self.encrypt = encrypt;
+        self
+    }
+
+    pub fn with_trust_server_certificate(mut self, trust_server_certificate: bool) -> Self
+    {
+        //! Sets the `TrustServerCertificate` attribute (`DEFINITION_TRUST_CERT`).
+
+        self.trust_server_certificate = trust_server_certificate;
+        self
+    }
+
+    pub fn build(&self) -> String
+    {
+        //! Builds the connection string.
+        //!
+        //! # Returns
+        //! - `Driver=...;Server=tcp:<host>,1433;...` with `yes`/`no`/`strict` for `Encrypt` and `yes`/`no`
+        //!   for `TrustServerCertificate`.
+
+        let yes_no = |enabled: bool| if enabled { "yes" } else { "no" };
+
+        format!(
+            "Driver={{ODBC Driver 18 for SQL Server}};\
+            Server=tcp:{host},1433;\
+            Database={db};\
+            Encrypt={encrypt};\
+            TrustServerCertificate={trust};\
+            Authentication=ActiveDirectoryServicePrincipal;\
+            UID={uid};\
+            PWD={pwd};",
+            host = self.host,
+            db = self.database,
+            encrypt = self.encrypt.as_str(),
+            trust = yes_no(self.trust_server_certificate),
+            uid = self.uid,
+            pwd = self.pwd
+        )
+    }
 }
 
 
@@ -107,17 +391,103 @@ pub struct AzureDefinitionConfig<'a>
 // Fabric SQL Helper Functions
 // ----------------------------
 
-fn find_fabric_sql_path(repo_root: &Path) -> PathBuf
+pub fn find_fabric_sql_path(repo_root: &Path, profile_name: &str) -> PathBuf
 {
-    //! Returns the path to the Fabric SQL definitions query file.
+    //! Returns the path to the definitions query file for the active profile.
     //!
     //! # Inputs
     //! - `repo_root`: Repository root path.
+    //! - `profile_name`: Canonical profile name (e.g. `databricks`).
+    //!
+    //! # Returns
+    //! - Path to `sql/fetch_<profile>_definitions.sql` when that file exists.
+    //! - Path to `sql/fetch_fabric_definitions.sql` otherwise.
+    //!
+    //! # SQL Contract
+    //! - The query binds the `?` parameters of the profile's [`DefinitionParamLayout`], see [`build_definition_params`].
+    //! - The table `LIKE` pattern is built by [`escape_like_pattern`], so the query must declare `ESCAPE '\'`.
+
+    let sql_dir = repo_root.join("sql");
+
+    if !profile_name.trim().is_empty()
+    {
+        let profile_sql_path = sql_dir.join(format!("fetch_{}_definitions.sql", profile_name.trim()));
+        if profile_sql_path.exists()
+        {
+            return profile_sql_path;
+        }
+    }
+
+    sql_dir.join("fetch_fabric_definitions.sql")
+}
+
+pub fn build_definition_params(layout: DefinitionParamLayout, table_prefix: &str, workspace: &str) -> Vec<String>
+{
+    //! Builds the positional parameters bound to the definitions query.
+    //!
+    //! # Inputs
+    //! - `layout`: Parameter layout of the active profile.
+    //! - `table_prefix`: Literal table prefix (wildcards are escaped).
+    //! - `workspace`: `DEFINITION_WORKSPACE` value.
+    //!
+    //! # Returns
+    //! - One value per `?` in the query, in binding order:
+    //!   - `TablePrefix`: `[<escaped prefix>%]`.
+    //!   - `WorkspaceAndTablePrefix`: `[<workspace>, <escaped prefix>%]`.
+
+    let table_like_pattern = format!("{}%", escape_like_pattern(table_prefix));
+
+    match layout
+    {
+        DefinitionParamLayout::TablePrefix => vec![table_like_pattern],
+        DefinitionParamLayout::WorkspaceAndTablePrefix => vec![workspace.trim().to_string(), table_like_pattern],
+    }
+}
+
+pub fn effective_param_layout(layout: DefinitionParamLayout, workspace: &str) -> DefinitionParamLayout
+{
+    //! Resolves the parameter layout actually used for a definitions query.
+    //!
+    //! # Inputs
+    //! - `layout`: Parameter layout declared by the active profile.
+    //! - `workspace`: `DEFINITION_WORKSPACE` value.
     //!
     //! # Returns
-    //! - Path to `sql/fetch_fabric_definitions.sql`.
+    //! - `DefinitionParamLayout::TablePrefix` when `layout` binds the workspace but `workspace` is blank.
+    //! - `layout` otherwise.
+
+    match layout
+    {
+        DefinitionParamLayout::WorkspaceAndTablePrefix if workspace.trim().is_empty() => DefinitionParamLayout::TablePrefix,
+        _ => layout,
+    }
+}
+
+pub fn escape_like_pattern(prefix: &str) -> String
+{
+    //! Escapes SQL `LIKE` wildcards in a literal prefix.
+    //!
+    //! # Inputs
+    //! - `prefix`: Literal table prefix (usually the notebook name).
+    //!
+    //! # Returns
+    //! - Prefix with `\`, `_`, `%`, `[` and `]` escaped using `\` as the escape character.
+    //!
+    //! # Notes
+    //! - Queries using the result must declare `ESCAPE '\'`.
+    //! - T-SQL treats `[...]` as a character class in `LIKE`, so both brackets are escaped.
+
+    let mut escaped = String::with_capacity(prefix.len());
+    for c in prefix.chars()
+    {
+        if matches!(c, '\\' | '_' | '%' | '[' | ']')
+        {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
 
-    repo_root.join("sql").join("fetch_fabric_definitions.sql")
+    escaped
 }
 
 fn get_fabric_definition_db_credentials(config: &FabricDefinitionConfig) -> DefinitionFabricDbCredentials
@@ -133,17 +503,26 @@ fn get_fabric_definition_db_credentials(config: &FabricDefinitionConfig) -> Defi
     //! # Panics
     //! - If required secrets are missing or empty.
 
-    let fabric_sql_endpoint = get_secret_from_key_vault(
+    let fabric_sql_endpoint = get_secret_from_key_vault_on(
+        config.akv_runtime,
         config.akv_base_url,
         config.akv_secret_definition_fabric_endpoint,
+        config.akv_credential,
+        &config.akv_options,
     );
-    let fabric_service_principal_client_id = get_secret_from_key_vault(
+    let fabric_service_principal_client_id = get_secret_from_key_vault_on(
+        config.akv_runtime,
         config.akv_base_url,
         config.akv_secret_definition_fabric_client_id,
+        config.akv_credential,
+        &config.akv_options,
     );
-    let fabric_service_principal_password = get_secret_from_key_vault(
+    let fabric_service_principal_password = get_secret_from_key_vault_on(
+        config.akv_runtime,
         config.akv_base_url,
         config.akv_secret_definition_fabric_password,
+        config.akv_credential,
+        &config.akv_options,
     );
 
     if fabric_sql_endpoint.trim().is_empty()
@@ -167,94 +546,346 @@ fn get_fabric_definition_db_credentials(config: &FabricDefinitionConfig) -> Defi
     }
 }
 
+pub fn substitute_sql_params(sql: &str, params: &HashMap<String, String>) -> (String, Vec<String>)
+{
+    //! Substitutes `{{KEY}}` placeholders in a definitions query.
+    //!
+    //! # Inputs
+    //! - `sql`: Raw SQL text.
+    //! - `params`: Values supplied with `--sql-param KEY=VALUE`.
+    //!
+    //! # Returns
+    //! - `(sql, unresolved)` where placeholders without a value are left literal in `sql`
+    //!   and listed once each, in order of first appearance, in `unresolved`.
+    //!
+    //! # Notes
+    //! - This is plain text substitution, not ODBC parameter binding; values are inserted as-is.
+
+    fn is_param_name(name: &str) -> bool
+    {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    let syntax = PlaceholderSyntax { open: "{{", close: "}}", is_name: is_param_name, escape: None };
+
+    substitute_placeholders(sql, &syntax, false, |name| params.get(name).cloned())
+}
+
+pub fn is_query_timeout(error: &odbc_api::Error) -> bool
+{
+    //! Checks whether an ODBC error is a query timeout.
+    //!
+    //! # Inputs
+    //! - `error`: Error returned by statement execution.
+    //!
+    //! # Returns
+    //! - `true` when the driver reported SQLSTATE `HYT00` (timeout expired).
+
+    match error
+    {
+        odbc_api::Error::Diagnostics { record, .. } => record.state.0 == *b"HYT00",
+        _ => false,
+    }
+}
+
 pub fn fetch_definitions_from_fabric(
     table_prefix: &str,
     config: &FabricDefinitionConfig,
-) -> Result<(Vec<String>, Vec<Vec<String>>)>
+) -> Result<DefinitionTable, DoxcerError>
 {
     //! Fetches table definitions from Fabric SQL via ODBC.
     //!
     //! # Inputs
-    //! - `table_prefix`: Prefix used for SQL `LIKE` filtering.
+    //! - `table_prefix`: Literal prefix used for SQL `LIKE` filtering (wildcards are escaped).
     //! - `config`: Fabric definition runtime settings.
     //!
     //! # Returns
-    //! - `Ok((column_names, rows))` when query execution succeeds.
-    //! - `Err(...)` for ODBC connection, query, or read failures.
+    //! - See [`query_definitions`].
+    //! - `Err(DoxcerError::Io)` when the SQL file cannot be read.
     //!
     //! # Panics
-    //! - If the `LIKE` pattern contains an interior null byte.
+    //! - If a query parameter contains an interior null byte.
 
     let fabric_definition_db_credentials = get_fabric_definition_db_credentials(config);
-    let fabric_odbc_environment = Environment::new().context("[ERR] - Failed to create ODBC environment")?;
-
-    let fabric_conn_str = format!(
-        "Driver={{ODBC Driver 18 for SQL Server}};\
-        Server=tcp:{host},1433;\
-        Database={db};\
-        Encrypt=yes;\
-        TrustServerCertificate=yes;\
-        Authentication=ActiveDirectoryServicePrincipal;\
-        UID={uid};\
-        PWD={pwd};",
-        host = fabric_definition_db_credentials.fabric_sql_endpoint.trim(),
-        db = config.definition_fabric_database,
-        uid = fabric_definition_db_credentials.fabric_service_principal_client_id.trim(),
-        pwd = fabric_definition_db_credentials.fabric_service_principal_password.trim()
-    );
+    let fabric_conn_str = OdbcConnectionStringBuilder::new(
+        &fabric_definition_db_credentials.fabric_sql_endpoint,
+        config.definition_fabric_database,
+        &fabric_definition_db_credentials.fabric_service_principal_client_id,
+        &fabric_definition_db_credentials.fabric_service_principal_password,
+    )
+    .with_encrypt(config.definition_encrypt)
+    .with_trust_server_certificate(config.definition_trust_cert)
+    .build();
 
-    let fabric_odbc_connection = fabric_odbc_environment
-        .connect_with_connection_string(&fabric_conn_str, ConnectionOptions::default())
-        .context("[ERR] - ODBC connect failed")?;
+    // A workspace layout without DEFINITION_WORKSPACE falls back to the default single-prefix query.
+    let param_layout = effective_param_layout(config.param_layout, config.workspace);
+    let sql_profile_name = if param_layout == config.param_layout
+    {
+        config.profile_name
+    }
+    else
+    {
+        log_warn!(
+            "Profile '{}' binds DEFINITION_WORKSPACE, but it is empty; using the default definitions query.",
+            config.profile_name
+        );
+        ""
+    };
 
-    let fabric_sql_query = fs::read_to_string(find_fabric_sql_path(config.repo_root))
-        .context("[ERR] - Failed to read SQL file for definitions")?;
+    let fabric_sql_query = fs::read_to_string(find_fabric_sql_path(config.repo_root, sql_profile_name))
+        .map_err(|err| DoxcerError::io("Failed to read SQL file for definitions", err))?;
+    let (fabric_sql_query, unresolved_params) = substitute_sql_params(&fabric_sql_query, config.sql_params);
+    for param in &unresolved_params
+    {
+        log_warn!("SQL placeholder '{{{{{}}}}}' has no matching --sql-param and was left as-is.", param);
+    }
 
-    let fabric_table_like_pattern = format!("{}%", table_prefix);
-    let fabric_table_like_pattern_c = CString::new(fabric_table_like_pattern)
-        .expect("[ERR] - LIKE pattern contained an interior null byte");
+    let fabric_query_params = build_definition_params(param_layout, table_prefix, config.workspace);
 
-    let fabric_maybe_cursor = fabric_odbc_connection
-        .execute(&fabric_sql_query, &fabric_table_like_pattern_c, None)
-        .context("[ERR] - Query execution failed")?;
+    query_definitions(
+        &fabric_conn_str,
+        &fabric_sql_query,
+        &fabric_query_params,
+        &DefinitionQueryOptions
+        {
+            odbc_batch_size: config.odbc_batch_size,
+            odbc_max_byte_size: config.odbc_max_byte_size,
+            odbc_timeout_secs: config.odbc_timeout_secs,
+            allow_partial: config.allow_partial,
+        },
+    )
+}
 
-    let mut fabric_cursor = match fabric_maybe_cursor
+pub fn query_definitions(
+    conn_str: &str,
+    sql_query: &str,
+    params: &[String],
+    options: &DefinitionQueryOptions,
+) -> Result<DefinitionTable, DoxcerError>
+{
+    //! Runs a definitions query via ODBC and reads every row.
+    //!
+    //! # Inputs
+    //! - `conn_str`: ODBC connection string, see [`OdbcConnectionStringBuilder`].
+    //! - `sql_query`: Query text with positional `?` parameters.
+    //! - `params`: Values bound to the `?` parameters, in order.
+    //! - `options`: Fetch batch size, buffer size, timeout and partial-result setting.
+    //!
+    //! # Returns
+    //! - `Ok(DefinitionTable)` when query execution succeeds; `NULL` cells are `None`.
+    //! - `Err(DoxcerError::Odbc)` for ODBC failures, wrapping the driver error; the kind is `Connection` or
+    //!   `Authentication` while connecting, `QueryExecution` while executing and `ResultFetch` while reading rows,
+    //!   refined by the SQLSTATE (see [`OdbcErrorKind::classify`]).
+    //! - `Err(DoxcerError::Odbc)` of kind `QueryExecution` with message `Query timed out` and no source when
+    //!   `options.odbc_timeout_secs` elapses.
+    //! - With `options.allow_partial`, the rows read so far when fetching fails after at least one batch,
+    //!   see [`accumulate_definition_rows`].
+    //!
+    //! # Panics
+    //! - If a query parameter contains an interior null byte.
+    //!
+    //! # Notes
+    //! - A timeout of `0` means no timeout; `None` keeps the driver default.
+
+    let odbc_environment = Environment::new()
+        .map_err(|err| DoxcerError::odbc(OdbcErrorKind::Connection, "Failed to create ODBC environment", err))?;
+    let odbc_connection = odbc_environment
+        .connect_with_connection_string(conn_str, ConnectionOptions::default())
+        .map_err(|err| DoxcerError::odbc(OdbcErrorKind::Connection, "ODBC connect failed", err))?;
+
+    let query_params: Vec<CString> = params
+        .iter()
+        .map(|param| CString::new(param.as_str()).expect("[ERR] - Definitions query parameter contained an interior null byte"))
+        .collect();
+
+    let query_timeout_secs = options.odbc_timeout_secs.map(|secs| secs as usize);
+    let maybe_cursor = match odbc_connection.execute(sql_query, query_params.as_slice(), query_timeout_secs)
+    {
+        Ok(cursor) => cursor,
+        Err(err) if is_query_timeout(&err) =>
+        {
+            return Err(DoxcerError::Odbc
+            {
+                kind: OdbcErrorKind::QueryExecution,
+                message: "Query timed out".to_string(),
+                source: None,
+            });
+        }
+        Err(err) => return Err(DoxcerError::odbc(OdbcErrorKind::QueryExecution, "Query execution failed", err)),
+    };
+
+    let mut cursor = match maybe_cursor
     {
         Some(c) => c,
-        None => return Ok((Vec::new(), Vec::new())),
+        None => return Ok(DefinitionTable::default()),
     };
 
-    let fabric_column_names: Vec<String> = fabric_cursor
+    let fetch_error = |message: &'static str| move |err| DoxcerError::odbc(OdbcErrorKind::ResultFetch, message, err);
+
+    let column_names: Vec<String> = cursor
         .column_names()
-        .context("[ERR] - Failed to read column names")?
-        .collect::<Result<Vec<_>, _>>()?
+        .map_err(fetch_error("Failed to read column names"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(fetch_error("Failed to read column names"))?
         .into_iter()
         .map(|s| s.to_string())
         .collect();
 
-    let mut fabric_text_row_set = TextRowSet::for_cursor(
-        config.odbc_batch_size,
-        &mut fabric_cursor,
-        Some(config.odbc_max_byte_size),
-    )?;
-    let mut fabric_row_set_cursor = fabric_cursor.bind_buffer(&mut fabric_text_row_set)?;
+    let mut text_row_set = TextRowSet::for_cursor(
+        options.odbc_batch_size,
+        &mut cursor,
+        Some(options.odbc_max_byte_size),
+    )
+    .map_err(fetch_error("Failed to allocate row buffer"))?;
+    let mut row_set_cursor = cursor
+        .bind_buffer(&mut text_row_set)
+        .map_err(fetch_error("Failed to bind row buffer"))?;
 
-    let mut fabric_all_rows: Vec<Vec<String>> = Vec::new();
-    while let Some(batch) = fabric_row_set_cursor.fetch()?
+    let next_batch = ||
     {
+        let Some(batch) = row_set_cursor.fetch().map_err(fetch_error("Failed to fetch rows"))?
+        else
+        {
+            return Ok(None);
+        };
+
+        let mut rows = Vec::with_capacity(batch.num_rows());
         for row_index in 0..batch.num_rows()
         {
-            let mut fields = Vec::with_capacity(batch.num_cols());
+            let mut cells = Vec::with_capacity(batch.num_cols());
             for col_index in 0..batch.num_cols()
             {
-                let bytes = batch.at(col_index, row_index).unwrap_or(&[]);
-                fields.push(String::from_utf8_lossy(bytes).to_string());
+                let cell = batch
+                    .at(col_index, row_index)
+                    .map(|bytes| String::from_utf8_lossy(bytes).to_string());
+                cells.push(cell);
             }
-            fabric_all_rows.push(fields);
+            rows.push(DefinitionRow { cells });
+        }
+        Ok(Some(rows))
+    };
+    let all_rows = accumulate_definition_rows(next_batch, options.allow_partial)?;
+
+    Ok(DefinitionTable { columns: column_names, rows: all_rows })
+}
+
+pub fn accumulate_definition_rows<F>(mut next_batch: F, allow_partial: bool) -> Result<Vec<DefinitionRow>, DoxcerError>
+where
+    F: FnMut() -> Result<Option<Vec<DefinitionRow>>, DoxcerError>,
+{
+    //! Collects the rows of every fetched batch.
+    //!
+    //! # Inputs
+    //! - `next_batch`: Returns the next batch of rows, `None` once the cursor is exhausted.
+    //! - `allow_partial`: Whether `DEFINITION_ALLOW_PARTIAL` is set.
+    //!
+    //! # Returns
+    //! - `Ok(rows)` with all rows once `next_batch` returns `None`.
+    //! - `Ok(rows)` with the rows read so far and a `[WRN]` when a batch fails, `allow_partial` is set and at least
+    //!   one row was read.
+    //! - `Err(DoxcerError)` from `next_batch` otherwise.
+
+    let mut rows: Vec<DefinitionRow> = Vec::new();
+    loop
+    {
+        match next_batch()
+        {
+            Ok(Some(batch)) => rows.extend(batch),
+            Ok(None) => return Ok(rows),
+            Err(err) if allow_partial && !rows.is_empty() =>
+            {
+                log_warn!("Definitions fetch failed after {} row(s); using the partial result. {}", rows.len(), err);
+                return Ok(rows);
+            }
+            Err(err) => return Err(err),
         }
     }
+}
+
+pub async fn fetch_definitions_from_fabric_async(
+    table_prefix: &str,
+    config: &FabricDefinitionConfig<'_>,
+) -> Result<DefinitionTable, DoxcerError>
+{
+    //! Fetches table definitions from Fabric SQL on tokio's blocking thread pool.
+    //!
+    //! # Inputs
+    //! - `table_prefix`: Literal prefix used for SQL `LIKE` filtering (wildcards are escaped).
+    //! - `config`: Fabric definition runtime settings; copied so the query can outlive the caller's borrow.
+    //!
+    //! # Returns
+    //! - See [`fetch_definitions_from_fabric`].
+    //! - `Err(DoxcerError::Task)` when the blocking task panics or is cancelled.
+    //!
+    //! # Notes
+    //! - Lets an async caller `tokio::select!` the lookup against a timeout without blocking the executor.
+    //! - `main` drives it with `block_on` on the Key Vault runtime; the Key Vault lookups inside the task run on
+    //!   the same runtime from the blocking thread.
+
+    let table_prefix = table_prefix.to_string();
+    let repo_root = config.repo_root.to_path_buf();
+    let profile_name = config.profile_name.to_string();
+    let sql_params = config.sql_params.clone();
+    let akv_base_url = config.akv_base_url.to_string();
+    let akv_credential = config.akv_credential.to_string();
+    let akv_options = config.akv_options;
+    let akv_runtime = config.akv_runtime.clone();
+    let definition_fabric_database = config.definition_fabric_database.to_string();
+    let secret_endpoint = config.akv_secret_definition_fabric_endpoint.to_string();
+    let secret_client_id = config.akv_secret_definition_fabric_client_id.to_string();
+    let secret_password = config.akv_secret_definition_fabric_password.to_string();
+    let odbc_batch_size = config.odbc_batch_size;
+    let odbc_max_byte_size = config.odbc_max_byte_size;
+    let definition_encrypt = config.definition_encrypt;
+    let definition_trust_cert = config.definition_trust_cert;
+    let odbc_timeout_secs = config.odbc_timeout_secs;
+    let param_layout = config.param_layout;
+    let workspace = config.workspace.to_string();
+    let allow_partial = config.allow_partial;
+
+    run_blocking(move ||
+    {
+        let config = FabricDefinitionConfig
+        {
+            repo_root: &repo_root,
+            profile_name: &profile_name,
+            sql_params: &sql_params,
+            akv_base_url: &akv_base_url,
+            akv_credential: &akv_credential,
+            akv_options,
+            akv_runtime: &akv_runtime,
+            definition_fabric_database: &definition_fabric_database,
+            akv_secret_definition_fabric_endpoint: &secret_endpoint,
+            akv_secret_definition_fabric_client_id: &secret_client_id,
+            akv_secret_definition_fabric_password: &secret_password,
+            odbc_batch_size,
+            odbc_max_byte_size,
+            definition_encrypt,
+            definition_trust_cert,
+            odbc_timeout_secs,
+            param_layout,
+            workspace: &workspace,
+            allow_partial,
+        };
+        fetch_definitions_from_fabric(&table_prefix, &config)
+    })
+    .await
+}
+
+pub async fn run_blocking<T, F>(task: F) -> Result<T, DoxcerError>
+where
+    F: FnOnce() -> Result<T, DoxcerError> + Send + 'static,
+    T: Send + 'static,
+{
+    //! Runs a blocking definitions task on tokio's blocking thread pool.
+    //!
+    //! # Returns
+    //! - The task's result.
+    //! - `Err(DoxcerError::Task)` when the task panics or is cancelled.
 
-    Ok((fabric_column_names, fabric_all_rows))
+    tokio::task::spawn_blocking(task)
+        .await
+        .map_err(|err| DoxcerError::Task { context: "Definitions task did not complete".to_string(), source: err })?
 }
 
 
@@ -290,19 +921,26 @@ fn get_azure_definition_db_credentials(config: &AzureDefinitionConfig) -> Defini
     //! # Panics
     //! - If required secrets are missing or empty.
 
-    // TODO: Implement Azure SQL credentials retrieval for definitions
-
-    let azure_sql_endpoint = get_secret_from_key_vault(
+    let azure_sql_endpoint = get_secret_from_key_vault_on(
+        config.akv_runtime,
         config.akv_base_url,
         config.akv_secret_definition_azure_endpoint,
+        config.akv_credential,
+        &config.akv_options,
     );
-    let azure_service_principal_client_id = get_secret_from_key_vault(
+    let azure_service_principal_client_id = get_secret_from_key_vault_on(
+        config.akv_runtime,
         config.akv_base_url,
         config.akv_secret_definition_azure_client_id,
+        config.akv_credential,
+        &config.akv_options,
     );
-    let azure_service_principal_password = get_secret_from_key_vault(
+    let azure_service_principal_password = get_secret_from_key_vault_on(
+        config.akv_runtime,
         config.akv_base_url,
         config.akv_secret_definition_azure_password,
+        config.akv_credential,
+        &config.akv_options,
     );
 
     if azure_sql_endpoint.trim().is_empty()
@@ -327,23 +965,91 @@ fn get_azure_definition_db_credentials(config: &AzureDefinitionConfig) -> Defini
 }
 
 pub fn fetch_definitions_from_azure(
-    _table_prefix: &str,
-    _config: &AzureDefinitionConfig,
-) -> Result<(Vec<String>, Vec<Vec<String>>)>
+    table_prefix: &str,
+    config: &AzureDefinitionConfig,
+) -> Result<DefinitionTable, DoxcerError>
 {
-    //! Placeholder for Azure SQL definition retrieval.
+    //! Fetches table definitions from Azure SQL via ODBC.
     //!
     //! # Inputs
-    //! - `_table_prefix`: Prefix used for SQL `LIKE` filtering.
-    //! - `_config`: Azure definition module runtime settings.
+    //! - `table_prefix`: Literal prefix used for SQL `LIKE` filtering (wildcards are escaped).
+    //! - `config`: Azure definition runtime settings.
     //!
     //! # Returns
-    //! - `Err(...)` because this implementation is currently not available.
+    //! - See [`query_definitions`].
+    //! - `Err(DoxcerError::Io)` when the SQL file cannot be read.
+    //!
+    //! # Panics
+    //! - If required secrets are missing or empty.
+
+    let azure_definition_db_credentials = get_azure_definition_db_credentials(config);
+    let azure_conn_str = OdbcConnectionStringBuilder::new(
+        &azure_definition_db_credentials.azure_sql_endpoint,
+        config.definition_azure_database,
+        &azure_definition_db_credentials.azure_service_principal_client_id,
+        &azure_definition_db_credentials.azure_service_principal_password,
+    )
+    .with_encrypt(config.definition_encrypt)
+    .with_trust_server_certificate(config.definition_trust_cert)
+    .build();
 
-    // TODO: Implement Azure SQL fetch for a definitions table.
-    Err(anyhow::anyhow!(
-        "[ERR] - Azure SQL definitions fetch is not implemented yet."
-    ))
+    let azure_sql_query = fs::read_to_string(find_azure_sql_path(config.repo_root))
+        .map_err(|err| DoxcerError::io("Failed to read SQL file for definitions", err))?;
+
+    query_definitions(
+        &azure_conn_str,
+        &azure_sql_query,
+        &build_definition_params(DefinitionParamLayout::TablePrefix, table_prefix, ""),
+        &DefinitionQueryOptions
+        {
+            odbc_batch_size: config.odbc_batch_size,
+            odbc_max_byte_size: config.odbc_max_byte_size,
+            odbc_timeout_secs: config.odbc_timeout_secs,
+            allow_partial: config.allow_partial,
+        },
+    )
+}
+
+pub fn disambiguate_column_names(col_names: &[String]) -> Vec<String>
+{
+    //! Makes duplicate column names unique for use as Markdown headers.
+    //!
+    //! # Inputs
+    //! - `col_names`: Column names as returned by the definitions query.
+    //!
+    //! # Returns
+    //! - Column names in the same order, with repeats suffixed `_2`, `_3`, ...
+    //! - Logs a `[WRN]` for every renamed column.
+
+    let mut seen: HashSet<String> = col_names.iter().cloned().collect();
+    let mut used: HashSet<&str> = HashSet::new();
+    let mut out = Vec::with_capacity(col_names.len());
+
+    for name in col_names
+    {
+        if used.insert(name.as_str())
+        {
+            out.push(name.clone());
+            continue;
+        }
+
+        let mut suffix = 2;
+        let mut candidate = format!("{}_{}", name, suffix);
+        while seen.contains(&candidate)
+        {
+            suffix += 1;
+            candidate = format!("{}_{}", name, suffix);
+        }
+
+        log_warn!(
+            "Duplicate definition column '{}' renamed to '{}'.",
+            name, candidate
+        );
+        seen.insert(candidate.clone());
+        out.push(candidate);
+    }
+
+    out
 }
 
 pub fn format_definitions_as_markdown_table(col_names: &[String], rows: &[Vec<String>]) -> String
@@ -365,13 +1071,15 @@ pub fn format_definitions_as_markdown_table(col_names: &[String], rows: &[Vec<St
 
     fn esc(s: &str) -> String
     {
-        s.replace('|', r"\|").replace('\n', " ").replace('\r', " ")
+        s.replace('|', r"\|").replace(['\n', '\r'], " ")
     }
 
+    let col_names = disambiguate_column_names(col_names);
+
     let mut out = String::new();
 
     out.push('|');
-    for c in col_names
+    for c in &col_names
     {
         out.push(' ');
         out.push_str(&esc(c));
@@ -381,7 +1089,7 @@ pub fn format_definitions_as_markdown_table(col_names: &[String], rows: &[Vec<St
     out.push('\n');
 
     out.push('|');
-    for _ in col_names
+    for _ in &col_names
     {
         out.push_str(" --- |");
     }
@@ -17,54 +17,339 @@
 // Imports
 // ----------------------------
 
+// Standard Libraries
+use std::sync::Arc;
+use std::time::Duration;
+
 // External Libraries
-use azure_identity::DeveloperToolsCredential;
+use azure_core::credentials::{AccessToken, TokenCredential, TokenRequestOptions};
+use azure_core::error::ErrorKind;
+use azure_core::http::{ClientOptions, RetryOptions};
+use azure_identity::{DeveloperToolsCredential, ManagedIdentityCredential};
 use azure_security_keyvault_secrets::{SecretClient, SecretClientOptions};
 
+// Internal Modules
+use crate::errors::DoxcerError;
+use crate::logger::log_info;
+use crate::retry::RetryPolicy;
+
+
+// ----------------------------
+// Constants
+// ----------------------------
+
+/// Default per-attempt timeout for Key Vault secret lookups (`--akv-timeout`, `AKV_TIMEOUT_SECS`).
+pub const DEFAULT_AKV_TIMEOUT_SECS: u64 = 30;
+
 
 // ----------------------------
 // Data Structures
 // ----------------------------
 
-pub fn get_secret_from_key_vault(vault_url: &str, secret_name: &str) -> String
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KeyVaultOptions
+{
+    /// Type: Struct.
+    /// Input:
+    /// - `--akv-retry-attempts` / `AKV_RETRY_ATTEMPTS` and `--akv-timeout` / `AKV_TIMEOUT_SECS`.
+    ///
+    /// Output:
+    /// - Retry policy and per-attempt timeout for Key Vault secret lookups.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    pub retry_policy: RetryPolicy,
+    pub timeout: Duration,
+}
+
+impl Default for KeyVaultOptions
 {
-    //! Fetches and returns a trimmed secret value from Azure Key Vault.
+    fn default() -> Self
+    {
+        Self { retry_policy: RetryPolicy::default(), timeout: Duration::from_secs(DEFAULT_AKV_TIMEOUT_SECS) }
+    }
+}
+
+#[derive(Debug)]
+struct DefaultChainCredential
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Ordered credential sources built by [`build_credential`].
+    ///
+    /// Output:
+    /// - Credential that returns the first token any source can provide.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    sources: Vec<Arc<dyn TokenCredential>>,
+}
+
+
+// ----------------------------
+// Data Enumerations
+// ----------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CredentialKind
+{
+    /// Type: Enum.
+    /// Input:
+    /// - `AKV_CREDENTIAL` value from `config/azure_key_vault.env`.
+    ///
+    /// Output:
+    /// - Azure credential type used for Key Vault access.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    Developer,
+    ManagedIdentity,
+    DefaultChain,
+}
+
+
+// ----------------------------
+// Credential Helper Functions
+// ----------------------------
+
+#[async_trait::async_trait]
+impl TokenCredential for DefaultChainCredential
+{
+    async fn get_token(
+        &self,
+        scopes: &[&str],
+        options: Option<TokenRequestOptions<'_>>,
+    ) -> azure_core::Result<AccessToken>
+    {
+        //! Requests a token from each source in order and returns the first success.
+        //!
+        //! # Returns
+        //! - `Ok(AccessToken)` from the first source that authenticates.
+        //! - `Err(...)` with the last source error when every source fails.
+
+        let mut last_error = None;
+        for source in &self.sources
+        {
+            match source.get_token(scopes, options.clone()).await
+            {
+                Ok(token) => return Ok(token),
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(||
+        {
+            azure_core::Error::with_message(ErrorKind::Credential, "No credential sources configured")
+        }))
+    }
+}
+
+pub fn parse_credential_kind(value: &str) -> Result<CredentialKind, String>
+{
+    //! Parses an `AKV_CREDENTIAL` value into a credential kind.
     //!
     //! # Inputs
+    //! - `value`: Raw env value (case-insensitive, surrounding whitespace ignored).
+    //!
+    //! # Returns
+    //! - `Ok(CredentialKind::Developer)` for `developer` or an empty value.
+    //! - `Ok(CredentialKind::ManagedIdentity)` for `managed_identity`.
+    //! - `Ok(CredentialKind::DefaultChain)` for `default_chain`.
+    //! - `Err(String)` with a user-facing message for any other value.
+
+    match value.trim().to_ascii_lowercase().as_str()
+    {
+        "" | "developer" => Ok(CredentialKind::Developer),
+        "managed_identity" => Ok(CredentialKind::ManagedIdentity),
+        "default_chain" => Ok(CredentialKind::DefaultChain),
+        other => Err(format!(
+            "[ERR] - Unsupported AKV_CREDENTIAL '{}'. Supported values: developer, managed_identity, default_chain.",
+            other
+        )),
+    }
+}
+
+pub fn build_credential(kind: &str) -> Result<Arc<dyn TokenCredential>, String>
+{
+    //! Constructs the Azure credential selected by `AKV_CREDENTIAL`.
+    //!
+    //! # Inputs
+    //! - `kind`: Raw credential kind value.
+    //!
+    //! # Returns
+    //! - `Ok(credential)` for a supported kind.
+    //! - `Err(String)` when the kind is unsupported or credential creation fails.
+    //!
+    //! # Notes
+    //! - `default_chain` tries managed identity first, then developer tools; a credential that cannot be
+    //!   constructed is skipped, see [`chain_sources`].
+
+    let developer = || -> Result<Arc<dyn TokenCredential>, String>
+    {
+        let credential = DeveloperToolsCredential::new(None)
+            .map_err(|err| format!("[ERR] - Failed to create DeveloperToolsCredential: {}", err))?;
+        Ok(credential)
+    };
+    let managed_identity = || -> Result<Arc<dyn TokenCredential>, String>
+    {
+        let credential = ManagedIdentityCredential::new(None)
+            .map_err(|err| format!("[ERR] - Failed to create ManagedIdentityCredential: {}", err))?;
+        Ok(credential)
+    };
+
+    match parse_credential_kind(kind)?
+    {
+        CredentialKind::Developer => developer(),
+        CredentialKind::ManagedIdentity => managed_identity(),
+        CredentialKind::DefaultChain => Ok(Arc::new(DefaultChainCredential
+        {
+            sources: chain_sources(vec![managed_identity(), developer()])?,
+        })),
+    }
+}
+
+pub fn chain_sources<T>(candidates: Vec<Result<T, String>>) -> Result<Vec<T>, String>
+{
+    //! Keeps the credentials of a `default_chain` that could be constructed.
+    //!
+    //! # Inputs
+    //! - `candidates`: Construction results in chain order.
+    //!
+    //! # Returns
+    //! - `Ok(sources)` with every constructed credential, in order; failed ones are logged with `[INF]` and skipped.
+    //! - `Err(String)` with all construction errors when none could be constructed.
+
+    let mut sources = Vec::new();
+    let mut errors = Vec::new();
+    for candidate in candidates
+    {
+        match candidate
+        {
+            Ok(source) => sources.push(source),
+            Err(err) =>
+            {
+                log_info!("Skipping credential in default_chain: {}", err.trim_start_matches("[ERR] - "));
+                errors.push(err);
+            }
+        }
+    }
+
+    if sources.is_empty()
+    {
+        return Err(errors.join(" "));
+    }
+    Ok(sources)
+}
+
+
+// ----------------------------
+// Key Vault Functions
+// ----------------------------
+
+pub fn get_secret_from_key_vault_on(
+    handle: &tokio::runtime::Handle,
+    vault_url: &str,
+    secret_name: &str,
+    credential_kind: &str,
+    options: &KeyVaultOptions,
+) -> String
+{
+    //! Fetches and returns a trimmed secret value from Azure Key Vault on an existing Tokio runtime.
+    //!
+    //! # Inputs
+    //! - `handle`: Handle of the runtime that drives the Key Vault request.
     //! - `vault_url`: Azure Key Vault base URL.
     //! - `secret_name`: Secret name to retrieve.
+    //! - `credential_kind`: `AKV_CREDENTIAL` value selecting the Azure credential.
+    //! - `options`: Retries for failed secret lookups and the timeout of each attempt, see [`KeyVaultOptions`].
     //!
     //! # Returns
     //! - Trimmed secret value.
     //!
     //! # Panics
-    //! - If Tokio runtime creation fails.
+    //! - If the credential kind is unsupported.
     //! - If Azure credential/client creation fails.
-    //! - If secret retrieval or model parsing fails.
+    //! - If secret retrieval or model parsing still fails after the last attempt.
     //! - If the retrieved secret has no value.
+    //! - If called from within an async context of the same runtime.
+    //!
+    //! # Notes
+    //! - Replaces `get_secret_from_key_vault`, which built a Tokio runtime per secret; callers pass the handle
+    //!   of one shared runtime instead.
 
-    let rt = tokio::runtime::Runtime::new()
-        .expect("[ERR] - Failed to create Tokio runtime");
+    try_get_secret_from_key_vault_on(handle, vault_url, secret_name, credential_kind, options)
+        .unwrap_or_else(|err| panic!("{}", err))
+}
 
-    rt.block_on(
-        async
-        {
-            let credential = DeveloperToolsCredential::new(None)
-                .expect("[ERR] - Failed to create DeveloperToolsCredential");
-            let client = SecretClient::new(
-                vault_url,
-                credential.clone(),
-                None::<SecretClientOptions>
-            ).expect("[ERR] - Failed to create SecretClient");
-            
-            let secret = client
-                .get_secret(secret_name, None)
-                .await
-                .expect("[ERR] - Failed to fetch secret")
-                .into_model()
-                .expect("[ERR] - Failed to deserialize secret model");
-
-            secret.value.expect("[WRN] - Secret has no value").trim().to_string()
-        }
-    )
+pub fn try_get_secret_from_key_vault_on(
+    handle: &tokio::runtime::Handle,
+    vault_url: &str,
+    secret_name: &str,
+    credential_kind: &str,
+    options: &KeyVaultOptions,
+) -> Result<String, DoxcerError>
+{
+    //! Fetches a trimmed secret value from Azure Key Vault, returning failures instead of panicking.
+    //!
+    //! # Inputs
+    //! - Same as [`get_secret_from_key_vault_on`].
+    //!
+    //! # Returns
+    //! - `Ok(String)` with the trimmed secret value.
+    //! - `Err(DoxcerError::KeyVault)` when the credential or client cannot be created, the lookup still fails
+    //!   after the last attempt, the secret model cannot be read or the secret has no value; Azure errors
+    //!   are available through `source()`.
+    //!
+    //! # Panics
+    //! - If called from within an async context of the same runtime.
+    //! - If the runtime was built without the time driver.
+    //!
+    //! # Notes
+    //! - An attempt that exceeds `options.timeout` fails and is retried like any other failed lookup.
+    //! - The SDK's own retries are disabled, so each attempt sends one request.
+
+    let key_vault_error = |context: &str, source: Option<azure_core::Error>|
+    {
+        DoxcerError::KeyVault { context: context.to_string(), source }
+    };
+
+    let credential = build_credential(credential_kind)
+        .map_err(|err| key_vault_error(err.trim_start_matches("[ERR] - "), None))?;
+    // The SDK client options have no per-request timeout, so retries and the timeout are applied by the
+    // wrapper below; SDK retries are turned off so `--akv-retry-attempts` is the real number of attempts.
+    let client_options = SecretClientOptions
+    {
+        client_options: ClientOptions { retry: RetryOptions::none(), ..ClientOptions::default() },
+        ..SecretClientOptions::default()
+    };
+    let client = SecretClient::new(
+        vault_url,
+        credential,
+        Some(client_options)
+    ).map_err(|err| key_vault_error("Failed to create SecretClient", Some(err)))?;
+
+    let lookup_with_timeout = ||
+    {
+        handle
+            .block_on(tokio::time::timeout(options.timeout, client.get_secret(secret_name, None)))
+            .unwrap_or_else(|_| Err(azure_core::Error::with_message(
+                ErrorKind::Io,
+                format!("Key Vault request timed out after {}s", options.timeout.as_secs()),
+            )))
+    };
+
+    let secret = options
+        .retry_policy
+        .run("Key Vault secret lookup", lookup_with_timeout, Result::is_err)
+        .map_err(|err| key_vault_error("Failed to fetch secret", Some(err)))?
+        .into_model()
+        .map_err(|err| key_vault_error("Failed to deserialize secret model", Some(err)))?;
+
+    secret
+        .value
+        .map(|value| value.trim().to_string())
+        .ok_or_else(|| key_vault_error(&format!("Secret '{}' has no value", secret_name), None))
 }
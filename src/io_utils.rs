@@ -0,0 +1,425 @@
+//////////////////////////////////////////////////////////
+// AUTHOR   : Stefan B. J. Meeuwessen
+// CREATION : 2026-10-16
+// VERSION  : 0.0.1
+//////////////////////////////////////////////////////////
+
+
+// ----------------------------
+// Compiler Directives
+// ----------------------------
+
+// #![allow(unused)]
+// #![allow(unused_doc_comments)]
+
+
+// ----------------------------
+// Imports
+// ----------------------------
+
+// Standard Libraries
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+
+// ----------------------------
+// Data Structures
+// ----------------------------
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OutputEncoding
+{
+    /// Type: Enum.
+    /// Input:
+    /// - `--output-encoding` value, see [`parse_output_encoding`].
+    ///
+    /// Output:
+    /// - Byte encoding of the saved Markdown file.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    #[default]
+    Utf8,
+    Utf16Le,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PlaceholderSyntax
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Delimiters and name rule of one placeholder style, e.g. `{name}`, `${NAME}` or `{{KEY}}`.
+    ///
+    /// Output:
+    /// - Settings used by [`substitute_placeholders`].
+    ///
+    /// Exceptions:
+    /// - None.
+
+    pub open: &'static str,
+    pub close: &'static str,
+    pub is_name: fn(&str) -> bool,
+    pub escape: Option<PlaceholderEscape>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PlaceholderEscape
+{
+    /// Type: Enum.
+    /// Input:
+    /// - A `$` directly before the opening delimiter.
+    ///
+    /// Output:
+    /// - How [`substitute_placeholders`] treats that placeholder.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    // Left literal, `$` included (`${NAME}` in a `{name}` template)
+    Keep,
+    // Left literal without the `$` (`$${NAME}` renders as `${NAME}`)
+    Drop,
+}
+
+
+// ----------------------------
+// Text Helper Functions
+// ----------------------------
+
+pub fn truncate_at_newline(s: &str, max_chars: usize) -> &str
+{
+    //! Truncates text to at most `max_chars` characters, preferring a newline boundary.
+    //!
+    //! # Inputs
+    //! - `s`: Text to truncate.
+    //! - `max_chars`: Maximum number of characters (not bytes) to keep.
+    //!
+    //! # Returns
+    //! - `s` unchanged when it already fits.
+    //! - The prefix up to and including the last newline within the limit.
+    //! - A hard cut at `max_chars` characters when no newline fits within the limit.
+
+    let Some((cut_index, _)) = s.char_indices().nth(max_chars)
+    else
+    {
+        return s;
+    };
+
+    let prefix = &s[..cut_index];
+    match prefix.rfind('\n')
+    {
+        Some(newline_index) => &s[..=newline_index],
+        None => prefix,
+    }
+}
+
+pub fn truncate_for_echo(content: &str, limit: usize) -> String
+{
+    //! Shortens text printed to the terminal (`--echo-limit`).
+    //!
+    //! # Inputs
+    //! - `content`: Text to print.
+    //! - `limit`: Maximum number of characters (not bytes) to keep.
+    //!
+    //! # Returns
+    //! - `content` unchanged when it has at most `limit` characters.
+    //! - The first `limit` characters followed by `…` otherwise.
+
+    match content.char_indices().nth(limit)
+    {
+        Some((cut_index, _)) => format!("{}…", &content[..cut_index]),
+        None => content.to_string(),
+    }
+}
+
+pub fn substitute_placeholders<F>(
+    content: &str,
+    syntax: &PlaceholderSyntax,
+    blank_unresolved: bool,
+    resolve: F,
+) -> (String, Vec<String>)
+where
+    F: Fn(&str) -> Option<String>,
+{
+    //! Substitutes placeholders of one syntax in template text.
+    //!
+    //! # Inputs
+    //! - `content`: Raw template text.
+    //! - `syntax`: Delimiters, name rule and `$` escape of the placeholders.
+    //! - `blank_unresolved`: Replace placeholders without a value with an empty string instead of leaving them literal.
+    //! - `resolve`: Value lookup for a placeholder name.
+    //!
+    //! # Returns
+    //! - `(rendered, unresolved)` where `unresolved` lists each name without a value once,
+    //!   in order of first appearance.
+    //! - Delimited text that fails `syntax.is_name` and unbalanced delimiters are left literal.
+
+    let mut rendered = String::with_capacity(content.len());
+    let mut unresolved: Vec<String> = Vec::new();
+    let mut rest = content;
+
+    while let Some(open) = rest.find(syntax.open)
+    {
+        rendered.push_str(&rest[..open]);
+        let after_open = &rest[open + syntax.open.len()..];
+
+        // Checked on the source, so a substituted value ending in `$` does not escape the next placeholder.
+        let escaped = content[..content.len() - rest.len() + open].ends_with('$');
+        if escaped && let Some(escape) = syntax.escape
+        {
+            if escape == PlaceholderEscape::Drop
+            {
+                rendered.pop();
+            }
+            rendered.push_str(syntax.open);
+            rest = after_open;
+            continue;
+        }
+
+        let Some(close) = after_open.find(syntax.close)
+        else
+        {
+            rendered.push_str(&rest[open..]);
+            rest = "";
+            break;
+        };
+
+        let name = &after_open[..close];
+        if !(syntax.is_name)(name)
+        {
+            // Only the first character moves on, so `{{filename}}` still renders the inner `{filename}`.
+            let first_len = syntax.open.chars().next().map_or(1, char::len_utf8);
+            rendered.push_str(&rest[open..open + first_len]);
+            rest = &rest[open + first_len..];
+            continue;
+        }
+
+        let placeholder_end = syntax.open.len() + close + syntax.close.len();
+        match resolve(name)
+        {
+            Some(value) => rendered.push_str(&value),
+            None =>
+            {
+                if !blank_unresolved
+                {
+                    rendered.push_str(&rest[open..open + placeholder_end]);
+                }
+                if !unresolved.iter().any(|existing| existing == name)
+                {
+                    unresolved.push(name.to_string());
+                }
+            }
+        }
+        rest = &rest[open + placeholder_end..];
+    }
+    rendered.push_str(rest);
+
+    (rendered, unresolved)
+}
+
+pub fn line_diff(old: &str, new: &str) -> String
+{
+    //! Builds a line diff between two texts.
+    //!
+    //! # Inputs
+    //! - `old`: Previous text.
+    //! - `new`: Current text.
+    //!
+    //! # Returns
+    //! - One line per changed line: `- ` for removed lines and `+ ` for added lines, in document order.
+    //! - An empty string when both texts have the same lines.
+    //!
+    //! # Notes
+    //! - Based on the longest common subsequence of lines; unchanged lines are left out.
+
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = length of the longest common subsequence of old_lines[i..] and new_lines[j..].
+    let mut lcs = vec![vec![0usize; new_lines.len() + 1]; old_lines.len() + 1];
+    for i in (0..old_lines.len()).rev()
+    {
+        for j in (0..new_lines.len()).rev()
+        {
+            lcs[i][j] = if old_lines[i] == new_lines[j]
+            {
+                lcs[i + 1][j + 1] + 1
+            }
+            else
+            {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old_lines.len() || j < new_lines.len()
+    {
+        if i < old_lines.len() && j < new_lines.len() && old_lines[i] == new_lines[j]
+        {
+            i += 1;
+            j += 1;
+        }
+        else if i < old_lines.len() && (j == new_lines.len() || lcs[i + 1][j] >= lcs[i][j + 1])
+        {
+            diff.push_str(&format!("- {}\n", old_lines[i]));
+            i += 1;
+        }
+        else
+        {
+            diff.push_str(&format!("+ {}\n", new_lines[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+
+// ----------------------------
+// Encoding Helper Functions
+// ----------------------------
+
+pub fn parse_output_encoding(value: &str) -> Option<OutputEncoding>
+{
+    //! Parses an `--output-encoding` value (case-insensitive).
+    //!
+    //! # Returns
+    //! - `Some(OutputEncoding)` for `utf8`/`utf-8` or `utf16le`/`utf-16le`.
+    //! - `None` for any other value.
+
+    match value.trim().to_ascii_lowercase().as_str()
+    {
+        "utf8" | "utf-8" => Some(OutputEncoding::Utf8),
+        "utf16le" | "utf-16le" => Some(OutputEncoding::Utf16Le),
+        _ => None,
+    }
+}
+
+pub fn encode_output(content: &str, encoding: OutputEncoding) -> Vec<u8>
+{
+    //! Encodes the documentation for writing.
+    //!
+    //! # Returns
+    //! - The UTF-8 bytes for `Utf8`.
+    //! - A UTF-16 LE byte order mark (`FF FE`) followed by the UTF-16 LE code units for `Utf16Le`.
+
+    match encoding
+    {
+        OutputEncoding::Utf8 => content.as_bytes().to_vec(),
+        OutputEncoding::Utf16Le =>
+        {
+            let mut bytes = vec![0xFF, 0xFE];
+            bytes.extend(content.encode_utf16().flat_map(u16::to_le_bytes));
+            bytes
+        }
+    }
+}
+
+
+// ----------------------------
+// File Helper Functions
+// ----------------------------
+
+pub fn atomic_write(path: &Path, content: &str) -> io::Result<()>
+{
+    //! Writes text atomically, see [`atomic_write_bytes`].
+
+    atomic_write_bytes(path, content.as_bytes())
+}
+
+pub fn atomic_write_bytes(path: &Path, content: &[u8]) -> io::Result<()>
+{
+    //! Writes a file atomically via a temp file in the same directory and a rename.
+    //!
+    //! # Inputs
+    //! - `path`: Destination file.
+    //! - `content`: Full file content.
+    //!
+    //! # Returns
+    //! - `Ok(())` once the content is flushed to disk and renamed into place.
+    //! - `Err(io::Error)` when writing or renaming fails; the temp file is removed and `path` is untouched.
+    //!
+    //! # Notes
+    //! - A crash leaves either the previous file or the complete new one, never a truncated file.
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "atomic_write path has no file name"))?;
+    let temp_path = path.with_file_name(format!(".{}.tmp-{}", file_name.to_string_lossy(), std::process::id()));
+
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut file|
+        {
+            file.write_all(content)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+
+    if result.is_err()
+    {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+pub fn append_line(path: &Path, line: &str) -> io::Result<()>
+{
+    //! Appends one line to a text file, creating the file when it does not exist.
+    //!
+    //! # Inputs
+    //! - `path`: File to append to.
+    //! - `line`: Line to append, without a trailing newline.
+    //!
+    //! # Returns
+    //! - `Ok(())` once the line is written.
+    //! - `Err(io::Error)` when the file cannot be read, created or written.
+    //!
+    //! # Notes
+    //! - A newline is inserted first when the existing file does not end with one.
+
+    let needs_separator = match fs::read(path)
+    {
+        Ok(existing) => existing.last().is_some_and(|byte| *byte != b'\n'),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => false,
+        Err(err) => return Err(err),
+    };
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if needs_separator
+    {
+        file.write_all(b"\n")?;
+    }
+    file.write_all(format!("{}\n", line).as_bytes())
+}
+
+pub fn append_changelog_entry(path: &Path, entry: &str) -> io::Result<()>
+{
+    //! Appends an entry to a changelog file unless the same entry is already present.
+    //!
+    //! # Inputs
+    //! - `path`: Changelog file; created when it does not exist.
+    //! - `entry`: Changelog line, e.g. `- 2026-10-16 regenerated sales_load (python)`.
+    //!
+    //! # Returns
+    //! - `Ok(())` when the entry was appended or was already present.
+    //! - `Err(io::Error)` when the file cannot be read or written.
+    //!
+    //! # Notes
+    //! - Entries carry their date, so regenerating the same doc twice on one day adds a single line.
+
+    let already_present = match fs::read_to_string(path)
+    {
+        Ok(existing) => existing.lines().any(|line| line.trim_end() == entry.trim_end()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => false,
+        Err(err) => return Err(err),
+    };
+
+    if already_present
+    {
+        return Ok(());
+    }
+    append_line(path, entry)
+}
@@ -0,0 +1,210 @@
+//////////////////////////////////////////////////////////
+// AUTHOR   : Stefan B. J. Meeuwessen
+// CREATION : 2026-10-16
+// VERSION  : 0.0.1
+//////////////////////////////////////////////////////////
+
+
+// ----------------------------
+// Compiler Directives
+// ----------------------------
+
+// #![allow(unused)]
+// #![allow(unused_doc_comments)]
+
+
+// ----------------------------
+// Imports
+// ----------------------------
+
+// Standard Libraries
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+// External Libraries
+use indicatif::ProgressBar;
+
+
+// ----------------------------
+// Data Structures
+// ----------------------------
+
+pub struct Logger
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Log messages emitted during a run.
+    ///
+    /// Output:
+    /// - Prefixed log lines and a running warning count; lines are printed above the active progress bar.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    warnings: AtomicUsize,
+    info_to_stderr: AtomicBool,
+    progress_bar: Mutex<Option<ProgressBar>>,
+}
+
+
+// ----------------------------
+// Global Logger
+// ----------------------------
+
+pub static LOGGER: Logger = Logger::new();
+
+macro_rules! log_warn
+{
+    ($($arg:tt)*) =>
+    {
+        $crate::logger::LOGGER.warn(format_args!($($arg)*))
+    };
+}
+pub(crate) use log_warn;
+
+macro_rules! log_info
+{
+    ($($arg:tt)*) =>
+    {
+        $crate::logger::LOGGER.info(format_args!($($arg)*))
+    };
+}
+pub(crate) use log_info;
+
+macro_rules! log_success
+{
+    ($($arg:tt)*) =>
+    {
+        $crate::logger::LOGGER.success(format_args!($($arg)*))
+    };
+}
+pub(crate) use log_success;
+
+macro_rules! log_error
+{
+    ($($arg:tt)*) =>
+    {
+        $crate::logger::LOGGER.error(format_args!($($arg)*))
+    };
+}
+pub(crate) use log_error;
+
+
+// ----------------------------
+// Logger Functions
+// ----------------------------
+
+impl Logger
+{
+    pub const fn new() -> Self
+    {
+        //! Creates a logger with no warnings recorded.
+
+        Logger
+        {
+            warnings: AtomicUsize::new(0),
+            info_to_stderr: AtomicBool::new(false),
+            progress_bar: Mutex::new(None),
+        }
+    }
+
+    pub fn set_progress_bar(&self, progress_bar: Option<ProgressBar>)
+    {
+        //! Registers the active progress bar (`--progress`), or clears it with `None`.
+        //!
+        //! # Notes
+        //! - While a bar is registered, every log line is printed through [`ProgressBar::suspend`], so the
+        //!   bar is redrawn below the line instead of being overwritten.
+
+        *self.progress_bar.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = progress_bar;
+    }
+
+    pub fn set_info_to_stderr(&self, enabled: bool)
+    {
+        //! Sends `[INF]` and `[SUC]` lines to stderr, keeping stdout for command output (e.g. `--definitions-only`).
+
+        self.info_to_stderr.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn info(&self, message: fmt::Arguments<'_>)
+    {
+        //! Prints an `[INF]` line to stdout, or to stderr after [`Logger::set_info_to_stderr`].
+
+        self.emit("INF", message);
+    }
+
+    pub fn success(&self, message: fmt::Arguments<'_>)
+    {
+        //! Prints a `[SUC]` line to stdout, or to stderr after [`Logger::set_info_to_stderr`].
+
+        self.emit("SUC", message);
+    }
+
+    pub fn warn(&self, message: fmt::Arguments<'_>)
+    {
+        //! Prints a `[WRN]` line to stderr and counts it.
+        //!
+        //! # Inputs
+        //! - `message`: Warning text without the `[WRN] - ` prefix.
+
+        self.warnings.fetch_add(1, Ordering::Relaxed);
+        self.write_line(true, format_args!("[WRN] - {}", message));
+    }
+
+    pub fn error(&self, message: fmt::Arguments<'_>)
+    {
+        //! Prints an error line to stderr as-is.
+        //!
+        //! # Notes
+        //! - Errors are built with their own `[ERR] - ` prefix, so none is added here.
+
+        self.write_line(true, message);
+    }
+
+    pub fn suspend<R>(&self, output: impl FnOnce() -> R) -> R
+    {
+        //! Runs `output` with the active progress bar hidden, for command output printed outside the log macros.
+
+        let progress_bar = self.progress_bar.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match progress_bar.as_ref()
+        {
+            Some(progress_bar) => progress_bar.suspend(output),
+            None => output(),
+        }
+    }
+
+    pub fn warning_count(&self) -> usize
+    {
+        //! Returns the number of warnings emitted so far.
+
+        self.warnings.load(Ordering::Relaxed)
+    }
+
+    fn emit(&self, level: &str, message: fmt::Arguments<'_>)
+    {
+        //! Prints one `[<level>] - <message>` line to the stream selected for informational output.
+
+        let to_stderr = self.info_to_stderr.load(Ordering::Relaxed);
+        self.write_line(to_stderr, format_args!("[{}] - {}", level, message));
+    }
+
+    fn write_line(&self, to_stderr: bool, line: fmt::Arguments<'_>)
+    {
+        //! Prints one line to stdout or stderr, suspending the active progress bar while it is written.
+
+        let print = ||
+        {
+            if to_stderr
+            {
+                eprintln!("{}", line);
+            }
+            else
+            {
+                println!("{}", line);
+            }
+        };
+
+        self.suspend(print);
+    }
+}
@@ -27,26 +27,49 @@
 // ----------------------------
 
 // Standard Libraries
+use std::collections::HashMap;
 use std::env;
 use std::fs;
+use std::io::Write;
+use std::panic;
 use std::path::{Path, PathBuf};
-use std::process;
+use std::process::{self, Command, Stdio};
+use std::str::FromStr;
+use std::thread;
 use std::time::{Duration, Instant};
 
 // External Libraries
 use chrono::Utc;
 use chrono_tz::Europe::Amsterdam;
-use dotenvy;
-use fetch_definitions::FabricDefinitionConfig;
+use errors::{DoxcerError, ParseError};
+use fetch_definitions::{AzureDefinitionConfig, DefinitionParamLayout, FabricDefinitionConfig, OdbcEncrypt};
+use fetch_secrets::KeyVaultOptions;
+use logger::{log_error, log_info, log_success, log_warn, LOGGER};
+use notebook::{Notebook, NotebookCleaner};
+use prompt_builder::PromptBuilder;
+use regex::Regex;
 use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use retry::RetryPolicy;
 use serde::{Deserialize, Serialize};
 use once_cell::sync::Lazy;
 
 // Unit Tests
 #[cfg(test)]
 mod unit_tests;
+mod batch;
+mod errors;
 mod fetch_definitions;
 mod fetch_secrets;
+mod io_utils;
+mod logger;
+mod notebook;
+mod notebook_analysis;
+mod notebook_formats;
+mod output_validation;
+mod prompt_builder;
+mod publish;
+mod retry;
 
 
 // ----------------------------
@@ -59,13 +82,17 @@ struct ChatRequest
     /// Type: Struct.
     /// Input:
     /// - Values assigned by caller before serialization.
+    ///
     /// Output:
     /// - JSON payload for chat completion requests.
+    ///
     /// Exceptions:
     /// - None.
 
     model: String,
     messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
 }
 
 #[derive(Serialize)]
@@ -74,8 +101,10 @@ struct Message
     /// Type: Struct.
     /// Input:
     /// - Values assigned by caller before serialization.
+    ///
     /// Output:
     /// - JSON message object in `ChatRequest`.
+    ///
     /// Exceptions:
     /// - None.
 
@@ -89,8 +118,10 @@ struct ChatResponse
     /// Type: Struct.
     /// Input:
     /// - JSON response payload from Azure OpenAI.
+    ///
     /// Output:
     /// - Deserialized response subset used by this application.
+    ///
     /// Exceptions:
     /// - None.
 
@@ -103,8 +134,10 @@ struct Choice
     /// Type: Struct.
     /// Input:
     /// - JSON `choices[]` entry from API response.
+    ///
     /// Output:
     /// - Deserialized choice containing one message.
+    ///
     /// Exceptions:
     /// - None.
 
@@ -117,8 +150,10 @@ struct ChoiceMessage
     /// Type: Struct.
     /// Input:
     /// - JSON message object from API response.
+    ///
     /// Output:
     /// - Deserialized assistant content text.
+    ///
     /// Exceptions:
     /// - None.
 
@@ -126,28 +161,145 @@ struct ChoiceMessage
     content: String,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Deserialize, Debug)]
+struct ApiError
+{
+    /// Type: Struct.
+    /// Input:
+    /// - JSON error payload from the API (`{"error": {...}}`).
+    ///
+    /// Output:
+    /// - Deserialized error details.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    error: ApiErrorDetail,
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiErrorDetail
+{
+    /// Type: Struct.
+    /// Input:
+    /// - JSON `error` object from API error payload.
+    ///
+    /// Output:
+    /// - API error code and message.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    code: Option<serde_json::Value>,
+    message: String,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
 struct CliArgs
 {
     /// Type: Struct.
     /// Input:
     /// - Parsed CLI tokens.
+    ///
     /// Output:
     /// - Runtime CLI argument object.
+    ///
     /// Exceptions:
     /// - None.
 
+    command: CliCommand,
     file_path: String,
     profile: PromptProfile,
+    profile_selected: bool,
+    no_template_fallback: bool,
+    notebook_name: Option<String>,
+    format_cmd: Option<String>,
+    truncate_definitions: Option<usize>,
+    stats: bool,
+    env_files: Vec<String>,
+    strict_env: bool,
+    profile_template_dir: Option<String>,
+    sql_params: HashMap<String, String>,
+    auto_profile: bool,
+    compare: Option<String>,
+    retry_failures: bool,
+    section: Option<String>,
+    references: Vec<String>,
+    fail_on_warning: bool,
+    context_format: ContextFormat,
+    explain: bool,
+    api_version: Option<String>,
+    expect_json_schema: Option<String>,
+    model: Option<String>,
+    lang: Option<String>,
+    retry_attempts: Option<u32>,
+    retry_delay_ms: Option<u64>,
+    akv_retry_attempts: Option<u32>,
+    akv_timeout_secs: Option<u64>,
+    allow_prod: bool,
+    rate_limit_delay_ms: Option<u64>,
+    max_input_bytes: Option<u64>,
+    definition_timeout_secs: Option<u64>,
+    progress: bool,
+    stdout_only: bool,
+    mock_response: Option<String>,
+    filter_cells: Option<String>,
+    frontmatter: bool,
+    frontmatter_fields: Vec<(String, String)>,
+    diff_against_head: bool,
+    exclude_nulls: Option<String>,
+    per_cell: bool,
+    trim_trailing_blanks: bool,
+    preserve_line_endings: bool,
+    strict: bool,
+    batch_order: Option<batch::BatchOrder>,
+    adf_json: bool,
+    context_version: Option<String>,
+    changelog: Option<String>,
+    force: bool,
+    include_imports: bool,
+    definition_format_template: Option<String>,
+    seed: Option<u64>,
+    odbc_encrypt: Option<OdbcEncrypt>,
+    echo_limit: Option<usize>,
+    odbc_trust_cert: Option<bool>,
+    include_source: bool,
+    template_version_comment: bool,
+    output_encoding: io_utils::OutputEncoding,
 }
 
-struct EnvParameters
+#[derive(Debug, Default)]
+struct RunStats
 {
     /// Type: Struct.
     /// Input:
-    /// - Environment variables loaded from split env files.
+    /// - Measurements collected while generating documentation.
+    ///
+    /// Output:
+    /// - Post-run statistics printed with `--stats`.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    input_bytes: u64,
+    cleaned_lines: usize,
+    estimated_prompt_tokens: usize,
+    definition_rows: usize,
+    api_response_ms: Option<u128>,
+    output_bytes: Option<u64>,
+    model: String,
+}
+
+#[derive(Serialize)]
+struct AiEnvParameters
+{
+    /// Type: Struct.
+    /// Input:
+    /// - AI model environment variables loaded from split env files.
+    ///
     /// Output:
-    /// - Strongly-typed runtime configuration.
+    /// - Configuration needed only when the AI endpoint is called; the Key Vault secret name is masked when serialized.
+    ///
     /// Exceptions:
     /// - None.
 
@@ -157,41 +309,104 @@ struct EnvParameters
     ai_model: String,
     ai_version: String,
     ai_task: String,
+    ai_system_role: String,
+    ai_user_role: String,
+    ai_extra_headers: String,
+
+    // Azure Key Vault
+    #[serde(serialize_with = "serialize_secret")]
+    akv_secret_ai: String,
+}
+
+#[derive(Default, Serialize)]
+struct EnvParameters
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Environment variables loaded from split env files.
+    ///
+    /// Output:
+    /// - Strongly-typed runtime configuration shared by every mode; Key Vault secret names are masked when serialized.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    // Output validation
+    min_output_chars: usize,
+
+    // Wiki publishing
+    publish_enabled: bool,
+    wiki_api_url: String,
+    #[serde(serialize_with = "serialize_secret")]
+    akv_secret_wiki: String,
 
     // Azure Key Vault
     akv_enabled: bool,
     akv_base_url: String,
-    akv_secret_ai: String,
+    akv_credential: String,
 
     // Definition DB
     definition_database_enabled: bool,
+    definition_environment: String,
     
     // Definition DB Fabric
     definition_fabric_database_enabled: bool,
     definition_fabric_database: String,
+    #[serde(serialize_with = "serialize_secret")]
     akv_secret_definition_fabric_endpoint: String,
+    #[serde(serialize_with = "serialize_secret")]
     akv_secret_definition_fabric_client_id: String,
+    #[serde(serialize_with = "serialize_secret")]
     akv_secret_definition_fabric_password: String,
 
     // Definition DB Azure
     definition_azure_database_enabled: bool,
     definition_azure_database: String,
+    #[serde(serialize_with = "serialize_secret")]
     akv_secret_definition_azure_endpoint: String,
+    #[serde(serialize_with = "serialize_secret")]
     akv_secret_definition_azure_client_id: String,
+    #[serde(serialize_with = "serialize_secret")]
     akv_secret_definition_azure_password: String,
 
     // ODBC
     odbc_batch_size: usize,
     odbc_max_byte_size: usize,
+    definition_encrypt: bool,
+    definition_trust_cert: bool,
+}
+
+#[derive(Serialize)]
+struct EffectiveConfig<'a>
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Resolved repo root, loaded env files, CLI overrides and `ENVCONFIG`.
+    ///
+    /// Output:
+    /// - TOML document printed by `--show-config`.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    absolute_doxcer_path: String,
+    env_files: Vec<String>,
+    model: String,
+    api_version: String,
+    ai: &'a AiEnvParameters,
+    env: &'a EnvParameters,
 }
 
+#[non_exhaustive]
 struct PromptProfileSpec
 {
     /// Type: Struct.
     /// Input:
     /// - Compile-time profile metadata values.
+    ///
     /// Output:
     /// - Single source of truth for profile names, selectors, and template stems.
+    ///
     /// Exceptions:
     /// - None.
 
@@ -199,6 +414,101 @@ struct PromptProfileSpec
     name: &'static str,
     selector_flags: &'static [&'static str],
     template_stem: &'static str,
+    base_profile: Option<PromptProfile>,
+    definition_params: DefinitionParamLayout,
+}
+
+impl PromptProfileSpec
+{
+    /// Values for fields an entry in [`PROMPT_PROFILE_SPECS`] does not set (`..PromptProfileSpec::UNSET`),
+    /// so adding a field does not require touching every entry.
+    const UNSET: PromptProfileSpec = PromptProfileSpec
+    {
+        profile: PromptProfile::Default,
+        name: "",
+        selector_flags: &[],
+        template_stem: "",
+        base_profile: None,
+        definition_params: DefinitionParamLayout::TablePrefix,
+    };
+}
+
+#[derive(Debug, Default, Serialize)]
+struct RunTrace
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Decisions recorded while generating documentation.
+    ///
+    /// Output:
+    /// - Pipeline trace printed with `--explain`.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    profile: String,
+    template: String,
+    definitions: String,
+    prompt_chars: usize,
+    model: String,
+    response_chars: Option<usize>,
+    context_version: Option<String>,
+    template_version: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct CollapseConfig
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Blank line rules for cleaned notebook source.
+    ///
+    /// Output:
+    /// - Settings used by `collapse_blank_lines`.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    max_consecutive: usize,
+    trim_trailing: bool,
+    preserve_line_endings: bool,
+}
+
+impl Default for CollapseConfig
+{
+    fn default() -> Self
+    {
+        Self { max_consecutive: 1, trim_trailing: false, preserve_line_endings: false }
+    }
+}
+
+struct DocumentationJob<'a>
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Run-wide inputs resolved once per notebook: CLI args, profile, definitions, templates, retry policies and the HTTP client.
+    ///
+    /// Output:
+    /// - Shared inputs for each prompt/API/save pass (one per cell with `--per-cell`).
+    ///
+    /// Exceptions:
+    /// - None.
+
+    cli_args: &'a CliArgs,
+    profile: PromptProfile,
+    definitions: &'a str,
+    prompt_template: &'a str,
+    prompt_path: &'a Path,
+    template_version: Option<&'a str>,
+    context_content: &'a str,
+    prompt_layout: Option<&'a str>,
+    output_schema: Option<&'a serde_json::Value>,
+    reference_notebooks: &'a [Notebook],
+    compare_notebook: Option<&'a Notebook>,
+    api_retry_policy: &'a RetryPolicy,
+    client: &'a Client,
+    akv_options: KeyVaultOptions,
+    min_output_chars: usize,
 }
 
 
@@ -206,14 +516,87 @@ struct PromptProfileSpec
 // Data Enumerations
 // ----------------------------
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum CliCommand
+{
+    /// Type: Enum.
+    /// Input:
+    /// - Command flag parsed from CLI (`Generate` when none is given).
+    ///
+    /// Output:
+    /// - Selected top-level command.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    Generate,
+    Clean,
+    ListProfiles,
+    ListEnvVars,
+    ShowConfig,
+    DefinitionsOnly,
+    Version,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ContextFormat
+{
+    /// Type: Enum.
+    /// Input:
+    /// - `--context-format` value from CLI.
+    ///
+    /// Output:
+    /// - Where `context.md` is placed in the chat messages.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    System,
+    User,
+    Both,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum GenerateOutcome
+{
+    /// Type: Enum.
+    /// Input:
+    /// - Result of one documentation run.
+    ///
+    /// Output:
+    /// - Whether documentation was saved or printed (`--stdout-only`), skipped for a `DOXCER:SKIP` marker
+    ///   or for empty or oversized input, rejected by `MIN_OUTPUT_CHARS`, `REQUIRED_SECTIONS` with `--strict` or `--expect-json-schema`,
+    ///   not writable, or failed by configuration, an empty or blocked response, the API or the definitions lookup.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    Saved,
+    Printed,
+    Skipped,
+    WriteFailed,
+    EmptyInput,
+    InputTooLarge,
+    BelowMinimum,
+    MissingSections,
+    SchemaMismatch,
+    ConfigError,
+    EmptyResponse,
+    ApiError,
+    DefinitionsError,
+    Failed,
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum PromptProfile
 {
     /// Type: Enum.
     /// Input:
     /// - Parsed selector flag from CLI.
+    ///
     /// Output:
     /// - Selected prompt profile variant.
+    ///
     /// Exceptions:
     /// - None.
 
@@ -225,6 +608,10 @@ enum PromptProfile
     Aws,
     DataFactory,
     Pipeline,
+    AzureML,
+    Gcp,
+    Scala,
+    Lakehouse,
 }
 
 
@@ -237,8 +624,9 @@ static PROMPT_PROFILE_SPECS: &[PromptProfileSpec] = &[
     {
         profile: PromptProfile::Default,
         name: "default",
-        selector_flags: &[],
+        selector_flags: &["-default"],
         template_stem: "default",
+        ..PromptProfileSpec::UNSET
     },
     PromptProfileSpec
     {
@@ -246,6 +634,7 @@ static PROMPT_PROFILE_SPECS: &[PromptProfileSpec] = &[
         name: "fabric",
         selector_flags: &["-fabric"],
         template_stem: "fabric",
+        ..PromptProfileSpec::UNSET
     },
     PromptProfileSpec
     {
@@ -253,6 +642,7 @@ static PROMPT_PROFILE_SPECS: &[PromptProfileSpec] = &[
         name: "synapse",
         selector_flags: &["-synapse"],
         template_stem: "synapse",
+        ..PromptProfileSpec::UNSET
     },
     PromptProfileSpec
     {
@@ -260,6 +650,7 @@ static PROMPT_PROFILE_SPECS: &[PromptProfileSpec] = &[
         name: "databricks",
         selector_flags: &["-databricks"],
         template_stem: "databricks",
+        ..PromptProfileSpec::UNSET
     },
     PromptProfileSpec
     {
@@ -267,6 +658,7 @@ static PROMPT_PROFILE_SPECS: &[PromptProfileSpec] = &[
         name: "powerbi",
         selector_flags: &["-powerbi"],
         template_stem: "powerbi",
+        ..PromptProfileSpec::UNSET
     },
     PromptProfileSpec
     {
@@ -274,6 +666,7 @@ static PROMPT_PROFILE_SPECS: &[PromptProfileSpec] = &[
         name: "aws",
         selector_flags: &["-aws"],
         template_stem: "aws",
+        ..PromptProfileSpec::UNSET
     },
     PromptProfileSpec
     {
@@ -281,6 +674,8 @@ static PROMPT_PROFILE_SPECS: &[PromptProfileSpec] = &[
         name: "datafactory",
         selector_flags: &["-datafactory"],
         template_stem: "datafactory",
+        definition_params: DefinitionParamLayout::WorkspaceAndTablePrefix,
+        ..PromptProfileSpec::UNSET
     },
     PromptProfileSpec
     {
@@ -288,16 +683,242 @@ static PROMPT_PROFILE_SPECS: &[PromptProfileSpec] = &[
         name: "pipeline",
         selector_flags: &["-pipeline"],
         template_stem: "pipeline",
+        ..PromptProfileSpec::UNSET
+    },
+    PromptProfileSpec
+    {
+        profile: PromptProfile::AzureML,
+        name: "azureml",
+        selector_flags: &["-azureml"],
+        template_stem: "azureml",
+        ..PromptProfileSpec::UNSET
+    },
+    PromptProfileSpec
+    {
+        profile: PromptProfile::Gcp,
+        name: "gcp",
+        selector_flags: &["-gcp"],
+        template_stem: "gcp",
+        ..PromptProfileSpec::UNSET
+    },
+    PromptProfileSpec
+    {
+        profile: PromptProfile::Scala,
+        name: "scala",
+        selector_flags: &["-scala"],
+        template_stem: "scala",
+        ..PromptProfileSpec::UNSET
+    },
+    PromptProfileSpec
+    {
+        profile: PromptProfile::Lakehouse,
+        name: "lakehouse",
+        selector_flags: &["-lakehouse"],
+        template_stem: "lakehouse",
+        base_profile: Some(PromptProfile::Fabric),
+        ..PromptProfileSpec::UNSET
     },
 ];
 
+/// Default file extension to profile map, used when no selector is given (see `DOXCER_EXTENSION_PROFILES`).
+const DEFAULT_EXTENSION_PROFILES: &[(&str, PromptProfile)] = &[
+    ("pbip", PromptProfile::PowerBi),
+    ("pbir", PromptProfile::PowerBi),
+    ("pbism", PromptProfile::PowerBi),
+    ("tmdl", PromptProfile::PowerBi),
+    ("dbc", PromptProfile::Databricks),
+];
+
+/// Databricks Scala export markers after `// `, stripped when the Scala profile is active.
+const DATABRICKS_SCALA_METADATA_MARKERS: &[&str] = &["MAGIC", "COMMAND ----------", "DBTITLE"];
+
+/// Request headers doxcer sets itself (lower case); `AI_EXTRA_HEADERS` may not replace them.
+const RESERVED_API_HEADERS: &[&str] = &["api-key", "content-type"];
+
+/// Directories checked (the start directory plus its parents) when searching for the repo root.
+/// Stops a misconfigured machine from walking up to the filesystem root; raise it if doxcer runs
+/// from deeper than 20 levels below the repo root.
+const MAX_ANCESTOR_DEPTH: usize = 20;
+
+/// Timeout for fetching `CONTEXT_URL`; a slow guidelines server falls back to the cached copy quickly.
+const CONTEXT_FETCH_TIMEOUT_SECS: u64 = 10;
+
+/// Process exit codes, one per failure class (see [`outcome_exit_code`] and [`apply_fail_on_warning`]).
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_CONFIG_ERROR: i32 = 1;
+const EXIT_EMPTY_RESPONSE: i32 = 2;
+const EXIT_API_ERROR: i32 = 3;
+const EXIT_DEFINITIONS_ERROR: i32 = 4;
+const EXIT_SCHEMA_MISMATCH: i32 = 5;
+const EXIT_MISSING_SECTIONS: i32 = 6;
+const EXIT_WARNINGS: i32 = 7;
+
+/// Leading lines of the first cell searched for the `DOXCER:SKIP` marker.
+const SKIP_MARKER_SCAN_LINES: usize = 10;
+
+// ----------------------------
+// Static Environment Variables
+// ----------------------------
+
+/// Environment variables read by doxcer as `(name, is_secret)`, in `--list-env-vars` order.
+/// Keep in sync with `ENVCONFIG` and the other `env::var` lookups; a unit test checks for drift.
+static ENV_VARS: &[(&str, bool)] = &[
+    // System
+    ("ABSOLUTE_DOXCER_PATH", false),
+    ("DOXCER_CONFIG_DIR", false),
+    ("DOXCER_ENV_FILES", false),
+    // AI Model
+    ("AI_ENABLED", false),
+    ("AI_BASE_URL", false),
+    ("AI_MODEL", false),
+    ("AI_VERSION", false),
+    ("AI_VERSION_OVERRIDE", false),
+    ("AI_TASK", false),
+    ("AI_SYSTEM_ROLE", false),
+    ("AI_USER_ROLE", false),
+    ("AI_EXTRA_HEADERS", false),
+    ("AI_SEED", false),
+    ("MIN_OUTPUT_CHARS", false),
+    ("REQUIRED_SECTIONS", false),
+    ("OUTPUT_LANGUAGE", false),
+    ("CONTEXT_URL", false),
+    // Wiki publishing
+    ("PUBLISH_ENABLED", false),
+    ("WIKI_API_URL", false),
+    ("AKV_SECRET_WIKI", true),
+    // Azure Key Vault
+    ("AKV_ENABLED", false),
+    ("AKV_BASE_URL", false),
+    ("AKV_CREDENTIAL", false),
+    ("AKV_SECRET_AI", true),
+    ("AKV_RETRY_ATTEMPTS", false),
+    ("AKV_TIMEOUT_SECS", false),
+    // Definition DB
+    ("DEFINITION_DATABASE_ENABLED", false),
+    ("DEFINITION_ENVIRONMENT", false),
+    ("DEFINITION_FABRIC_DATABASE_ENABLED", false),
+    ("DEFINITION_FABRIC_DATABASE", false),
+    ("AKV_SECRET_DEFINITION_FABRIC_ENDPOINT", true),
+    ("AKV_SECRET_DEFINITION_FABRIC_SERVICE_PRINCIPAL_CLIENT", true),
+    ("AKV_SECRET_DEFINITION_FABRIC_SERVICE_PRINCIPAL_PASSWORD", true),
+    ("DEFINITION_AZURE_DATABASE_ENABLED", false),
+    ("DEFINITION_AZURE_DATABASE", false),
+    ("AKV_SECRET_DEFINITION_AZURE_ENDPOINT", true),
+    ("AKV_SECRET_DEFINITION_AZURE_SERVICE_PRINCIPAL_CLIENT", true),
+    ("AKV_SECRET_DEFINITION_AZURE_SERVICE_PRINCIPAL_PASSWORD", true),
+    ("DEFINITION_ENCRYPT", false),
+    ("ODBC_ENCRYPT", false),
+    ("ODBC_TRUST_SERVER_CERTIFICATE", false),
+    ("DEFINITION_TRUST_CERT", false),
+    ("DEFINITION_TIMEOUT_SECS", false),
+    ("DEFINITION_ALLOW_PARTIAL", false),
+    ("DEFINITION_PREFIX_TEMPLATE", false),
+    ("DEFINITION_WORKSPACE", false),
+    ("ODBC_BATCH_SIZE", false),
+    ("ODBC_MAX_BYTE_SIZE", false),
+    // Runtime switches
+    ("DOXCER_TEMPLATE_DIR", false),
+    ("DOXCER_NO_TEMPLATE_FALLBACK", false),
+    ("DOXCER_BLANK_UNDEFINED_ENV", false),
+    ("DOXCER_DEBUG_API", false),
+    ("DOXCER_RETRY_ATTEMPTS", false),
+    ("DOXCER_RETRY_DELAY_MS", false),
+    ("DOXCER_RATE_LIMIT_DELAY_MS", false),
+    ("DOXCER_MAX_INPUT_BYTES", false),
+    ("DOXCER_EXTENSION_PROFILES", false),
+    ("STRIP_LEADING_COMMENT_BLOCK", false),
+    ("MAX_CELL_CHARS", false),
+];
+
 const USAGE_TEXT_TEMPLATE: &str = "[INF] - Usage:
 [INF] -   doxcer <path/to/notebook.py>
-[INF] -   doxcer [selector] <path/to/notebook.py>
+[INF] -   doxcer [selector] <path/to/notebooks/>
+[INF] -   doxcer --retry-failures [selector] <path/to/notebooks/>
+[INF] -   doxcer --clean [selector] <path/to/notebook.py>
+[INF] -   doxcer --list-profiles
+[INF] -   doxcer --list-env-vars
+[INF] -   doxcer --show-config
+[INF] -   doxcer --definitions-only [selector] <path/to/notebook.py>
+[INF] -   doxcer --version
 [INF] -   doxcer --help
+[INF] - Commands (no env files required):
+[INF] -   --clean, --no-env        Print the cleaned notebook source without calling the AI endpoint.
+[INF] -   --list-profiles          Print the available prompt profiles.
+[INF] -   --version                Print the doxcer version.
+[INF] - Commands (load the env files, no AI call):
+[INF] -   --list-env-vars          Print the env vars doxcer reads and their values.
+[INF] -   --show-config            Print the effective configuration as TOML.
+[INF] -   --definitions-only       Print the definitions table for the notebook.
 [INF] - Selectors:
 [INF] -   {selectors}
-[INF] - The path and selector can be provided in any order.";
+[INF] - Options:
+[INF] -   --no-template-fallback   Fail when the profile template is missing instead of using the default template.
+[INF] -   --notebook-name <name>   Override the output name inferred from the notebook path.
+[INF] -   --format-cmd \"<command>\"
+[INF] -                            Pipe the generated Markdown through an external formatter before saving.
+[INF] -   --truncate-definitions <n>
+[INF] -                            Limit the definitions section to at most n characters.
+[INF] -   --stats                  Print a statistics summary to stderr after the run.
+[INF] -   --env-file <path>        Load an extra env file on top of config/ (repeatable).
+[INF] -   --strict-env             Fail instead of warning when an --env-file path is missing.
+[INF] -   --profile-template-dir <path>
+[INF] -                            Look for prompt templates here before templates/.
+[INF] -   --sql-param KEY=VALUE    Replace {{KEY}} in the definitions SQL (repeatable).
+[INF] -   --auto-profile           Detect the profile from the notebook source when no selector is given.
+[INF] -   --compare <path>         Document the changes from an older notebook version at <path>.
+[INF] -   --retry-failures         Batch mode: only reprocess the paths in <dir>/_failures.txt.
+[INF] -   --section <name>         Generate only the named documentation section.
+[INF] -   --reference <path>       Add a companion notebook as context only (repeatable).
+[INF] -   --fail-on-warning        Exit with code {exit_warnings} when any [WRN] was emitted.
+[INF] -   --strict                 Fail (exit code {exit_missing_sections}) instead of warning when REQUIRED_SECTIONS are missing.
+[INF] -   --context-format <fmt>   Send context.md as system (default), user or both messages.
+[INF] -   --explain                Print a trace of the decisions made during the run.
+[INF] -   --context-version <v>    Append a prompt version marker to the system message.
+[INF] -   --changelog <path>       Append a dated entry per generated doc to <path>.
+[INF] -   --force                  With --changelog: append even when today's entry already exists.
+[INF] -   --include-imports        List the notebook's Python imports in a separate prompt section.
+[INF] -   --include-source         Append the cleaned notebook as a '## Source' section (alias --include-raw-notebook).
+[INF] -   --template-version-comment
+[INF] -                            Start the doc with the prompt template's template-version comment.
+[INF] -   --definition-format-template <t>
+[INF] -                            Definition section of the prompt (default 'Definitions: {definitions}').
+[INF] -   --api-version <version>  Override the AI API version (e.g. 2024-02-01).
+[INF] -   --expect-json-schema <path>
+[INF] -                            Validate the output as JSON against a schema before saving.
+[INF] -   --model <name>           Override the AI model (AI_MODEL) for this run.
+[INF] -   --seed <n>               Sampling seed for reproducible output (overrides AI_SEED).
+[INF] -   --lang <code>            Write the documentation in this language (e.g. nl, en, de).
+[INF] -   --retry-attempts <n>     Attempts for the AI API call (default 3, at least 1).
+[INF] -   --retry-delay <ms>       Delay between AI API attempts in milliseconds (default 1000).
+[INF] -   --akv-retry-attempts <n>
+[INF] -                            Attempts for Key Vault secret lookups (default 3, at least 1).
+[INF] -   --akv-timeout <seconds>  Timeout of each Key Vault secret lookup attempt (default 30).
+[INF] -   --allow-prod             Allow definitions queries when DEFINITION_ENVIRONMENT=prod.
+[INF] -   --rate-limit-delay <ms>  Wait between consecutive API calls in batch mode (default 0).
+[INF] -   --max-input-bytes <n>    Refuse notebooks larger than n bytes.
+[INF] -   --definition-timeout <s>
+[INF] -                            ODBC query timeout for definitions in seconds (0 = none).
+[INF] -   --odbc-encrypt <mode>    ODBC Encrypt mode: yes (default), no or strict.
+[INF] -   --odbc-trust-cert <bool>
+[INF] -                            ODBC TrustServerCertificate: true (default) or false.
+[INF] -   --exclude-nulls <column>
+[INF] -                            Drop definition rows that are NULL in this column.
+[INF] -   --progress               Show a progress bar on stderr in batch mode.
+[INF] -   --order <order>          Batch processing order: size-asc, size-desc, name or mtime.
+[INF] -   --stdout-only            Print the generated documentation instead of saving it.
+[INF] -   --mock-response <file>   Use a saved chat response JSON instead of calling the API.
+[INF] -   --output-encoding <enc>  Encoding of the saved file: utf8 (default) or utf16le (with BOM).
+[INF] -   --echo-limit <n>         After saving, also print the first <n> characters of the documentation.
+[INF] -   --adf-json               Strip Data Factory metadata (etag, lastModifiedAt, id, type) from .json inputs.
+[INF] -   --trim-trailing-blanks   Remove trailing blank lines from the cleaned notebook source.
+[INF] -   --preserve-line-endings  Keep the input's predominant line ending (CRLF or LF) in the cleaned source.
+[INF] -   --filter-cells <regex>   Only document the cells whose content matches <regex>.
+[INF] -   --per-cell               Document every `# CELL` separately into docs/newly-documented/<stem>/cell-NN.md.
+[INF] -   --frontmatter            Prepend YAML front matter (title, profile, generated_at, source).
+[INF] -   --frontmatter-field <k=v>
+[INF] -                            Add a custom front matter field (repeatable, implies --frontmatter).
+[INF] -   --diff-against-head      Print a line diff against the output file in git HEAD before saving.
+[INF] - The path, selector and options can be provided in any order.";
 
 
 // ----------------------------
@@ -309,42 +930,79 @@ static ENVCONFIG: Lazy<EnvParameters> = Lazy::new(||
     load_env();
     EnvParameters
     {
-        // Azure AI Foundry model configuration
-        ai_enabled: env::var("AI_ENABLED").unwrap_or_else(|_| "false".to_string()) == "true",
-        ai_base_url: env::var("AI_BASE_URL").expect("[WRN] - Missing AI_BASE_URL"),
-        ai_model: env::var("AI_MODEL").expect("[WRN] - Missing AI_MODEL"),
-        ai_version: env::var("AI_VERSION").expect("[WRN] - Missing AI_VERSION"),
-        ai_task: env::var("AI_TASK").expect("[WRN] - Missing AI_TASK"),
+        // Output validation
+        min_output_chars: env::var("MIN_OUTPUT_CHARS").unwrap_or_else(|_| "0".to_string()).parse().expect("[WRN] - Invalid MIN_OUTPUT_CHARS"),
+
+        // Wiki publishing (optional)
+        publish_enabled: env_flag_enabled("PUBLISH_ENABLED"),
+        wiki_api_url: env::var("WIKI_API_URL").unwrap_or_default(),
+        akv_secret_wiki: env::var("AKV_SECRET_WIKI").unwrap_or_default(),
 
         // Azure Key Vault Secrets
         akv_enabled: env::var("AKV_ENABLED").unwrap_or_else(|_| "false".to_string()) == "true",
         akv_base_url: env::var("AKV_BASE_URL").expect("[WRN] - Missing AKV_BASE_URL"),
-        akv_secret_ai: env::var("AKV_SECRET_AI").expect("[WRN] - Missing AKV_SECRET_AI"),
+        akv_credential: env::var("AKV_CREDENTIAL").unwrap_or_else(|_| "developer".to_string()),
 
         // Definition database
         definition_database_enabled: env::var("DEFINITION_DATABASE_ENABLED").unwrap_or_else(|_| "false".to_string()) == "true",
+        definition_environment: env::var("DEFINITION_ENVIRONMENT").unwrap_or_default(),
 
         // Fabric SQL Definition database Azure Key Vault
         definition_fabric_database_enabled: env::var("DEFINITION_FABRIC_DATABASE_ENABLED").unwrap_or_else(|_| "false".to_string()) == "true",
-        definition_fabric_database: env::var("DEFINITION_FABRIC_DATABASE").expect("[WRN] - Missing DEFINITION_FABRIC_DATABASE"),
-        akv_secret_definition_fabric_endpoint: env::var("AKV_SECRET_DEFINITION_FABRIC_ENDPOINT").expect("[WRN] - Missing AKV_SECRET_DEFINITION_FABRIC_ENDPOINT"),
-        akv_secret_definition_fabric_client_id: env::var("AKV_SECRET_DEFINITION_FABRIC_SERVICE_PRINCIPAL_CLIENT").expect("[WRN] - Missing AKV_SECRET_DEFINITION_FABRIC_SERVICE_PRINCIPAL_CLIENT"),
-        akv_secret_definition_fabric_password: env::var("AKV_SECRET_DEFINITION_FABRIC_SERVICE_PRINCIPAL_PASSWORD").expect("[WRN] - Missing AKV_SECRET_DEFINITION_FABRIC_SERVICE_PRINCIPAL_PASSWORD"),
+        definition_fabric_database: env::var("DEFINITION_FABRIC_DATABASE").unwrap_or_default(),
+        akv_secret_definition_fabric_endpoint: env::var("AKV_SECRET_DEFINITION_FABRIC_ENDPOINT").unwrap_or_default(),
+        akv_secret_definition_fabric_client_id: env::var("AKV_SECRET_DEFINITION_FABRIC_SERVICE_PRINCIPAL_CLIENT").unwrap_or_default(),
+        akv_secret_definition_fabric_password: env::var("AKV_SECRET_DEFINITION_FABRIC_SERVICE_PRINCIPAL_PASSWORD").unwrap_or_default(),
 
         // Azure SQL Definition database Azure Key Vault
         definition_azure_database_enabled: env::var("DEFINITION_AZURE_DATABASE_ENABLED").unwrap_or_else(|_| "false".to_string()) == "true",
-        definition_azure_database: env::var("DEFINITION_AZURE_DATABASE").expect("Missing DEFINITION_AZURE_DATABASE"),
-        akv_secret_definition_azure_endpoint: env::var("AKV_SECRET_DEFINITION_AZURE_ENDPOINT").expect("[WRN] - Missing AKV_SECRET_DEFINITION_AZURE_ENDPOINT"),
-        akv_secret_definition_azure_client_id: env::var("AKV_SECRET_DEFINITION_AZURE_SERVICE_PRINCIPAL_CLIENT").expect("[WRN] - Missing AKV_SECRET_DEFINITION_AZURE_SERVICE_PRINCIPAL_CLIENT"),
-        akv_secret_definition_azure_password: env::var("AKV_SECRET_DEFINITION_AZURE_SERVICE_PRINCIPAL_PASSWORD").expect("[WRN] - Missing AKV_SECRET_DEFINITION_AZURE_SERVICE_PRINCIPAL_PASSWORD"),
+        definition_azure_database: env::var("DEFINITION_AZURE_DATABASE").unwrap_or_default(),
+        akv_secret_definition_azure_endpoint: env::var("AKV_SECRET_DEFINITION_AZURE_ENDPOINT").unwrap_or_default(),
+        akv_secret_definition_azure_client_id: env::var("AKV_SECRET_DEFINITION_AZURE_SERVICE_PRINCIPAL_CLIENT").unwrap_or_default(),
+        akv_secret_definition_azure_password: env::var("AKV_SECRET_DEFINITION_AZURE_SERVICE_PRINCIPAL_PASSWORD").unwrap_or_default(),
 
         // ODBC Database connection configuration
         odbc_batch_size: env::var("ODBC_BATCH_SIZE").unwrap_or_else(|_| "200".to_string()).parse().expect("[WRN] - Invalid ODBC_BATCH_SIZE"),
         odbc_max_byte_size: env::var("ODBC_MAX_BYTE_SIZE").unwrap_or_else(|_| "4096".to_string()).parse().expect("[WRN] - Invalid ODBC_MAX_BYTE_SIZE"),
+        definition_encrypt: env::var("DEFINITION_ENCRYPT").unwrap_or_else(|_| "true".to_string()) == "true",
+        definition_trust_cert: env::var("DEFINITION_TRUST_CERT").unwrap_or_else(|_| "true".to_string()) == "true",
+    }
+});
+
+
+/// AI model settings, read on first use so modes that never call the AI endpoint do not require them.
+static AI_ENVCONFIG: Lazy<AiEnvParameters> = Lazy::new(||
+{
+    Lazy::force(&ENVCONFIG);
+    AiEnvParameters
+    {
+        // Azure AI Foundry model configuration
+        ai_enabled: env::var("AI_ENABLED").unwrap_or_else(|_| "false".to_string()) == "true",
+        ai_base_url: env::var("AI_BASE_URL").expect("[WRN] - Missing AI_BASE_URL"),
+        ai_model: env::var("AI_MODEL").expect("[WRN] - Missing AI_MODEL"),
+        ai_version: env::var("AI_VERSION").expect("[WRN] - Missing AI_VERSION"),
+        ai_task: env::var("AI_TASK").expect("[WRN] - Missing AI_TASK"),
+        ai_system_role: env::var("AI_SYSTEM_ROLE").ok().filter(|role| !role.trim().is_empty()).unwrap_or_else(|| "system".to_string()),
+        ai_user_role: env::var("AI_USER_ROLE").ok().filter(|role| !role.trim().is_empty()).unwrap_or_else(|| "user".to_string()),
+        ai_extra_headers: env::var("AI_EXTRA_HEADERS").unwrap_or_default(),
+
+        // Azure Key Vault Secrets
+        akv_secret_ai: env::var("AKV_SECRET_AI").expect("[WRN] - Missing AKV_SECRET_AI"),
     }
 });
 
 
+// ----------------------------
+// Tokio Runtime
+// ----------------------------
+
+/// Shared runtime for Key Vault lookups, created on first use and reused for every secret.
+static KEY_VAULT_RUNTIME: Lazy<tokio::runtime::Runtime> = Lazy::new(||
+{
+    tokio::runtime::Runtime::new().expect("[ERR] - Failed to create Tokio runtime")
+});
+
+
 // ----------------------------
 // Helper Functions
 // ----------------------------
@@ -360,10 +1018,22 @@ fn load_env()
     //! - Sets process environment variables from each required env file.
     //!
     //! # Panics
+    //! - If `DOXCER_CONFIG_DIR` is set but is not an absolute path to an existing directory.
     //! - If a required env file is missing.
     //! - If loading/parsing a required env file fails.
 
-    for env_path in find_env_paths()
+    let config_dir = config_dir_override(env::var("DOXCER_CONFIG_DIR").ok().as_deref())
+        .unwrap_or_else(|err| panic!("{}", err));
+    if let Some(config_dir) = &config_dir
+        && !config_dir.is_dir()
+    {
+        panic!(
+            "[ERR] - DOXCER_CONFIG_DIR '{}' does not exist or is not a directory.",
+            config_dir.display()
+        );
+    }
+
+    for env_path in find_env_paths(config_dir.as_deref())
     {
         if !env_path.exists()
         {
@@ -373,7 +1043,7 @@ fn load_env()
             );
         }
 
-        println!("[INF] - Loading environment from {}", env_path.display());
+        log_info!("Loading environment from {}", env_path.display());
 
         // system.env is intentionally not a dotenv file; it contains only path mapping metadata.
         let is_system_env = env_path
@@ -413,11 +1083,13 @@ fn has_repo_markers(path: &Path) -> bool
     //! - `path`: Candidate directory path.
     //!
     //! # Returns
-    //! - `true` when required repository markers exist (`Cargo.toml`, `config/`, `templates/`).
+    //! - `true` when required repository markers exist (`Cargo.toml`, `config/`, `templates/`),
+    //!   or when a `doxcer.toml` project config file exists (pre-built binary installs).
 
-    path.join("Cargo.toml").is_file()
+    (path.join("Cargo.toml").is_file()
         && path.join("config").is_dir()
-        && path.join("templates").is_dir()
+        && path.join("templates").is_dir())
+        || path.join("doxcer.toml").is_file()
 }
 
 fn parse_system_env_absolute_path(system_env_path: &Path) -> Option<PathBuf>
@@ -428,24 +1100,47 @@ fn parse_system_env_absolute_path(system_env_path: &Path) -> Option<PathBuf>
     //! - `system_env_path`: Path to the system env mapping file.
     //!
     //! # Returns
-    //! - `Some(PathBuf)` when a non-empty `ABSOLUTE_DOXCER_PATH=` value is found.
+    //! - `Some(PathBuf)` when a non-empty `ABSOLUTE_DOXCER_PATH=` value is found, see [`parse_absolute_doxcer_path`].
     //! - `None` when the file cannot be read or the key/value is missing or empty.
 
     let content = fs::read_to_string(system_env_path).ok()?;
-    for line in content.lines()
+    parse_absolute_doxcer_path(&content)
+}
+
+fn parse_absolute_doxcer_path(content: &str) -> Option<PathBuf>
+{
+    //! Extracts the `ABSOLUTE_DOXCER_PATH` value from `system.env` content.
+    //!
+    //! # Inputs
+    //! - `content`: Env file text, with `\n` or `\r\n` line endings.
+    //!
+    //! # Returns
+    //! - `Some(PathBuf)` for the first non-empty value.
+    //! - `None` when the key is missing or every value is empty.
+    //!
+    //! # Notes
+    //! - Whitespace around the key, `=` and value is ignored, as are inline comments (see [`strip_inline_comment`]).
+    //! - Surrounding single or double quotes are removed from the value.
+
+    for line in content.split('\n')
     {
-        let trimmed = line.trim();
+        let trimmed = line.trim_end_matches('\r').trim();
         if trimmed.is_empty() || trimmed.starts_with('#')
         {
             continue;
         }
 
-        let Some(value) = trimmed.strip_prefix("ABSOLUTE_DOXCER_PATH=") else
+        let Some((key, value)) = trimmed.split_once('=')
+        else
         {
             continue;
         };
+        if key.trim() != "ABSOLUTE_DOXCER_PATH"
+        {
+            continue;
+        }
 
-        let normalized = value.trim().trim_matches('"').trim_matches('\'');
+        let normalized = strip_inline_comment(value).trim().trim_matches('"').trim_matches('\'');
         if normalized.is_empty()
         {
             continue;
@@ -457,19 +1152,42 @@ fn parse_system_env_absolute_path(system_env_path: &Path) -> Option<PathBuf>
     None
 }
 
-fn find_repo_root_in_ancestors(start: &Path) -> Option<PathBuf>
+fn strip_inline_comment(value: &str) -> &str
+{
+    //! Removes an inline comment from an env value.
+    //!
+    //! # Returns
+    //! - `value` up to the first `#` that is not inside single or double quotes.
+    //! - `value` unchanged when it has no unquoted `#`.
+
+    let mut quote: Option<char> = None;
+    for (index, ch) in value.char_indices()
+    {
+        match (quote, ch)
+        {
+            (None, '"' | '\'') => quote = Some(ch),
+            (Some(open), _) if ch == open => quote = None,
+            (None, '#') => return &value[..index],
+            _ => {}
+        }
+    }
+    value
+}
+
+fn find_repo_root_in_ancestors(start: &Path, max_depth: usize) -> Option<PathBuf>
 {
     //! Walks ancestor directories to locate a valid repository root.
     //!
     //! # Inputs
     //! - `start`: Starting path used for ancestor traversal.
+    //! - `max_depth`: Maximum number of directories to check, starting with `start` (see [`MAX_ANCESTOR_DEPTH`]).
     //!
     //! # Returns
     //! - `Some(PathBuf)` when a directory with repository markers is found directly.
     //! - `Some(PathBuf)` when a valid mapped root is found via `config/system.env`.
-    //! - `None` when no valid root can be resolved.
+    //! - `None` when no valid root can be resolved within `max_depth` directories.
 
-    for ancestor in start.ancestors()
+    for ancestor in start.ancestors().take(max_depth)
     {
         if has_repo_markers(ancestor)
         {
@@ -478,11 +1196,9 @@ fn find_repo_root_in_ancestors(start: &Path) -> Option<PathBuf>
 
         let system_env = ancestor.join("config").join("system.env");
         if let Some(repo_root) = parse_system_env_absolute_path(&system_env)
+            && has_repo_markers(&repo_root)
         {
-            if has_repo_markers(&repo_root)
-            {
-                return Some(repo_root);
-            }
+            return Some(repo_root);
         }
     }
 
@@ -513,41 +1229,89 @@ fn find_repo_root_path() -> PathBuf
                 return candidate_path;
             }
 
-            eprintln!(
-                "[WRN] - ABSOLUTE_DOXCER_PATH is set but invalid: {}",
+            log_warn!(
+                "ABSOLUTE_DOXCER_PATH is set but invalid: {}",
                 candidate_path.display()
             );
         }
     }
 
     if let Ok(current_dir) = env::current_dir()
+        && let Some(repo_root) = find_repo_root_in_ancestors(&current_dir, MAX_ANCESTOR_DEPTH)
     {
-        if let Some(repo_root) = find_repo_root_in_ancestors(&current_dir)
-        {
-            return repo_root;
-        }
+        return repo_root;
     }
 
     if let Ok(exe_path) = env::current_exe()
+        && let Some(repo_root) = find_repo_root_in_ancestors(&exe_path, MAX_ANCESTOR_DEPTH)
+    {
+        return repo_root;
+    }
+
+    panic!("[ERR] - Failed to locate repository root. Run 'set-up-doxcer.ps1' first.");
+}
+
+fn load_env_file(path: &Path) -> std::result::Result<(), String>
+{
+    //! Loads one env file into process environment variables with dotenvy (existing variables win).
+
+    dotenvy::from_path(path).map_err(|err| err.to_string())
+}
+
+fn load_extra_env_files<F>(env_files: &[String], strict: bool, mut load: F) -> std::result::Result<usize, String>
+where
+    F: FnMut(&Path) -> std::result::Result<(), String>,
+{
+    //! Loads the `--env-file` paths into process environment variables.
+    //!
+    //! # Inputs
+    //! - `env_files`: Paths in CLI order, resolved relative to the current directory.
+    //! - `strict`: Treat a missing path as an error instead of a warning.
+    //! - `load`: Env file loader ([`load_env_file`] outside tests).
+    //!
+    //! # Returns
+    //! - `Ok(usize)` with the number of files loaded.
+    //! - `Err(String)` when a file fails to parse, or is missing while `strict` is set.
+    //!
+    //! # Notes
+    //! - Must run before `ENVCONFIG` is initialised. dotenvy never overwrites a variable that is
+    //!   already set, so files are applied last-to-first: later files override earlier ones, and
+    //!   all of them override the standard `config/` files.
+
+    let mut loaded = 0;
+
+    for env_file in env_files.iter().rev()
     {
-        if let Some(repo_root) = find_repo_root_in_ancestors(&exe_path)
+        let env_path = Path::new(env_file);
+        if !env_path.exists()
         {
-            return repo_root;
+            if strict
+            {
+                return Err(format!("[ERR] - Env file '{}' does not exist.", env_file));
+            }
+            log_warn!("Env file '{}' does not exist; skipping.", env_file);
+            continue;
         }
+
+        log_info!("Loading environment from {}", env_path.display());
+        load(env_path).map_err(|err| format!("[ERR] - Failed to load env file '{}': {}", env_file, err))?;
+        loaded += 1;
     }
 
-    panic!("[ERR] - Failed to locate repository root. Run 'set-up-doxcer.ps1' first.");
+    Ok(loaded)
 }
 
-fn find_env_paths() -> Vec<PathBuf>
+fn find_env_paths(config_dir: Option<&Path>) -> Vec<PathBuf>
 {
     //! Returns required environment file paths in load order.
     //!
     //! # Inputs
-    //! - None.
+    //! - `config_dir`: Resolved `DOXCER_CONFIG_DIR` override, see [`config_dir_override`].
+    //! - Reads `DOXCER_ENV_FILES` when set.
     //!
     //! # Returns
-    //! - Paths, in order:
+    //! - The paths listed in `DOXCER_ENV_FILES` (see [`parse_env_file_list`]) when it is set and non-blank.
+    //! - Otherwise, in order (`config/` is replaced by `config_dir` when given):
     //!   - `config/system.env`
     //!   - `config/definitions.env`
     //!   - `config/azure_key_vault.env`
@@ -557,7 +1321,17 @@ fn find_env_paths() -> Vec<PathBuf>
     //! - If repository root discovery fails.
     
     let repo = find_repo_root_path();
-    let config_dir = repo.join("config");
+
+    if let Ok(raw) = env::var("DOXCER_ENV_FILES")
+    {
+        let paths = parse_env_file_list(&raw, &repo);
+        if !paths.is_empty()
+        {
+            return paths;
+        }
+    }
+
+    let config_dir = config_dir.map(Path::to_path_buf).unwrap_or_else(|| repo.join("config"));
 
     vec![
         config_dir.join("system.env"),
@@ -567,19 +1341,69 @@ fn find_env_paths() -> Vec<PathBuf>
     ]
 }
 
-fn print_usage()
+fn config_dir_override(env_value: Option<&str>) -> std::result::Result<Option<PathBuf>, String>
 {
-    //! Prints CLI usage instructions to stderr.
-
+    //! Resolves the `DOXCER_CONFIG_DIR` override for the `config/` directory.
+    //!
+    //! # Inputs
+    //! - `env_value`: `DOXCER_CONFIG_DIR` value.
+    //!
+    //! # Returns
+    //! - `Ok(Some(PathBuf))` for an absolute path, `Ok(None)` when unset or blank.
+    //! - `Err(String)` for a relative path, which would depend on the working directory.
+
+    let Some(dir) = env_value.map(str::trim).filter(|dir| !dir.is_empty())
+    else
+    {
+        return Ok(None);
+    };
+
+    let path = PathBuf::from(dir);
+    if !path.is_absolute()
+    {
+        return Err(format!("[ERR] - DOXCER_CONFIG_DIR '{}' must be an absolute path.", dir));
+    }
+    Ok(Some(path))
+}
+
+fn parse_env_file_list(raw: &str, repo_root: &Path) -> Vec<PathBuf>
+{
+    //! Parses a comma-separated `DOXCER_ENV_FILES` value.
+    //!
+    //! # Inputs
+    //! - `raw`: Comma-separated list of env file paths.
+    //! - `repo_root`: Base for relative paths.
+    //!
+    //! # Returns
+    //! - Paths in listed order; blank entries are skipped and relative paths are joined to `repo_root`.
+
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry|
+        {
+            let path = Path::new(entry);
+            if path.is_absolute() { path.to_path_buf() } else { repo_root.join(path) }
+        })
+        .collect()
+}
+
+fn print_usage()
+{
+    //! Prints CLI usage instructions to stderr.
+
     eprintln!("{}", usage_text());
 }
 
 fn usage_text() -> String
 {
-    //! Builds CLI usage instructions with the current selector list.
+    //! Builds CLI usage instructions with the current selector list and exit codes.
 
     let selector_display = supported_selector_list().replace(", ", " | ");
-    USAGE_TEXT_TEMPLATE.replace("{selectors}", &selector_display)
+    USAGE_TEXT_TEMPLATE
+        .replace("{selectors}", &selector_display)
+        .replace("{exit_warnings}", &EXIT_WARNINGS.to_string())
+        .replace("{exit_missing_sections}", &EXIT_MISSING_SECTIONS.to_string())
 }
 
 fn is_help_requested(args: &[String]) -> bool
@@ -621,7 +1445,7 @@ fn parse_profile_selector(arg: &str) -> Option<PromptProfile>
 
     for spec in PROMPT_PROFILE_SPECS
     {
-        if spec.selector_flags.iter().any(|selector| *selector == arg)
+        if spec.selector_flags.contains(&arg)
         {
             return Some(spec.profile);
         }
@@ -643,24 +1467,176 @@ fn profile_selector_name(profile: PromptProfile) -> &'static str
     prompt_profile_spec(profile).name
 }
 
-fn supported_selector_list() -> String
+fn supported_selectors() -> Vec<&'static str>
 {
-    //! Builds a comma-separated list of supported selector flags.
+    //! Lists the canonical selector flag of every profile that has one.
     //!
     //! # Inputs
     //! - None.
     //!
     //! # Returns
-    //! - Comma-separated selector list in registry order.
+    //! - Selector flags (e.g. `-fabric`) in registry order; aliases are left out.
 
     PROMPT_PROFILE_SPECS
         .iter()
         .filter_map(|spec| spec.selector_flags.first().copied())
-        .collect::<Vec<&str>>()
-        .join(", ")
+        .collect()
+}
+
+fn supported_selector_list() -> String
+{
+    //! Builds a comma-separated list of supported selector flags, see [`supported_selectors`].
+    //!
+    //! # Inputs
+    //! - None.
+    //!
+    //! # Returns
+    //! - Comma-separated selector list in registry order.
+
+    supported_selectors().join(", ")
+}
+
+fn take_flag_value<'a, I>(flag: &str, tokens: &mut I) -> std::result::Result<String, ParseError>
+where
+    I: Iterator<Item = &'a String>,
+{
+    //! Takes the value token that follows a value-carrying CLI flag.
+    //!
+    //! # Inputs
+    //! - `flag`: Flag name used in the error message.
+    //! - `tokens`: Remaining CLI tokens.
+    //!
+    //! # Returns
+    //! - `Ok(String)` with the trimmed, non-empty value.
+    //! - `Err(ParseError::MissingValue)` when the value is missing or empty.
+
+    match tokens.next()
+    {
+        Some(value) if !value.trim().is_empty() => Ok(value.trim().to_string()),
+        _ => Err(ParseError::MissingValue { flag: flag.to_string() }),
+    }
+}
+
+fn take_flag_number<'a, I, T>(flag: &str, tokens: &mut I) -> std::result::Result<T, ParseError>
+where
+    I: Iterator<Item = &'a String>,
+    T: FromStr,
+{
+    //! Takes and parses the numeric value that follows a value-carrying CLI flag.
+    //!
+    //! # Inputs
+    //! - `flag`: Flag name used in error messages.
+    //! - `tokens`: Remaining CLI tokens.
+    //!
+    //! # Returns
+    //! - `Ok(T)` with the parsed value.
+    //! - `Err(ParseError)` when the value is missing or cannot be parsed.
+
+    let value = take_flag_value(flag, tokens)?;
+    value
+        .parse::<T>()
+        .map_err(|_| ParseError::invalid_value(flag, &value, None))
+}
+
+fn take_attempt_count<'a, I>(flag: &str, tokens: &mut I) -> std::result::Result<u32, ParseError>
+where
+    I: Iterator<Item = &'a String>,
+{
+    //! Takes a retry attempt count that follows a CLI flag.
+    //!
+    //! # Returns
+    //! - `Ok(u32)` when the value is a number of at least 1.
+    //! - `Err(ParseError)` when the value is missing, not a number, or 0.
+
+    let attempts: u32 = take_flag_number(flag, tokens)?;
+    if attempts == 0
+    {
+        return Err(ParseError::BelowMinimum { flag: flag.to_string(), minimum: 1 });
+    }
+    Ok(attempts)
+}
+
+fn parse_key_value(value: &str, arg: &str) -> std::result::Result<(String, String), ParseError>
+{
+    //! Parses a `KEY=VALUE` flag value (`--sql-param`, `--frontmatter-field`).
+    //!
+    //! # Inputs
+    //! - `value`: Raw `KEY=VALUE` token.
+    //! - `arg`: Flag name used in the error message.
+    //!
+    //! # Returns
+    //! - `Ok((key, value))` with a trimmed, non-empty key; the value may be empty.
+    //! - `Err(ParseError::InvalidValue)` when `=` or the key is missing.
+
+    match value.split_once('=')
+    {
+        Some((key, param_value)) if !key.trim().is_empty() =>
+        {
+            Ok((key.trim().to_string(), param_value.to_string()))
+        }
+        _ => Err(ParseError::invalid_value(arg, value, Some("KEY=VALUE"))),
+    }
+}
+
+fn is_frontmatter_key(key: &str) -> bool
+{
+    //! Returns `true` when a `--frontmatter-field` key can be written as a plain YAML key.
+    //!
+    //! # Notes
+    //! - Only ASCII letters, digits, `_`, `-` and `.` are allowed and the key may not start with `-`,
+    //!   so a `:`, `#`, quote or line break cannot break the front matter block.
+
+    !key.starts_with('-')
+        && !key.is_empty()
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+fn parse_context_format(value: &str) -> Option<ContextFormat>
+{
+    //! Parses a `--context-format` value (case-insensitive).
+    //!
+    //! # Returns
+    //! - `Some(ContextFormat)` for `system`, `user` or `both`.
+    //! - `None` for any other value.
+
+    match value.trim().to_ascii_lowercase().as_str()
+    {
+        "system" => Some(ContextFormat::System),
+        "user" => Some(ContextFormat::User),
+        "both" => Some(ContextFormat::Both),
+        _ => None,
+    }
+}
+
+fn set_cli_command(
+    current: &mut Option<CliCommand>,
+    next: CliCommand,
+    arg: &str,
+) -> std::result::Result<(), ParseError>
+{
+    //! Records a command flag, rejecting a second, different command.
+    //!
+    //! # Inputs
+    //! - `current`: Command selected so far.
+    //! - `next`: Command selected by `arg`.
+    //! - `arg`: Raw CLI token used in the error message.
+    //!
+    //! # Returns
+    //! - `Ok(())` when no other command was selected.
+    //! - `Err(ParseError::ConflictingCommands)` for conflicting commands.
+
+    match current
+    {
+        Some(existing) if *existing != next => Err(ParseError::ConflictingCommands { arg: arg.to_string() }),
+        _ =>
+        {
+            *current = Some(next);
+            Ok(())
+        }
+    }
 }
 
-fn parse_cli_args(args: &[String]) -> std::result::Result<CliArgs, String>
+fn parse_cli_args(args: &[String]) -> std::result::Result<CliArgs, ParseError>
 {
     //! Parses and validates CLI arguments.
     //!
@@ -669,17 +1645,74 @@ fn parse_cli_args(args: &[String]) -> std::result::Result<CliArgs, String>
     //!
     //! # Returns
     //! - `Ok(CliArgs)` when the path/selectors are valid.
-    //! - `Err(String)` with a user-facing validation message.
+    //! - `Err(ParseError)` describing the first invalid argument; its `Display` is the user-facing message.
 
     if args.is_empty()
     {
-        return Err("[ERR] - Missing executable name.".to_string());
+        return Err(ParseError::MissingExecutableName);
     }
 
+    let mut command: Option<CliCommand> = None;
     let mut selector_profile: Option<PromptProfile> = None;
     let mut file_path: Option<String> = None;
-
-    for arg in args.iter().skip(1)
+    let mut no_template_fallback = false;
+    let mut notebook_name: Option<String> = None;
+    let mut format_cmd: Option<String> = None;
+    let mut truncate_definitions: Option<usize> = None;
+    let mut stats = false;
+    let mut env_files: Vec<String> = Vec::new();
+    let mut strict_env = false;
+    let mut profile_template_dir: Option<String> = None;
+    let mut sql_params: HashMap<String, String> = HashMap::new();
+    let mut auto_profile = false;
+    let mut compare: Option<String> = None;
+    let mut retry_failures = false;
+    let mut section: Option<String> = None;
+    let mut references: Vec<String> = Vec::new();
+    let mut fail_on_warning = false;
+    let mut context_format = ContextFormat::System;
+    let mut explain = false;
+    let mut api_version: Option<String> = None;
+    let mut expect_json_schema: Option<String> = None;
+    let mut model: Option<String> = None;
+    let mut lang: Option<String> = None;
+    let mut retry_attempts: Option<u32> = None;
+    let mut retry_delay_ms: Option<u64> = None;
+    let mut akv_retry_attempts: Option<u32> = None;
+    let mut akv_timeout_secs: Option<u64> = None;
+    let mut allow_prod = false;
+    let mut rate_limit_delay_ms: Option<u64> = None;
+    let mut max_input_bytes: Option<u64> = None;
+    let mut definition_timeout_secs: Option<u64> = None;
+    let mut progress = false;
+    let mut stdout_only = false;
+    let mut mock_response: Option<String> = None;
+    let mut filter_cells: Option<String> = None;
+    let mut diff_against_head = false;
+    let mut exclude_nulls: Option<String> = None;
+    let mut per_cell = false;
+    let mut trim_trailing_blanks = false;
+    let mut preserve_line_endings = false;
+    let mut strict = false;
+    let mut batch_order: Option<batch::BatchOrder> = None;
+    let mut adf_json = false;
+    let mut context_version: Option<String> = None;
+    let mut changelog: Option<String> = None;
+    let mut force = false;
+    let mut include_imports = false;
+    let mut definition_format_template: Option<String> = None;
+    let mut seed: Option<u64> = None;
+    let mut odbc_encrypt: Option<OdbcEncrypt> = None;
+    let mut echo_limit: Option<usize> = None;
+    let mut odbc_trust_cert: Option<bool> = None;
+    let mut include_source = false;
+    let mut template_version_comment = false;
+    let mut output_encoding = io_utils::OutputEncoding::default();
+    let mut frontmatter = false;
+    let mut frontmatter_fields: Vec<(String, String)> = Vec::new();
+
+    let mut tokens = args.iter().skip(1);
+    while let Some(arg) = tokens.next()
     {
         if let Some(parsed_selector) = parse_profile_selector(arg)
         {
@@ -687,11 +1720,11 @@ fn parse_cli_args(args: &[String]) -> std::result::Result<CliArgs, String>
             {
                 if existing_selector != parsed_selector
                 {
-                    return Err(format!(
-                        "[ERR] - Conflicting selectors: both '{}' and '{}' were provided.",
-                        profile_selector_name(existing_selector),
-                        profile_selector_name(parsed_selector)
-                    ));
+                    return Err(ParseError::ConflictingSelectors
+                    {
+                        first: profile_selector_name(existing_selector).to_string(),
+                        second: profile_selector_name(parsed_selector).to_string(),
+                    });
                 }
             }
             else
@@ -703,603 +1736,4358 @@ fn parse_cli_args(args: &[String]) -> std::result::Result<CliArgs, String>
 
         match arg.as_str()
         {
+            "--clean" | "--no-env" => set_cli_command(&mut command, CliCommand::Clean, arg)?,
+            "--list-profiles" => set_cli_command(&mut command, CliCommand::ListProfiles, arg)?,
+            "--list-env-vars" => set_cli_command(&mut command, CliCommand::ListEnvVars, arg)?,
+            "--definitions-only" => set_cli_command(&mut command, CliCommand::DefinitionsOnly, arg)?,
+            "--show-config" => set_cli_command(&mut command, CliCommand::ShowConfig, arg)?,
+            "--version" => set_cli_command(&mut command, CliCommand::Version, arg)?,
+            "--no-template-fallback" =>
+            {
+                no_template_fallback = true;
+            }
+            "--notebook-name" =>
+            {
+                notebook_name = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--format-cmd" =>
+            {
+                format_cmd = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--truncate-definitions" =>
+            {
+                truncate_definitions = Some(take_flag_number(arg, &mut tokens)?);
+            }
+            "--stats" =>
+            {
+                stats = true;
+            }
+            "--env-file" =>
+            {
+                env_files.push(take_flag_value(arg, &mut tokens)?);
+            }
+            "--strict-env" =>
+            {
+                strict_env = true;
+            }
+            "--profile-template-dir" =>
+            {
+                profile_template_dir = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--sql-param" =>
+            {
+                let value = take_flag_value(arg, &mut tokens)?;
+                let (key, param_value) = parse_key_value(&value, arg)?;
+                sql_params.insert(key, param_value);
+            }
+            "--auto-profile" =>
+            {
+                auto_profile = true;
+            }
+            "--compare" =>
+            {
+                compare = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--retry-failures" =>
+            {
+                retry_failures = true;
+            }
+            "--section" =>
+            {
+                section = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--reference" =>
+            {
+                references.push(take_flag_value(arg, &mut tokens)?);
+            }
+            "--fail-on-warning" =>
+            {
+                fail_on_warning = true;
+            }
+            "--explain" =>
+            {
+                explain = true;
+            }
+            "--api-version" =>
+            {
+                api_version = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--expect-json-schema" =>
+            {
+                expect_json_schema = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--model" =>
+            {
+                model = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--lang" =>
+            {
+                lang = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--retry-attempts" =>
+            {
+                retry_attempts = Some(take_attempt_count(arg, &mut tokens)?);
+            }
+            "--retry-delay" =>
+            {
+                retry_delay_ms = Some(take_flag_number(arg, &mut tokens)?);
+            }
+            "--akv-retry-attempts" =>
+            {
+                akv_retry_attempts = Some(take_attempt_count(arg, &mut tokens)?);
+            }
+            "--akv-timeout" =>
+            {
+                let timeout_secs: u64 = take_flag_number(arg, &mut tokens)?;
+                if timeout_secs == 0
+                {
+                    return Err(ParseError::BelowMinimum { flag: arg.to_string(), minimum: 1 });
+                }
+                akv_timeout_secs = Some(timeout_secs);
+            }
+            "--allow-prod" =>
+            {
+                allow_prod = true;
+            }
+            "--progress" =>
+            {
+                progress = true;
+            }
+            "--stdout-only" =>
+            {
+                stdout_only = true;
+            }
+            "--mock-response" =>
+            {
+                mock_response = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--filter-cells" =>
+            {
+                filter_cells = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--diff-against-head" =>
+            {
+                diff_against_head = true;
+            }
+            "--exclude-nulls" =>
+            {
+                exclude_nulls = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--per-cell" =>
+            {
+                per_cell = true;
+            }
+            "--trim-trailing-blanks" =>
+            {
+                trim_trailing_blanks = true;
+            }
+            "--preserve-line-endings" =>
+            {
+                preserve_line_endings = true;
+            }
+            "--strict" =>
+            {
+                strict = true;
+            }
+            "--adf-json" =>
+            {
+                adf_json = true;
+            }
+            "--context-version" =>
+            {
+                context_version = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--changelog" =>
+            {
+                changelog = Some(take_flag_value(arg, &mut tokens)?);
+            }
+            "--force" =>
+            {
+                force = true;
+            }
+            "--include-imports" =>
+            {
+                include_imports = true;
+            }
+            "--seed" =>
+            {
+                seed = Some(take_flag_number(arg, &mut tokens)?);
+            }
+            "--definition-format-template" =>
+            {
+                // Shells pass "\n" literally; treat it as a line break in the section header.
+                definition_format_template = Some(take_flag_value(arg, &mut tokens)?.replace("\\n", "\n"));
+            }
+            "--echo-limit" =>
+            {
+                echo_limit = Some(take_flag_number(arg, &mut tokens)?);
+            }
+            "--odbc-encrypt" =>
+            {
+                let value = take_flag_value(arg, &mut tokens)?;
+                odbc_encrypt = Some(fetch_definitions::parse_odbc_encrypt(&value)
+                    .ok_or_else(|| ParseError::invalid_value(arg, &value, Some("yes, no or strict")))?);
+            }
+            "--include-source" | "--include-raw-notebook" =>
+            {
+                include_source = true;
+            }
+            "--template-version-comment" =>
+            {
+                template_version_comment = true;
+            }
+            "--output-encoding" =>
+            {
+                let value = take_flag_value(arg, &mut tokens)?;
+                output_encoding = io_utils::parse_output_encoding(&value)
+                    .ok_or_else(|| ParseError::invalid_value(arg, &value, Some("utf8 or utf16le")))?;
+            }
+            "--odbc-trust-cert" =>
+            {
+                let value = take_flag_value(arg, &mut tokens)?;
+                odbc_trust_cert = Some(parse_bool_value(&value)
+                    .ok_or_else(|| ParseError::invalid_value(arg, &value, Some("true or false")))?);
+            }
+            "--order" =>
+            {
+                let value = take_flag_value(arg, &mut tokens)?;
+                batch_order = Some(batch::parse_batch_order(&value)
+                    .ok_or_else(|| ParseError::invalid_value(arg, &value, Some("size-asc, size-desc, name or mtime")))?);
+            }
+            "--frontmatter" =>
+            {
+                frontmatter = true;
+            }
+            "--frontmatter-field" =>
+            {
+                let value = take_flag_value(arg, &mut tokens)?;
+                let (key, field_value) = parse_key_value(&value, arg)?;
+                if !is_frontmatter_key(&key)
+                {
+                    return Err(ParseError::invalid_value(
+                        arg,
+                        &value,
+                        Some("KEY=VALUE with a KEY of letters, digits, '_', '-' or '.' (not starting with '-')"),
+                    ));
+                }
+                frontmatter_fields.push((key, field_value));
+            }
+            "--rate-limit-delay" =>
+            {
+                rate_limit_delay_ms = Some(take_flag_number(arg, &mut tokens)?);
+            }
+            "--max-input-bytes" =>
+            {
+                max_input_bytes = Some(take_flag_number(arg, &mut tokens)?);
+            }
+            "--definition-timeout" =>
+            {
+                definition_timeout_secs = Some(take_flag_number(arg, &mut tokens)?);
+            }
+            "--context-format" =>
+            {
+                let value = take_flag_value(arg, &mut tokens)?;
+                context_format = parse_context_format(&value)
+                    .ok_or_else(|| ParseError::invalid_value(arg, &value, Some("system, user or both")))?;
+            }
             _ if arg.starts_with('-') =>
             {
-                return Err(format!(
-                    "[ERR] - Unknown selector '{}'. Supported selectors: {}.",
-                    arg,
-                    supported_selector_list()
-                ));
+                return Err(if arg.starts_with("--")
+                {
+                    ParseError::UnknownFlag { arg: arg.to_string() }
+                }
+                else
+                {
+                    ParseError::UnknownSelector { arg: arg.to_string() }
+                });
             }
             _ =>
             {
                 if let Some(existing_path) = &file_path
                 {
-                    return Err(format!(
-                        "[ERR] - Multiple input paths were provided: '{}' and '{}'.",
-                        existing_path, arg
-                    ));
+                    return Err(ParseError::MultipleNotebookPaths
+                    {
+                        first: existing_path.to_string(),
+                        second: arg.to_string(),
+                    });
                 }
                 file_path = Some(arg.to_string());
             }
         }
     }
 
+    let command = command.unwrap_or(CliCommand::Generate);
     let profile = selector_profile.unwrap_or(PromptProfile::Default);
 
-    let file_path = file_path
-        .ok_or_else(|| "[ERR] - Missing required notebook path argument.".to_string())?;
+    let file_path = match command
+    {
+        CliCommand::ListProfiles | CliCommand::ListEnvVars | CliCommand::ShowConfig | CliCommand::Version =>
+        {
+            file_path.unwrap_or_default()
+        }
+        CliCommand::Generate | CliCommand::Clean | CliCommand::DefinitionsOnly => file_path
+            .ok_or(ParseError::MissingNotebookPath)?,
+    };
 
     Ok(CliArgs
     {
+        command,
         file_path,
         profile,
+        profile_selected: selector_profile.is_some(),
+        no_template_fallback,
+        notebook_name,
+        format_cmd,
+        truncate_definitions,
+        stats,
+        env_files,
+        strict_env,
+        profile_template_dir,
+        sql_params,
+        auto_profile,
+        compare,
+        retry_failures,
+        section,
+        references,
+        fail_on_warning,
+        context_format,
+        explain,
+        api_version,
+        expect_json_schema,
+        model,
+        lang,
+        retry_attempts,
+        retry_delay_ms,
+        akv_retry_attempts,
+        akv_timeout_secs,
+        allow_prod,
+        rate_limit_delay_ms,
+        max_input_bytes,
+        definition_timeout_secs,
+        progress,
+        stdout_only,
+        mock_response,
+        filter_cells,
+        frontmatter,
+        frontmatter_fields,
+        diff_against_head,
+        exclude_nulls,
+        per_cell,
+        trim_trailing_blanks,
+        preserve_line_endings,
+        strict,
+        batch_order,
+        adf_json,
+        context_version,
+        changelog,
+        force,
+        include_imports,
+        definition_format_template,
+        seed,
+        odbc_encrypt,
+        echo_limit,
+        odbc_trust_cert,
+        include_source,
+        template_version_comment,
+        output_encoding,
     })
 }
 
-fn find_prompt_path(profile: &PromptProfile) -> PathBuf
+fn resolve_prompt_path(
+    template_dirs: &[PathBuf],
+    template_stem: &str,
+    allow_fallback: bool,
+) -> std::result::Result<PathBuf, DoxcerError>
 {
-    //! Resolves the prompt template path for the selected profile.
+    //! Resolves a prompt template across template directories in priority order.
     //!
     //! # Inputs
-    //! - `profile`: Prompt profile selector.
+    //! - `template_dirs`: Directories containing `*_prompt.md` templates, highest priority first.
+    //! - `template_stem`: Profile template stem.
+    //! - `allow_fallback`: Whether a missing template may fall back to `default_prompt.md`.
     //!
     //! # Returns
-    //! - Profile-specific template path when it exists.
-    //! - Default template path when profile template is missing.
+    //! - `Ok(PathBuf)` for the first existing profile template.
+    //! - `Ok(PathBuf)` for the first existing `default_prompt.md` (or the last directory's one)
+    //!   when the profile template is missing and fallback is allowed.
+    //! - `Err(DoxcerError::TemplateNotFound)` when the profile template is missing and fallback is disabled.
     //!
     //! # Panics
-    //! - If repository root discovery fails.
-
-    let prompt_file_stem = prompt_profile_spec(*profile).template_stem;
+    //! - If `template_dirs` is empty.
 
-    let repo = find_repo_root_path();
-    let template_dir = repo.join("templates");
-    let selected_template = template_dir.join(format!("{}_prompt.md", prompt_file_stem));
+    let template_file = format!("{}_prompt.md", template_stem);
 
-    if selected_template.exists()
+    if let Some(selected_template) = template_dirs
+        .iter()
+        .map(|dir| dir.join(&template_file))
+        .find(|path| path.exists())
+    {
+        Ok(selected_template)
+    }
+    else if allow_fallback
     {
-        selected_template
+        let default_template = template_dirs
+            .iter()
+            .map(|dir| dir.join("default_prompt.md"))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| template_dirs.last().expect("[ERR] - No template directories").join("default_prompt.md"));
+        Ok(default_template)
     }
     else
     {
-        template_dir.join("default_prompt.md")
+        Err(DoxcerError::TemplateNotFound(template_dirs[0].join(template_file)))
     }
 }
 
-fn find_context_path() -> PathBuf
+fn prompt_template_dirs(template_dir: Option<&Path>) -> Vec<PathBuf>
 {
-    //! Returns the path to `templates/context.md`.
+    //! Returns the template directories in search order: `template_dir` (when given), then `<repo_root>/templates`.
     //!
     //! # Panics
     //! - If repository root discovery fails.
-    
-    let repo = find_repo_root_path();
-    repo.join("templates")
-        .join("context.md")
+
+    let mut template_dirs = Vec::new();
+    if let Some(dir) = template_dir
+    {
+        template_dirs.push(dir.to_path_buf());
+    }
+    template_dirs.push(find_repo_root_path().join("templates"));
+    template_dirs
 }
 
-fn find_docs_path() -> PathBuf
+fn load_prompt_template(
+    profile: &PromptProfile,
+    template_dir: Option<&Path>,
+    allow_fallback: bool,
+) -> std::result::Result<(PathBuf, String), DoxcerError>
 {
-    //! Returns the output directory for generated Markdown files.
+    //! Loads the prompt template for a profile, including the template of its base profile.
+    //!
+    //! # Inputs
+    //! - `profile`: Prompt profile selector.
+    //! - `template_dir`: Optional template directory searched before `<repo_root>/templates`.
+    //! - `allow_fallback`: Whether a missing template may fall back to `default_prompt.md`.
+    //!
+    //! # Returns
+    //! - The template path and its content (see [`resolve_prompt_path`]) for a profile without a base.
+    //! - For a profile with a `base_profile`: the base template followed by `\n---\n` and the child
+    //!   template, or the base template alone when the child template does not exist.
+    //! - `Err(DoxcerError::TemplateNotFound)` as returned by [`resolve_prompt_path`].
     //!
     //! # Panics
-    //! - If repository root discovery fails.
+    //! - If repository root discovery fails or a template cannot be read.
 
-    let repo = find_repo_root_path();
-    repo.join("docs")
-        .join("newly-documented")
+    load_prompt_template_from(profile, &prompt_template_dirs(template_dir), allow_fallback)
 }
 
-fn is_metadata_line(line: &str) -> bool
+fn load_prompt_template_from(
+    profile: &PromptProfile,
+    template_dirs: &[PathBuf],
+    allow_fallback: bool,
+) -> std::result::Result<(PathBuf, String), DoxcerError>
+{
+    //! Loads the prompt template for a profile from explicit template directories, see [`load_prompt_template`].
+    //!
+    //! # Panics
+    //! - If a template cannot be read.
+
+    let read = |path: &Path| load_template(path)
+        .unwrap_or_else(|_| panic!("[ERR] - Failed to read prompt template {}", path.display()));
+    let spec = prompt_profile_spec(*profile);
+
+    let Some(base_profile) = spec.base_profile
+    else
+    {
+        let path = resolve_prompt_path(template_dirs, spec.template_stem, allow_fallback)?;
+        let content = read(&path);
+        return Ok((path, content));
+    };
+
+    let (base_path, base_content) = load_prompt_template_from(&base_profile, template_dirs, allow_fallback)?;
+    match resolve_prompt_path(template_dirs, spec.template_stem, false)
+    {
+        Ok(child_path) =>
+        {
+            log_info!(
+                "Extending prompt template {} with {}",
+                base_path.display(),
+                child_path.display()
+            );
+            let content = combine_inherited_template(&base_content, &read(&child_path));
+            Ok((child_path, content))
+        }
+        Err(_) => Ok((base_path, base_content)),
+    }
+}
+
+fn combine_inherited_template(base: &str, child: &str) -> String
+{
+    //! Appends a child profile template to its base profile template.
+    //!
+    //! # Returns
+    //! - `base`, a `---` separator line and `child`.
+
+    format!("{}\n---\n{}", base.trim_end(), child.trim_start())
+}
+
+fn find_context_path() -> PathBuf
+{
+    //! Returns the path to `templates/context.md`.
+    //!
+    //! # Panics
+    //! - If repository root discovery fails.
+    
+    let repo = find_repo_root_path();
+    repo.join("templates")
+        .join("context.md")
+}
+
+fn find_context_cache_path() -> PathBuf
+{
+    //! Returns the path to `templates/.context_cache.md`, the last context fetched from `CONTEXT_URL`.
+    //!
+    //! # Panics
+    //! - If repository root discovery fails.
+
+    let repo = find_repo_root_path();
+    repo.join("templates")
+        .join(".context_cache.md")
+}
+
+fn find_prompt_layout_path() -> PathBuf
+{
+    //! Returns the path to `templates/prompt_layout.md`.
+    //!
+    //! # Panics
+    //! - If repository root discovery fails.
+
+    let repo = find_repo_root_path();
+    repo.join("templates")
+        .join("prompt_layout.md")
+}
+
+fn find_docs_path() -> PathBuf
+{
+    //! Returns the output directory for generated Markdown files.
+    //!
+    //! # Panics
+    //! - If repository root discovery fails.
+
+    let repo = find_repo_root_path();
+    repo.join("docs")
+        .join("newly-documented")
+}
+
+fn is_placeholder_name(name: &str) -> bool
+{
+    //! Returns `true` when text between braces is a template placeholder name.
+    //!
+    //! # Notes
+    //! - Placeholders are identifiers (`[A-Za-z_][A-Za-z0-9_]*`), so free-text instructions
+    //!   such as `{Notebook bestandsnaam}` in the templates are left alone.
+
+    let mut chars = name.chars();
+    match chars.next()
+    {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' =>
+        {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn render_template(content: &str, vars: &[(&str, &str)]) -> (String, Vec<String>)
+{
+    //! Substitutes `{name}` placeholders in a prompt template.
+    //!
+    //! # Inputs
+    //! - `content`: Raw template text.
+    //! - `vars`: Known placeholder names and their values (e.g. `filename`, `date`, `definitions`).
+    //!
+    //! # Returns
+    //! - `(rendered, unresolved)` where unknown placeholders are left literal in `rendered`
+    //!   and listed once each, in order of first appearance, in `unresolved`.
+    //! - `${NAME}` env references (see [`interpolate_env`]) are never treated as placeholders.
+
+    let syntax = io_utils::PlaceholderSyntax
+    {
+        open: "{",
+        close: "}",
+        is_name: is_placeholder_name,
+        escape: Some(io_utils::PlaceholderEscape::Keep),
+    };
+
+    io_utils::substitute_placeholders(content, &syntax, false, |name|
+    {
+        vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+    })
+}
+
+fn interpolate_env<F>(content: &str, blank_undefined: bool, lookup: F) -> (String, Vec<String>)
+where
+    F: Fn(&str) -> Option<String>,
+{
+    //! Substitutes `${NAME}` references with environment variables.
+    //!
+    //! # Inputs
+    //! - `content`: Raw template text.
+    //! - `blank_undefined`: Replace undefined variables with an empty string instead of leaving them literal.
+    //! - `lookup`: Variable lookup (`env::var` outside tests).
+    //!
+    //! # Returns
+    //! - `(interpolated, undefined)` where `undefined` lists each missing variable once,
+    //!   in order of first appearance.
+    //! - `$${NAME}` is an escape and renders as the literal `${NAME}`.
+
+    let syntax = io_utils::PlaceholderSyntax
+    {
+        open: "${",
+        close: "}",
+        is_name: is_placeholder_name,
+        escape: Some(io_utils::PlaceholderEscape::Drop),
+    };
+
+    io_utils::substitute_placeholders(content, &syntax, blank_undefined, lookup)
+}
+
+fn load_template(path: &Path) -> std::io::Result<String>
+{
+    //! Reads a prompt or context template and interpolates `${NAME}` env references.
+    //!
+    //! # Inputs
+    //! - `path`: Template file path.
+    //!
+    //! # Returns
+    //! - Template text after [`interpolate_env`]; undefined variables are logged as `[WRN]`
+    //!   and blanked when `DOXCER_BLANK_UNDEFINED_ENV` is enabled, otherwise left as-is.
+
+    let blank_undefined = env_flag_enabled("DOXCER_BLANK_UNDEFINED_ENV");
+    let (content, undefined) = interpolate_env(&fs::read_to_string(path)?, blank_undefined, |name| env::var(name).ok());
+
+    for name in &undefined
+    {
+        log_warn!(
+            "Environment variable '${{{}}}' in template {} is not set; {}.",
+            name,
+            path.display(),
+            if blank_undefined { "replaced with an empty value" } else { "left as-is" }
+        );
+    }
+
+    Ok(content)
+}
+
+fn is_blank_source(source: &str) -> bool
+{
+    //! Returns `true` when notebook source is zero-length or whitespace-only.
+
+    source.trim().is_empty()
+}
+
+fn has_skip_marker(source: &str) -> bool
+{
+    //! Checks whether a notebook opts out of documentation.
+    //!
+    //! # Inputs
+    //! - `source`: Cleaned source of one cell, see [`notebook_has_skip_marker`].
+    //!
+    //! # Returns
+    //! - `true` when one of the first [`SKIP_MARKER_SCAN_LINES`] lines is a `DOXCER:SKIP` comment
+    //!   (`# DOXCER:SKIP`, `-- DOXCER:SKIP` or `// DOXCER:SKIP`).
+    //! - `false` otherwise, including a marker further down the notebook.
+
+    source.lines().take(SKIP_MARKER_SCAN_LINES).any(|line|
+    {
+        let trimmed = line.trim();
+        ["#", "--", "//"]
+            .iter()
+            .filter_map(|prefix| trimmed.strip_prefix(prefix))
+            .any(|rest| rest.trim() == "DOXCER:SKIP")
+    })
+}
+
+fn notebook_has_skip_marker(source: &str, input_path: &Path, comment_prefixes: &[&str], profile: PromptProfile) -> bool
+{
+    //! Checks whether the first cell of a notebook carries a `DOXCER:SKIP` marker.
+    //!
+    //! # Inputs
+    //! - `source`: Raw notebook source.
+    //! - `input_path`: Input path, used to detect `.ipynb` notebooks.
+    //! - `comment_prefixes`: Line comment prefixes of the notebook language.
+    //! - `profile`: Active prompt profile, see [`strip_notebook_metadata`].
+    //!
+    //! # Returns
+    //! - `true` when the first non-blank cell, without its metadata lines, passes [`has_skip_marker`].
+    //! - `false` otherwise.
+    //!
+    //! # Notes
+    //! - `.ipynb` cells come from [`notebook_formats::ipynb_cell_sources`]; other notebooks are split with
+    //!   [`split_cells`], so a Fabric header and `# METADATA` block do not count towards the scanned lines.
+
+    let is_ipynb = input_path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("ipynb"));
+    let cells = if is_ipynb
+    {
+        notebook_formats::ipynb_cell_sources(source).unwrap_or_default()
+    }
+    else
+    {
+        split_cells(source, comment_prefixes)
+    };
+
+    cells
+        .iter()
+        .map(|cell| strip_notebook_metadata(cell, comment_prefixes, profile))
+        .find(|cell| !is_blank_source(cell))
+        .is_some_and(|cell| has_skip_marker(&cell))
+}
+
+fn is_comment_marker(line: &str, comment_prefixes: &[&str], keywords: &[&str], ignore_case: bool) -> bool
+{
+    //! Checks whether a line is a `<prefix> KEYWORD` marker comment.
+    //!
+    //! # Inputs
+    //! - `line`: Single notebook source line.
+    //! - `comment_prefixes`: Line comment prefixes of the notebook language.
+    //! - `keywords`: Upper-case marker keywords, e.g. `CELL`.
+    //! - `ignore_case`: Whether the keyword also matches in lower or mixed case.
+    //!
+    //! # Returns
+    //! - `true` when the line starts (after indentation) with a prefix, at least one space or tab and a keyword,
+    //!   followed by the end of the line or a non-alphanumeric character.
+    //! - `false` otherwise, e.g. for `#CELL` or `# CELLULAR`, and for `# Cell` unless `ignore_case` is set.
+
+    let trimmed = line.trim_start();
+    comment_prefixes.iter().any(|prefix|
+    {
+        let Some(rest) = trimmed.strip_prefix(prefix)
+        else
+        {
+            return false;
+        };
+        let word = rest.trim_start_matches([' ', '\t']);
+        if word.len() == rest.len()
+        {
+            return false;
+        }
+
+        keywords.iter().any(|keyword|
+        {
+            let head_matches = word.get(..keyword.len()).is_some_and(|head|
+            {
+                if ignore_case { head.eq_ignore_ascii_case(keyword) } else { head == *keyword }
+            });
+            head_matches && !word[keyword.len()..].starts_with(|c: char| c.is_alphanumeric())
+        })
+    })
+}
+
+fn is_metadata_line(line: &str, comment_prefixes: &[&str], profile: PromptProfile) -> bool
 {
     //! Checks whether a source line is notebook metadata.
     //!
     //! # Inputs
     //! - `line`: Single notebook source line.
+    //! - `comment_prefixes`: Line comment prefixes of the notebook language.
+    //! - `profile`: Active prompt profile.
     //!
     //! # Returns
-    //! - `true` when line starts with `# METADATA`, `# META`, or `# CELL`.
+    //! - `true` when line starts with `<prefix> METADATA`, `<prefix> META`, or `<prefix> CELL`
+    //!   (e.g. `# CELL` in Python, `-- CELL` in SQL), with spaces or tabs after the prefix.
+    //! - `METADATA` matches in any case; `META` and `CELL` must be upper case.
+    //! - With `PromptProfile::Scala`, also `true` for Databricks `// MAGIC`, `// COMMAND ----------`
+    //!   and `// DBTITLE` lines.
 
     let trimmed = line.trim_start();
-    trimmed.starts_with("# METADATA")
-        || trimmed.starts_with("# META")
-        || trimmed.starts_with("# CELL")
+
+    // Only METADATA is matched in any case; `# Cell 3 loads ...` is an ordinary comment.
+    is_comment_marker(line, comment_prefixes, &["METADATA"], true)
+        || is_comment_marker(line, comment_prefixes, &["META", "CELL"], false)
+        || (profile == PromptProfile::Scala
+            && trimmed
+                .strip_prefix("// ")
+                .is_some_and(|rest| DATABRICKS_SCALA_METADATA_MARKERS.iter().any(|marker| rest.starts_with(marker))))
 }
 
-fn strip_notebook_metadata(source: &str) -> String
+fn split_cells(source: &str, comment_prefixes: &[&str]) -> Vec<String>
 {
-    //! Removes metadata lines from notebook source text.
+    //! Splits raw notebook source on cell marker lines.
     //!
     //! # Inputs
-    //! - `source`: Raw notebook source text.
+    //! - `source`: Raw notebook source.
+    //! - `comment_prefixes`: Line comment prefixes of the notebook language.
     //!
     //! # Returns
-    //! - Source text without metadata lines.
+    //! - The source of each cell, without its `<prefix> CELL` marker line.
+    //! - `source` as a single cell when it has no cell markers.
+    //!
+    //! # Notes
+    //! - Text before the first marker (e.g. the Fabric header and notebook metadata) is not a cell and is dropped.
+    //! - Splits the raw source because cleaning removes the cell markers; each cell is cleaned on its own.
 
-    let mut cleaned_lines: Vec<&str> = Vec::new();
+    let is_cell_marker = |line: &str| is_comment_marker(line, comment_prefixes, &["CELL"], false);
+
+    if !source.lines().any(is_cell_marker)
+    {
+        return vec![source.to_string()];
+    }
 
+    let mut cells: Vec<String> = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
     for line in source.lines()
     {
-        if !is_metadata_line(line)
+        if is_cell_marker(line)
         {
-            cleaned_lines.push(line);
+            if let Some(lines) = current.take()
+            {
+                cells.push(lines.join("\n"));
+            }
+            current = Some(Vec::new());
+        }
+        else if let Some(lines) = current.as_mut()
+        {
+            lines.push(line);
         }
     }
-
-    cleaned_lines.join("\n")
+    if let Some(lines) = current
+    {
+        cells.push(lines.join("\n"));
+    }
+    cells
 }
 
-fn collapse_blank_lines(source: &str) -> String
+fn truncate_large_cells(source: &str, max_chars: usize, comment_prefixes: &[&str]) -> String
 {
-    //! Collapses consecutive blank lines into a single blank line.
+    //! Caps the size of every individual notebook cell.
     //!
     //! # Inputs
-    //! - `source`: Multi-line text.
+    //! - `source`: Raw notebook source, including its `<prefix> CELL` marker lines.
+    //! - `max_chars`: Maximum number of characters (not bytes) kept per cell.
+    //! - `comment_prefixes`: Line comment prefixes of the notebook language.
     //!
     //! # Returns
-    //! - Text with consecutive blank lines collapsed.
+    //! - `source` with each cell over `max_chars` cut (at a line boundary when possible) and followed by a
+    //!   `<prefix> ... [truncated] ...` line; cells within the limit are unchanged.
+    //!
+    //! # Notes
+    //! - Text before the first marker counts as a cell, so a notebook without markers is capped as a whole.
+    //! - Runs on the raw source, before cleaning removes the cell markers.
 
-    let mut result: Vec<&str> = Vec::new();
-    let mut previous_was_blank = false;
+    let is_cell_marker = |line: &str| is_comment_marker(line, comment_prefixes, &["CELL"], false);
+    let truncation_marker = format!("{} ... [truncated] ...", comment_prefixes.first().copied().unwrap_or("#"));
 
-    for line in source.lines()
+    let mut output: Vec<String> = Vec::new();
+    let mut cell: Vec<&str> = Vec::new();
+    let flush_cell = |cell: &mut Vec<&str>, output: &mut Vec<String>|
     {
-        let is_blank = line.trim().is_empty();
-
-        if is_blank
+        let body = cell.join("\n");
+        if body.chars().count() > max_chars
         {
-            if !previous_was_blank
+            let kept = io_utils::truncate_at_newline(&body, max_chars).trim_end_matches('\n');
+            if !kept.is_empty()
             {
-                result.push(line);
-                previous_was_blank = true;
+                output.push(kept.to_string());
             }
+            output.push(truncation_marker.clone());
         }
         else
         {
-            result.push(line);
-            previous_was_blank = false;
+            output.extend(cell.iter().map(|line| line.to_string()));
+        }
+        cell.clear();
+    };
+
+    for line in source.lines()
+    {
+        if is_cell_marker(line)
+        {
+            flush_cell(&mut cell, &mut output);
+            output.push(line.to_string());
+        }
+        else
+        {
+            cell.push(line);
         }
     }
+    flush_cell(&mut cell, &mut output);
 
-    result.join("\n")
+    output.join("\n")
 }
 
-fn is_assistant_role(role: Option<&str>) -> bool
+fn filter_cells_by_regex(source: &str, pattern: &str, comment_prefixes: &[&str]) -> Result<String, String>
 {
-    //! Returns `true` when a response role is missing or explicitly assistant.
+    //! Keeps only the notebook cells whose content matches a regular expression (`--filter-cells`).
     //!
     //! # Inputs
-    //! - `role`: Optional response role value from the API payload.
+    //! - `source`: Raw notebook source, including its `<prefix> CELL` marker lines.
+    //! - `pattern`: Regular expression matched against the content of each cell.
+    //! - `comment_prefixes`: Line comment prefixes of the notebook language.
     //!
     //! # Returns
-    //! - `true` for `None` to preserve compatibility with payloads that omit role.
-    //! - `true` for case-insensitive `"assistant"`.
-    //! - `false` for any other role.
+    //! - `Ok(String)` with the matching cells and their marker lines, in order.
+    //! - `Err(String)` when `pattern` is not a valid regular expression.
+    //!
+    //! # Notes
+    //! - Text before the first marker (e.g. the Fabric header) is always kept; cleaning removes its metadata.
+    //! - A notebook without markers is one cell, kept as a whole or dropped as a whole.
 
-    role
-        .map(|value| value.eq_ignore_ascii_case("assistant"))
-        .unwrap_or(true)
+    let regex = Regex::new(pattern)
+        .map_err(|err| format!("[ERR] - Invalid --filter-cells pattern '{}': {}", pattern, err))?;
+    let is_cell_marker = |line: &str| is_comment_marker(line, comment_prefixes, &["CELL"], false);
+
+    if !source.lines().any(is_cell_marker)
+    {
+        return Ok(if regex.is_match(source) { source.to_string() } else { String::new() });
+    }
+
+    // blocks[0] is the text before the first marker; every later block starts with its marker line.
+    let mut blocks: Vec<Vec<&str>> = vec![Vec::new()];
+    for line in source.lines()
+    {
+        if is_cell_marker(line)
+        {
+            blocks.push(Vec::new());
+        }
+        blocks.last_mut().expect("blocks is never empty").push(line);
+    }
+
+    let header = blocks.remove(0);
+    let output: Vec<&str> = header
+        .into_iter()
+        .chain(
+            blocks
+                .into_iter()
+                .filter(|block| regex.is_match(&block[1..].join("\n")))
+                .flatten(),
+        )
+        .collect();
+
+    Ok(output.join("\n"))
 }
 
-fn looks_like_internal_prompt(content: &str) -> bool
+fn max_cell_chars(env_value: Option<&str>) -> Option<usize>
 {
-    //! Heuristically detects when model output appears to be a full prompt echo.
+    //! Resolves the per-cell size cap.
     //!
     //! # Inputs
-    //! - `content`: Assistant text content.
+    //! - `env_value`: `MAX_CELL_CHARS` value.
     //!
     //! # Returns
-    //! - `true` when content contains all internal prompt envelope markers.
+    //! - `Some(limit)` for a positive number.
+    //! - `None` when unset or blank; `0` or a value that is not a number is ignored with a `[WRN]`.
 
-    content.contains("Current date time:")
-        && content.contains("Notebook filename:")
-        && content.contains("Documentation template:")
-        && content.contains("Code:")
+    let raw = env_value.map(str::trim).filter(|value| !value.is_empty())?;
+    match raw.parse::<usize>()
+    {
+        Ok(limit) if limit > 0 => Some(limit),
+        _ =>
+        {
+            log_warn!("Invalid MAX_CELL_CHARS '{}'; cells are not truncated.", raw);
+            None
+        }
+    }
+}
+
+fn cell_output_stem(stem: &str, cell_number: usize) -> String
+{
+    //! Returns the output stem for one cell with `--per-cell`.
+    //!
+    //! # Inputs
+    //! - `stem`: Output stem of the whole notebook.
+    //! - `cell_number`: 1-based cell number.
+    //!
+    //! # Returns
+    //! - `<stem>/cell-NN`, saved as `docs/newly-documented/<stem>/cell-NN.md`.
+
+    format!("{}/cell-{:02}", stem, cell_number)
+}
+
+fn strip_notebook_metadata(source: &str, comment_prefixes: &[&str], profile: PromptProfile) -> String
+{
+    //! Removes metadata lines from notebook source text.
+    //!
+    //! # Inputs
+    //! - `source`: Raw notebook source text.
+    //! - `comment_prefixes`: Line comment prefixes of the notebook language.
+    //! - `profile`: Active prompt profile, see [`is_metadata_line`].
+    //!
+    //! # Returns
+    //! - Source text without metadata lines.
+
+    let mut cleaned_lines: Vec<&str> = Vec::new();
+
+    for line in source.lines()
+    {
+        if !is_metadata_line(line, comment_prefixes, profile)
+        {
+            cleaned_lines.push(line);
+        }
+    }
+
+    cleaned_lines.join("\n")
+}
+
+fn strip_leading_comment_block(source: &str, comment_prefixes: &[&str]) -> String
+{
+    //! Removes a license/banner comment block at the top of cleaned source.
+    //!
+    //! # Inputs
+    //! - `source`: Cleaned notebook source.
+    //! - `comment_prefixes`: Line comment prefixes, e.g. `#` or `--`.
+    //!
+    //! # Returns
+    //! - Source starting at the first line that is neither blank nor a comment.
+    //! - `source` unchanged when it does not start with a comment line.
+
+    let is_comment = |line: &str|
+    {
+        let trimmed = line.trim_start();
+        comment_prefixes.iter().any(|prefix| trimmed.starts_with(prefix))
+    };
+
+    let mut lines = source.lines().peekable();
+    while lines.peek().is_some_and(|line| line.trim().is_empty())
+    {
+        lines.next();
+    }
+    if !lines.peek().is_some_and(|line| is_comment(line))
+    {
+        return source.to_string();
+    }
+
+    lines
+        .skip_while(|line| line.trim().is_empty() || is_comment(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn collapse_config(cli_args: &CliArgs) -> CollapseConfig
+{
+    //! Returns the blank line rules for a run.
+    //!
+    //! # Returns
+    //! - [`CollapseConfig::default`], with trailing blank lines trimmed when `--trim-trailing-blanks` is set.
+    //! - Line endings follow the input when `--preserve-line-endings` is set.
+
+    CollapseConfig
+    {
+        trim_trailing: cli_args.trim_trailing_blanks,
+        preserve_line_endings: cli_args.preserve_line_endings,
+        ..CollapseConfig::default()
+    }
+}
+
+fn predominant_line_ending(source: &str) -> &'static str
+{
+    //! Detects the line ending used by most lines of a text.
+    //!
+    //! # Returns
+    //! - `"\r\n"` when more lines end in CRLF than in a bare LF.
+    //! - `"\n"` otherwise, including text without line breaks.
+
+    let crlf_count = source.matches("\r\n").count();
+    let lf_count = source.matches('\n').count() - crlf_count;
+
+    if crlf_count > lf_count { "\r\n" } else { "\n" }
+}
+
+fn with_line_ending(text: &str, line_ending: &str) -> String
+{
+    //! Rewrites every line break in `text` to `line_ending`.
+
+    let normalized = text.replace("\r\n", "\n");
+    if line_ending == "\n"
+    {
+        normalized
+    }
+    else
+    {
+        normalized.replace('\n', line_ending)
+    }
+}
+
+fn collapse_blank_lines(source: &str, config: &CollapseConfig) -> String
+{
+    //! Collapses runs of consecutive blank lines.
+    //!
+    //! # Inputs
+    //! - `source`: Multi-line text with LF or CRLF line endings.
+    //! - `config`: Maximum blank lines in a row, whether trailing blank lines are removed, and whether line endings are kept.
+    //!
+    //! # Returns
+    //! - Text with at most `config.max_consecutive` blank lines in a row.
+    //! - Without trailing blank lines when `config.trim_trailing` is set.
+    //! - Joined with the predominant line ending of `source` when `config.preserve_line_endings` is set, LF otherwise.
+
+    let mut result: Vec<&str> = Vec::new();
+    let mut blank_run = 0;
+
+    for line in source.lines()
+    {
+        if line.trim().is_empty()
+        {
+            blank_run += 1;
+            if blank_run <= config.max_consecutive
+            {
+                result.push(line);
+            }
+        }
+        else
+        {
+            result.push(line);
+            blank_run = 0;
+        }
+    }
+
+    if config.trim_trailing
+    {
+        while result.last().is_some_and(|line| line.trim().is_empty())
+        {
+            result.pop();
+        }
+    }
+
+    let line_ending = if config.preserve_line_endings { predominant_line_ending(source) } else { "\n" };
+    result.join(line_ending)
+}
+
+fn is_assistant_role(role: Option<&str>) -> bool
+{
+    //! Returns `true` when a response role is missing or explicitly assistant.
+    //!
+    //! # Inputs
+    //! - `role`: Optional response role value from the API payload.
+    //!
+    //! # Returns
+    //! - `true` for `None` to preserve compatibility with payloads that omit role.
+    //! - `true` for case-insensitive `"assistant"`.
+    //! - `false` for any other role.
+
+    role
+        .map(|value| value.eq_ignore_ascii_case("assistant"))
+        .unwrap_or(true)
+}
+
+fn looks_like_internal_prompt(content: &str) -> bool
+{
+    //! Heuristically detects when model output appears to be a full prompt echo.
+    //!
+    //! # Inputs
+    //! - `content`: Assistant text content.
+    //!
+    //! # Returns
+    //! - `true` when content contains all internal prompt envelope markers.
+
+    content.contains("Current date time:")
+        && content.contains("Notebook filename:")
+        && content.contains("Documentation template:")
+        && content.contains("Code:")
+}
+
+fn parse_bool_value(value: &str) -> Option<bool>
+{
+    //! Parses a boolean CLI or environment value (case-insensitive).
+    //!
+    //! # Returns
+    //! - `Some(true)` for `true`, `yes`, `1`, `on`; `Some(false)` for `false`, `no`, `0`, `off`.
+    //! - `None` for any other value.
+
+    match value.trim().to_ascii_lowercase().as_str()
+    {
+        "true" | "yes" | "1" | "on" => Some(true),
+        "false" | "no" | "0" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn env_flag_enabled(name: &str) -> bool
+{
+    //! Reads a boolean-like environment flag.
+    //!
+    //! # Inputs
+    //! - `name`: Environment variable name.
+    //!
+    //! # Returns
+    //! - `true` for values: `1`, `true`, `yes`, `on` (case-insensitive).
+    //! - `false` otherwise.
+
+    match env::var(name)
+    {
+        Ok(value) =>
+        {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        }
+        Err(_) => false,
+    }
+}
+
+fn yaml_scalar(value: &str) -> String
+{
+    //! Formats a front matter value as a YAML scalar.
+    //!
+    //! # Returns
+    //! - The value as-is when it is plain text, otherwise a double-quoted string
+    //!   (values with spaces, YAML indicator characters, or empty values).
+
+    let needs_quotes = value.is_empty()
+        || value.chars().any(|c| c.is_whitespace() || matches!(c, ':' | '#' | '"' | '\'' | '\\'))
+        || value.starts_with(['-', '[', '{', '&', '*', '!', '|', '>', '%', '@', '`']);
+
+    if needs_quotes
+    {
+        format!(
+            "\"{}\"",
+            value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+        )
+    }
+    else
+    {
+        value.to_string()
+    }
+}
+
+fn append_source_section(content: &str, source: &str, lang: &str) -> String
+{
+    //! Appends the notebook source to the generated documentation (`--include-source`).
+    //!
+    //! # Inputs
+    //! - `content`: Generated Markdown.
+    //! - `source`: Cleaned notebook source.
+    //! - `lang`: Code fence language tag, e.g. `python`; empty for no tag.
+    //!
+    //! # Returns
+    //! - `content` followed by a `## Source` section with `source` in a fenced code block.
+    //!
+    //! # Notes
+    //! - The fence is one backtick longer than the longest backtick run in `source` (at least three),
+    //!   so backticks in the source cannot close the block early.
+
+    let longest_run = source
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat((longest_run + 1).max(3));
+
+    format!(
+        "{}\n\n## Source\n\n{}{}\n{}\n{}\n",
+        content.trim_end(),
+        fence,
+        lang,
+        source.trim_end(),
+        fence
+    )
+}
+
+fn prepend_frontmatter(content: &str, fields: &[(&str, String)], extra_fields: &[(String, String)]) -> String
+{
+    //! Prepends a YAML front matter block to the generated documentation.
+    //!
+    //! # Inputs
+    //! - `content`: Generated Markdown.
+    //! - `fields`: Reserved fields (`title`, `profile`, `generated_at`, `source`) in output order.
+    //! - `extra_fields`: `--frontmatter-field` entries, appended in the given order.
+    //!
+    //! # Returns
+    //! - `---` delimited front matter followed by `content`.
+    //!
+    //! # Notes
+    //! - An extra field named like a reserved field (case-insensitive) replaces its value with a `[WRN]`;
+    //!   a repeated extra key keeps the last value.
+
+    let mut entries: Vec<(String, String)> = fields
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.clone()))
+        .collect();
+
+    for (key, value) in extra_fields
+    {
+        if let Some(reserved) = fields.iter().find(|(reserved, _)| reserved.eq_ignore_ascii_case(key))
+        {
+            log_warn!("Front matter field '{}' overrides the generated value.", reserved.0);
+        }
+
+        match entries.iter_mut().find(|(existing, _)| existing.eq_ignore_ascii_case(key))
+        {
+            Some(entry) => entry.1 = value.clone(),
+            None => entries.push((key.clone(), value.clone())),
+        }
+    }
+
+    let header: String = entries
+        .iter()
+        .map(|(key, value)| format!("{}: {}\n", key, yaml_scalar(value)))
+        .collect();
+    format!("---\n{}---\n\n{}", header, content)
+}
+
+fn doc_stats(content: &str) -> (usize, usize, usize)
+{
+    //! Counts lines, words, and characters in generated documentation.
+    //!
+    //! # Inputs
+    //! - `content`: Generated Markdown text.
+    //!
+    //! # Returns
+    //! - `(lines, words, chars)` where words are whitespace-separated and chars are Unicode scalar values.
+    //! - A trailing newline does not count as an extra line.
+
+    let lines = content.lines().count();
+    let words = content.split_whitespace().count();
+    let chars = content.chars().count();
+
+    (lines, words, chars)
+}
+
+fn is_below_min_output(content: &str, min_chars: usize) -> bool
+{
+    //! Checks generated documentation against the `MIN_OUTPUT_CHARS` gate.
+    //!
+    //! # Inputs
+    //! - `content`: Generated Markdown text.
+    //! - `min_chars`: Minimum character count; `0` disables the gate.
+    //!
+    //! # Returns
+    //! - `true` when the trimmed content has fewer than `min_chars` characters.
+
+    min_chars > 0 && content.trim().chars().count() < min_chars
+}
+
+fn resolve_api_version(cli_version: Option<&str>, env_override: Option<&str>, configured: &str) -> String
+{
+    //! Resolves the AI API version used in the request URL.
+    //!
+    //! # Inputs
+    //! - `cli_version`: `--api-version` value.
+    //! - `env_override`: `AI_VERSION_OVERRIDE` value.
+    //! - `configured`: `AI_VERSION` from `config/ai_model.env`.
+    //!
+    //! # Returns
+    //! - The first non-blank value in that order, trimmed.
+
+    [cli_version, env_override]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|version| !version.is_empty())
+        .unwrap_or(configured.trim())
+        .to_string()
+}
+
+fn resolve_seed(cli_seed: Option<u64>, env_seed: Option<&str>) -> Option<u64>
+{
+    //! Resolves the sampling seed sent in `ChatRequest.seed`.
+    //!
+    //! # Inputs
+    //! - `cli_seed`: `--seed` value.
+    //! - `env_seed`: `AI_SEED` value.
+    //!
+    //! # Returns
+    //! - `cli_seed` when set, otherwise `AI_SEED` parsed as a number.
+    //! - `None` when neither is set; an `AI_SEED` that is not a number is ignored with a `[WRN]`.
+
+    if cli_seed.is_some()
+    {
+        return cli_seed;
+    }
+
+    let raw = env_seed.map(str::trim).filter(|value| !value.is_empty())?;
+    match raw.parse::<u64>()
+    {
+        Ok(seed) => Some(seed),
+        Err(_) =>
+        {
+            log_warn!("Invalid AI_SEED '{}'; sending no seed.", raw);
+            None
+        }
+    }
+}
+
+fn serialize_secret<S>(value: &str, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    //! Serializes a Key Vault secret name as `<SECRET>`, or an empty string when unset.
+
+    serializer.serialize_str(if value.trim().is_empty() { "" } else { "<SECRET>" })
+}
+
+fn render_effective_config(cli_args: &CliArgs) -> std::result::Result<String, String>
+{
+    //! Renders the effective configuration for `--show-config`.
+    //!
+    //! # Inputs
+    //! - `cli_args`: Parsed CLI arguments; `--model`, `--api-version` and `--env-file` are applied.
+    //!
+    //! # Returns
+    //! - TOML with the resolved `ABSOLUTE_DOXCER_PATH`, the env files in load order, the resolved
+    //!   model and API version, and `[ai]` and `[env]` tables with `AiEnvParameters` and `EnvParameters`
+    //!   (secret names masked).
+    //! - `Err(String)` when serialization fails.
+    //!
+    //! # Notes
+    //! - Must run after [`load_extra_env_files`]; touching `ENVCONFIG` loads the `config/` files.
+
+    let env_config: &EnvParameters = &ENVCONFIG;
+    let ai_config: &AiEnvParameters = &AI_ENVCONFIG;
+    let config_dir = config_dir_override(env::var("DOXCER_CONFIG_DIR").ok().as_deref()).ok().flatten();
+    let env_files = find_env_paths(config_dir.as_deref())
+        .into_iter()
+        .chain(cli_args.env_files.iter().map(PathBuf::from).filter(|path| path.exists()))
+        .map(|path| path.display().to_string())
+        .collect();
+
+    let effective = EffectiveConfig
+    {
+        absolute_doxcer_path: find_repo_root_path().display().to_string(),
+        env_files,
+        model: resolve_model(cli_args.model.as_deref(), &ai_config.ai_model),
+        api_version: resolve_api_version(
+            cli_args.api_version.as_deref(),
+            env::var("AI_VERSION_OVERRIDE").ok().as_deref(),
+            &ai_config.ai_version,
+        ),
+        ai: ai_config,
+        env: env_config,
+    };
+
+    toml::to_string(&effective)
+        .map_err(|err| format!("[ERR] - Failed to render configuration: {}", err))
+}
+
+fn format_env_var_table(lookup: impl Fn(&str) -> Option<String>) -> String
+{
+    //! Formats [`ENV_VARS`] as a `variable name` / `current value` table.
+    //!
+    //! # Inputs
+    //! - `lookup`: Returns the current value of a variable.
+    //!
+    //! # Returns
+    //! - One aligned line per variable; secrets show `<SET>`, unset variables `<NOT SET>`.
+
+    let width = ENV_VARS
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max("variable name".len());
+
+    let mut lines = vec![format!("{:<width$}  current value", "variable name")];
+    for (name, is_secret) in ENV_VARS
+    {
+        let value = match lookup(name)
+        {
+            None => "<NOT SET>".to_string(),
+            Some(_) if *is_secret => "<SET>".to_string(),
+            Some(value) => value,
+        };
+        lines.push(format!("{:<width$}  {}", name, value));
+    }
+
+    lines.join("\n")
+}
+
+fn list_env_vars<F, C, L>(
+    env_files: &[String],
+    strict: bool,
+    load: F,
+    load_config: C,
+    lookup: L,
+) -> std::result::Result<String, String>
+where
+    F: FnMut(&Path) -> std::result::Result<(), String>,
+    C: FnOnce(),
+    L: Fn(&str) -> Option<String>,
+{
+    //! Loads the env files and builds the `--list-env-vars` table.
+    //!
+    //! # Inputs
+    //! - `env_files`: `--env-file` paths, see [`load_extra_env_files`].
+    //! - `strict`: Whether `--strict-env` is set.
+    //! - `load`: Env file loader ([`load_env_file`] outside tests).
+    //! - `load_config`: Loads the standard `config/` files ([`load_env`] outside tests).
+    //! - `lookup`: Returns the current value of a variable.
+    //!
+    //! # Returns
+    //! - `Ok(String)` with the table from [`format_env_var_table`].
+    //! - `Err(String)` when an `--env-file` cannot be loaded.
+    //!
+    //! # Notes
+    //! - The `--env-file` paths are loaded before `config/` so their values win, as in the other commands.
+
+    load_extra_env_files(env_files, strict, load)?;
+    load_config();
+
+    Ok(format_env_var_table(lookup))
+}
+
+fn language_instruction(code: &str) -> Option<String>
+{
+    //! Builds the output language instruction appended to the system context.
+    //!
+    //! # Inputs
+    //! - `code`: Language code from `--lang` or `OUTPUT_LANGUAGE` (case-insensitive).
+    //!
+    //! # Returns
+    //! - `Some("Write the documentation in <language>.")`; known codes map to a language name,
+    //!   unknown values are passed through as-is.
+    //! - `None` when `code` is blank.
+
+    let code = code.trim();
+    if code.is_empty()
+    {
+        return None;
+    }
+
+    let language = match code.to_ascii_lowercase().as_str()
+    {
+        "en" => "English",
+        "nl" => "Dutch",
+        "de" => "German",
+        "fr" => "French",
+        "es" => "Spanish",
+        "it" => "Italian",
+        "pt" => "Portuguese",
+        _ => code,
+    };
+
+    Some(format!("Write the documentation in {}.", language))
+}
+
+fn check_definition_environment(environment: &str, allow_prod: bool) -> std::result::Result<(), String>
+{
+    //! Safety interlock for the production definitions database.
+    //!
+    //! # Inputs
+    //! - `environment`: `DEFINITION_ENVIRONMENT` label (case-insensitive).
+    //! - `allow_prod`: Whether `--allow-prod` was passed.
+    //!
+    //! # Returns
+    //! - `Ok(())` for any label other than `prod`, or for `prod` with `--allow-prod`.
+    //! - `Err(String)` for `prod` without `--allow-prod`.
+
+    if environment.trim().eq_ignore_ascii_case("prod") && !allow_prod
+    {
+        return Err(
+            "[ERR] - DEFINITION_ENVIRONMENT is 'prod'; pass --allow-prod to query the production definitions database."
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+fn resolve_model(cli_model: Option<&str>, configured: &str) -> String
+{
+    //! Resolves the AI model sent in `ChatRequest.model`.
+    //!
+    //! # Inputs
+    //! - `cli_model`: `--model` value.
+    //! - `configured`: `AI_MODEL` from `config/ai_model.env`.
+    //!
+    //! # Returns
+    //! - The trimmed `--model` value when non-blank, otherwise the configured model.
+
+    cli_model
+        .map(str::trim)
+        .filter(|model| !model.is_empty())
+        .unwrap_or(configured.trim())
+        .to_string()
+}
+
+fn append_context_version(context: &str, version: Option<&str>) -> String
+{
+    //! Appends the `--context-version` marker to the context (system message).
+    //!
+    //! # Inputs
+    //! - `context`: Content of `templates/context.md`.
+    //! - `version`: Version string from `--context-version`.
+    //!
+    //! # Returns
+    //! - `context` followed by `\n\n---\nPrompt version: <version>`.
+    //! - `context` unchanged when `version` is `None` or blank.
+
+    match version.map(str::trim).filter(|version| !version.is_empty())
+    {
+        Some(version) => format!("{}\n\n---\nPrompt version: {}", context, version),
+        None => context.to_string(),
+    }
+}
+
+fn parse_template_version(content: &str) -> Option<String>
+{
+    //! Reads the template version from the first line of a prompt template.
+    //!
+    //! # Inputs
+    //! - `content`: Prompt template content.
+    //!
+    //! # Returns
+    //! - `Some(version)` when the first non-blank line is `<!-- template-version: <version> -->`.
+    //! - `None` when the line is absent or malformed (no closing `-->`, empty version or spaces in the version).
+
+    let first_line = content.lines().find(|line| !line.trim().is_empty())?.trim();
+    let version = first_line
+        .strip_prefix("<!--")?
+        .strip_suffix("-->")?
+        .trim()
+        .strip_prefix("template-version:")?
+        .trim();
+
+    if version.is_empty() || version.contains(char::is_whitespace)
+    {
+        return None;
+    }
+    Some(version.to_string())
+}
+
+fn strip_template_version(content: &str) -> String
+{
+    //! Removes the `<!-- template-version: ... -->` line, see [`parse_template_version`].
+    //!
+    //! # Returns
+    //! - `content` without its leading blank lines and version line, so the marker is not sent to the model.
+    //! - `content` unchanged when it has no valid version line.
+
+    if parse_template_version(content).is_none()
+    {
+        return content.to_string();
+    }
+    let rest = content.trim_start().split_once('\n').map_or("", |(_, rest)| rest);
+    rest.trim_start_matches(['\r', '\n']).to_string()
+}
+
+fn template_version_comment(version: &str) -> String
+{
+    //! Formats the `--template-version-comment` line written at the top of the documentation.
+
+    format!("<!-- template-version: {} -->", version)
+}
+
+fn fetch_remote_context(client: &Client, url: &str) -> std::result::Result<String, DoxcerError>
+{
+    //! Downloads the shared context from `CONTEXT_URL`.
+    //!
+    //! # Inputs
+    //! - `client`: HTTP client; the request uses a [`CONTEXT_FETCH_TIMEOUT_SECS`] timeout.
+    //! - `url`: Context URL.
+    //!
+    //! # Returns
+    //! - `Ok(String)` with the response body.
+    //! - `Err(DoxcerError::Api)` when the request fails, the status is not a success or the body is empty;
+    //!   HTTP errors are available through `source()`.
+
+    let api_error = |context: String, source: Option<reqwest::Error>| DoxcerError::Api { context, source };
+
+    let response = client
+        .get(url)
+        .timeout(Duration::from_secs(CONTEXT_FETCH_TIMEOUT_SECS))
+        .send()
+        .map_err(|err| api_error("context request failed".to_string(), Some(err)))?;
+
+    let status = response.status();
+    if !status.is_success()
+    {
+        return Err(api_error(format!("status {}", status), None));
+    }
+
+    let body = response
+        .text()
+        .map_err(|err| api_error("failed to read context body".to_string(), Some(err)))?;
+    if body.trim().is_empty()
+    {
+        return Err(api_error("empty response".to_string(), None));
+    }
+    Ok(body)
+}
+
+fn load_remote_context<F>(url: &str, cache_path: &Path, fetch: F) -> Option<String>
+where
+    F: Fn(&str) -> std::result::Result<String, String>,
+{
+    //! Loads the shared context from `CONTEXT_URL`, falling back to the last cached copy.
+    //!
+    //! # Inputs
+    //! - `url`: Context URL.
+    //! - `cache_path`: Cache file, see [`find_context_cache_path`].
+    //! - `fetch`: Downloads the URL, normally [`fetch_remote_context`].
+    //!
+    //! # Returns
+    //! - The fetched context, which also replaces the cache.
+    //! - The cached context with a `[WRN]` when the fetch fails.
+    //! - `None` with a `[WRN]` when the fetch fails and there is no cache.
+
+    match fetch(url)
+    {
+        Ok(content) =>
+        {
+            log_success!("Context fetched from {}", url);
+            if let Err(err) = io_utils::atomic_write(cache_path, &content)
+            {
+                log_warn!("Failed to cache remote context at {}: {}", cache_path.display(), err);
+            }
+            Some(content)
+        }
+        Err(err) => match fs::read_to_string(cache_path)
+        {
+            Ok(cached) =>
+            {
+                log_warn!("Failed to fetch context from {} ({}); using cached copy {}.", url, err, cache_path.display());
+                Some(cached)
+            }
+            Err(_) =>
+            {
+                log_warn!("Failed to fetch context from {} ({}) and no cached copy exists.", url, err);
+                None
+            }
+        },
+    }
+}
+
+fn build_messages(
+    context: &str,
+    prompt: &str,
+    context_format: ContextFormat,
+    system_role: &str,
+    user_role: &str,
+) -> Vec<Message>
+{
+    //! Builds the chat `messages` array.
+    //!
+    //! # Inputs
+    //! - `context`: Content of `templates/context.md`.
+    //! - `prompt`: User prompt.
+    //! - `context_format`: Where the context is placed.
+    //! - `system_role`: Role of the context message (`AI_SYSTEM_ROLE`, default `system`).
+    //! - `user_role`: Role of the prompt message (`AI_USER_ROLE`, default `user`).
+    //!
+    //! # Returns
+    //! - `System`: a system message with the context and a user message with the prompt.
+    //! - `User`: a single user message with the context prepended to the prompt.
+    //! - `Both`: the system message plus a user message with the context prepended.
+
+    let system_message = Message { role: system_role.to_string(), content: context.to_string() };
+    let inline_user_message = Message { role: user_role.to_string(), content: format!("{}\n\n{}", context, prompt) };
+
+    match context_format
+    {
+        ContextFormat::System => vec![
+            system_message,
+            Message { role: user_role.to_string(), content: prompt.to_string() },
+        ],
+        ContextFormat::User => vec![inline_user_message],
+        ContextFormat::Both => vec![system_message, inline_user_message],
+    }
+}
+
+fn parse_extra_headers(raw: &str) -> HeaderMap
+{
+    //! Parses `AI_EXTRA_HEADERS` into request headers.
+    //!
+    //! # Inputs
+    //! - `raw`: Header list in `key1=val1;key2=val2` form.
+    //!
+    //! # Returns
+    //! - Header map with every well-formed entry; empty when `raw` is blank.
+    //!
+    //! # Notes
+    //! - Entries without `=`, with an empty or invalid name, or with an invalid value are skipped with a `[WRN]`.
+    //! - [`RESERVED_API_HEADERS`] (`api-key`, `Content-Type`, in any case) are skipped with a `[WRN]`,
+    //!   so the Key Vault API key cannot be replaced from the env files.
+
+    let mut headers = HeaderMap::new();
+    for entry in raw.split(';').map(str::trim).filter(|entry| !entry.is_empty())
+    {
+        let Some((name, value)) = entry.split_once('=')
+        else
+        {
+            log_warn!("Skipping AI_EXTRA_HEADERS entry '{}': expected key=value.", entry);
+            continue;
+        };
+
+        match (HeaderName::from_bytes(name.trim().as_bytes()), HeaderValue::from_str(value.trim()))
+        {
+            (Ok(name), _) if RESERVED_API_HEADERS.contains(&name.as_str()) =>
+            {
+                log_warn!("Skipping AI_EXTRA_HEADERS header '{}': doxcer sets it itself.", name);
+            }
+            (Ok(name), Ok(value)) =>
+            {
+                headers.insert(name, value);
+            }
+            (Err(_), _) => log_warn!("Skipping AI_EXTRA_HEADERS entry '{}': invalid header name.", entry),
+            (_, Err(_)) => log_warn!("Skipping AI_EXTRA_HEADERS entry '{}': invalid header value.", entry),
+        }
+    }
+    headers
+}
+
+fn describe_api_error(body_text: &str) -> Option<String>
+{
+    //! Extracts the API's own error message from an error JSON body.
+    //!
+    //! # Inputs
+    //! - `body_text`: Raw API response body, from a 2xx response that is not a chat completion
+    //!   or from a non-2xx response (`{"error":{"code","message"}}`).
+    //!
+    //! # Returns
+    //! - `Some(String)` with an `[ERR]` line when the body matches the `ApiError` shape.
+    //! - `None` otherwise.
+
+    let api_error = serde_json::from_str::<ApiError>(body_text).ok()?;
+    let code = match api_error.error.code
+    {
+        Some(serde_json::Value::String(code)) => code,
+        Some(serde_json::Value::Null) | None => "unknown".to_string(),
+        Some(other) => other.to_string(),
+    };
+
+    Some(format!("[ERR] - API returned error '{}': {}", code, api_error.error.message))
+}
+
+fn estimate_tokens(text: &str) -> usize
+{
+    //! Estimates the token count of a prompt.
+    //!
+    //! # Inputs
+    //! - `text`: Prompt text.
+    //!
+    //! # Returns
+    //! - Rough token estimate, see [`estimate_tokens_for_chars`].
+
+    estimate_tokens_for_chars(text.chars().count())
+}
+
+fn estimate_tokens_for_chars(chars: usize) -> usize
+{
+    //! Estimates the token count of a text from its character count.
+    //!
+    //! # Inputs
+    //! - `chars`: Number of characters.
+    //!
+    //! # Returns
+    //! - Rough token estimate using four characters per token, rounded up.
+
+    chars.div_ceil(4)
+}
+
+fn format_run_stats(stats: &RunStats) -> String
+{
+    //! Formats run statistics as a block of `[INF]` lines.
+    //!
+    //! # Inputs
+    //! - `stats`: Collected run statistics.
+    //!
+    //! # Returns
+    //! - Multi-line summary; steps that did not run are shown as `n/a`.
+
+    let api_response = stats
+        .api_response_ms
+        .map_or_else(|| "n/a".to_string(), |ms| format!("{} ms", ms));
+    let output_size = stats
+        .output_bytes
+        .map_or_else(|| "n/a".to_string(), |bytes| format!("{} bytes", bytes));
+    let model = if stats.model.is_empty() { "n/a" } else { stats.model.as_str() };
+
+    format!(
+        "[INF] - Run statistics:\n\
+         [INF] -   Input file size:         {} bytes\n\
+         [INF] -   Cleaned notebook lines:  {}\n\
+         [INF] -   Estimated prompt tokens: {}\n\
+         [INF] -   Definition rows:         {}\n\
+         [INF] -   Model:                   {}\n\
+         [INF] -   API response time:       {}\n\
+         [INF] -   Output file size:        {}",
+        stats.input_bytes,
+        stats.cleaned_lines,
+        stats.estimated_prompt_tokens,
+        stats.definition_rows,
+        model,
+        api_response,
+        output_size
+    )
+}
+
+fn format_run_trace(trace: &RunTrace) -> String
+{
+    //! Formats the pipeline trace as a block of `[INF]` lines.
+    //!
+    //! # Inputs
+    //! - `trace`: Collected run decisions.
+    //!
+    //! # Returns
+    //! - Multi-line trace; values that were not reached are shown as `n/a`.
+    //! - The `--context-version` and template version lines only when a version was set.
+
+    fn or_na(value: &str) -> &str
+    {
+        if value.is_empty() { "n/a" } else { value }
+    }
+
+    let prompt_size = if trace.prompt_chars == 0
+    {
+        "n/a".to_string()
+    }
+    else
+    {
+        format!("{} chars (~{} tokens)", trace.prompt_chars, estimate_tokens_for_chars(trace.prompt_chars))
+    };
+    let response_size = trace
+        .response_chars
+        .map_or_else(|| "n/a".to_string(), |chars| format!("{} chars", chars));
+
+    let mut formatted = format!(
+        "[INF] - Run trace:\n\
+         [INF] -   Profile:       {}\n\
+         [INF] -   Template:      {}\n\
+         [INF] -   Definitions:   {}\n\
+         [INF] -   Prompt size:   {}\n\
+         [INF] -   Model:         {}\n\
+         [INF] -   Response size: {}",
+        or_na(&trace.profile),
+        or_na(&trace.template),
+        or_na(&trace.definitions),
+        prompt_size,
+        or_na(&trace.model),
+        response_size
+    );
+    if let Some(version) = &trace.context_version
+    {
+        formatted.push_str(&format!("\n[INF] -   Context ver.:  {}", version));
+    }
+    if let Some(version) = &trace.template_version
+    {
+        formatted.push_str(&format!("\n[INF] -   Template ver.: {}", version));
+    }
+    formatted
+}
+
+fn split_command_line(command: &str) -> std::result::Result<Vec<String>, String>
+{
+    //! Splits a command line into program and arguments, without a shell.
+    //!
+    //! # Inputs
+    //! - `command`: Command line, e.g. `"C:\Program Files\fmt.exe" --wrap 'no limit'`.
+    //!
+    //! # Returns
+    //! - `Ok(Vec<String>)` with one entry per argument; whitespace outside quotes separates arguments.
+    //! - `Err(String)` when a quote is not closed.
+    //!
+    //! # Notes
+    //! - Single quotes keep their content as-is; inside double quotes `\"` is a literal `"`.
+    //! - Backslashes are literal elsewhere, so Windows paths need no escaping.
+
+    let mut args: Vec<String> = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next()
+    {
+        match c
+        {
+            '\'' | '"' =>
+            {
+                let arg = current.get_or_insert_with(String::new);
+                loop
+                {
+                    match chars.next()
+                    {
+                        Some(inner) if inner == c => break,
+                        Some('\\') if c == '"' && chars.peek() == Some(&'"') =>
+                        {
+                            arg.push('"');
+                            chars.next();
+                        }
+                        Some(inner) => arg.push(inner),
+                        None => return Err(format!("unclosed {} quote in '{}'", c, command)),
+                    }
+                }
+            }
+            c if c.is_whitespace() =>
+            {
+                args.extend(current.take());
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+
+    Ok(args)
+}
+
+fn pipe_through_formatter(command: &str, content: &str) -> std::result::Result<String, String>
+{
+    //! Pipes generated documentation through an external formatter command.
+    //!
+    //! # Inputs
+    //! - `command`: Formatter program and arguments, split with [`split_command_line`] (no shell).
+    //! - `content`: Markdown written to the formatter's stdin.
+    //!
+    //! # Returns
+    //! - `Ok(String)` with the formatter's stdout.
+    //! - `Err(String)` when the command is empty or has an unclosed quote, cannot be started, exits non-zero,
+    //!   or prints non-UTF-8.
+
+    let parts = split_command_line(command)?;
+    let Some((program, args)) = parts.split_first()
+    else
+    {
+        return Err("formatter command is empty".to_string());
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("failed to start '{}': {}", program, err))?;
+
+    // Write stdin on a separate thread so a formatter that streams output cannot deadlock on full pipes.
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| "failed to open formatter stdin".to_string())?;
+    let input = content.to_string();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let output = child
+        .wait_with_output()
+        .map_err(|err| format!("failed to wait for '{}': {}", program, err))?;
+    writer
+        .join()
+        .map_err(|_| "formatter stdin writer panicked".to_string())?
+        .map_err(|err| format!("failed to write to '{}': {}", program, err))?;
+
+    if !output.status.success()
+    {
+        return Err(format!(
+            "'{}' exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    String::from_utf8(output.stdout)
+        .map_err(|err| format!("'{}' produced non-UTF-8 output: {}", program, err))
+}
+
+fn run_git(repo_root: &Path, args: &[&str]) -> std::result::Result<String, String>
+{
+    //! Runs `git` in the repository root and returns its stdout.
+    //!
+    //! # Inputs
+    //! - `repo_root`: Working directory for the command.
+    //! - `args`: Arguments passed to `git`.
+    //!
+    //! # Returns
+    //! - `Ok(String)` with stdout when git exits successfully.
+    //! - `Err(String)` when git cannot be started, exits non-zero, or prints non-UTF-8.
+
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(repo_root)
+        .output()
+        .map_err(|err| format!("failed to start git: {}", err))?;
+
+    if !output.status.success()
+    {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    String::from_utf8(output.stdout).map_err(|err| format!("git produced non-UTF-8 output: {}", err))
+}
+
+fn git_head_content<F>(repo_root: &Path, path: &Path, run: F) -> Option<String>
+where
+    F: Fn(&Path, &[&str]) -> std::result::Result<String, String>,
+{
+    //! Reads the version of a file committed in `HEAD`.
+    //!
+    //! # Inputs
+    //! - `repo_root`: Doxcer repository root; git runs here and `path` is made relative to it.
+    //! - `path`: File to look up.
+    //! - `run`: Command runner (`run_git` outside tests).
+    //!
+    //! # Returns
+    //! - `Some(String)` with the committed content.
+    //! - `None` when `path` is outside `repo_root`, git is unavailable, or the file is not in `HEAD`;
+    //!   an `[INF]` line explains why.
+    //!
+    //! # Notes
+    //! - Uses `git show HEAD:./<path>`, which git resolves against the working directory, so the lookup
+    //!   also works when doxcer lives in a subdirectory of a larger git repository.
+
+    let Ok(relative) = path.strip_prefix(repo_root)
+    else
+    {
+        log_info!("{} is outside the repository; skipping diff against HEAD.", path.display());
+        return None;
+    };
+
+    let relative = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let spec = format!("HEAD:./{}", relative);
+
+    match run(repo_root, &["show", &spec])
+    {
+        Ok(content) => Some(content),
+        Err(err) =>
+        {
+            log_info!("No previous version of {} in HEAD; skipping diff ({}).", relative, err);
+            None
+        }
+    }
+}
+
+fn determine_output_names(input_path: &Path) -> (String, String)
+{
+    //! Derives output names from the input notebook path.
+    //!
+    //! # Inputs
+    //! - `input_path`: Input notebook path.
+    //!
+    //! # Returns
+    //! - `(output_file_name, output_file_name_ext)`.
+    //! - Uses parent directory name when file is `notebook-content.py` (`.Notebook` suffix trimmed).
+    //! - Uses parent directory name when file is `pipeline-content.json` (`.DataPipeline` suffix trimmed).
+    //! - Uses the file stem otherwise, e.g. `experiment` for `experiment.ipynb` or `report.Rmd`.
+    //!
+    //! # Panics
+    //! - If `input_path` has no filename.
+
+    let filename_os = input_path
+        .file_name()
+        .expect("[ERR] - Input file has no filename");
+
+    let filename = filename_os.to_string_lossy();
+
+    let (fallback_name, folder_suffix, extension) = match filename.as_ref()
+    {
+        "notebook-content.py" => ("notebook-content", ".Notebook", "py"),
+        "pipeline-content.json" => ("pipeline-content", ".DataPipeline", "json"),
+        _ =>
+        {
+            let output_file_name = input_path
+                .file_stem()
+                .unwrap_or(filename_os)
+                .to_string_lossy()
+                .to_string();
+
+            let output_file_name_ext = filename.to_string();
+
+            return (output_file_name, output_file_name_ext);
+        }
+    };
+
+    // Special case: Fabric item content files are named after their parent folder
+    let parent_dir_name = input_path
+        .parent()
+        .and_then(|p| p.file_name())
+        .map(|os| os.to_string_lossy().to_string())
+        .unwrap_or_else(|| fallback_name.to_string());
+
+    let output_file_name = parent_dir_name
+        .trim_end_matches(folder_suffix)
+        .to_string();
+
+    let output_file_name_ext = format!("{}.{}", output_file_name, extension);
+
+    (output_file_name, output_file_name_ext)
+}
+
+fn is_json_input(input_path: &Path) -> bool
+{
+    //! Returns `true` when the input path has a `.json` extension (case-insensitive).
+
+    input_path
+        .extension()
+        .map(|extension| extension.eq_ignore_ascii_case("json"))
+        .unwrap_or(false)
+}
+
+fn resolve_output_names(input_path: &Path, notebook_name: Option<&str>) -> (String, String)
+{
+    //! Resolves output names, preferring an explicit `--notebook-name` override.
+    //!
+    //! # Inputs
+    //! - `input_path`: Input notebook path.
+    //! - `notebook_name`: Optional output name override.
+    //!
+    //! # Returns
+    //! - `(name, name.<input_extension>)` when an override is given (no extension when the input has none).
+    //! - [`determine_output_names`] otherwise.
+
+    let Some(name) = notebook_name
+    else
+    {
+        return determine_output_names(input_path);
+    };
+
+    let output_file_name_ext = match input_path.extension()
+    {
+        Some(extension) => format!("{}.{}", name, extension.to_string_lossy()),
+        None => name.to_string(),
+    };
+
+    (name.to_string(), output_file_name_ext)
+}
+
+fn profile_by_name(name: &str) -> Option<PromptProfile>
+{
+    //! Looks up a profile by name (`powerbi`) or selector (`-powerbi`), case-insensitively.
+
+    let name = name.trim().to_ascii_lowercase();
+    PROMPT_PROFILE_SPECS
+        .iter()
+        .find(|spec| spec.name == name || spec.selector_flags.contains(&name.as_str()))
+        .map(|spec| spec.profile)
+}
+
+fn parse_extension_profiles(raw: &str) -> Vec<(String, PromptProfile)>
+{
+    //! Parses a `DOXCER_EXTENSION_PROFILES` value.
+    //!
+    //! # Inputs
+    //! - `raw`: Mappings as `ext=profile` separated by `,` or `;` (e.g. `pbip=powerbi,dbc=databricks`).
+    //!
+    //! # Returns
+    //! - `(extension, profile)` pairs with lowercase extensions without the leading dot.
+    //!
+    //! # Notes
+    //! - Entries without `=` or with an unknown profile are skipped with a `[WRN]`.
+
+    let mut mappings = Vec::new();
+    for entry in raw.split([',', ';']).map(str::trim).filter(|entry| !entry.is_empty())
+    {
+        let Some((extension, name)) = entry
+            .split_once('=')
+            .map(|(extension, name)| (extension.trim().trim_start_matches('.').to_ascii_lowercase(), name))
+            .filter(|(extension, _)| !extension.is_empty())
+        else
+        {
+            log_warn!("Skipping DOXCER_EXTENSION_PROFILES entry '{}': expected ext=profile.", entry);
+            continue;
+        };
+
+        match profile_by_name(name)
+        {
+            Some(profile) => mappings.push((extension, profile)),
+            None => log_warn!("Skipping DOXCER_EXTENSION_PROFILES entry '{}': unknown profile.", entry),
+        }
+    }
+    mappings
+}
+
+fn profile_from_extension(extension: &str) -> Option<PromptProfile>
+{
+    //! Maps a file extension to a prompt profile.
+    //!
+    //! # Inputs
+    //! - `extension`: File extension, with or without the leading dot (case-insensitive).
+    //!
+    //! # Returns
+    //! - The profile from `DOXCER_EXTENSION_PROFILES` when listed there, else from
+    //!   `DEFAULT_EXTENSION_PROFILES` (`.pbip`/`.pbir`/`.pbism`/`.tmdl` → Power BI, `.dbc` → Databricks).
+    //! - `None` for unmapped extensions.
+
+    let extension = extension.trim().trim_start_matches('.').to_ascii_lowercase();
+    let configured = env::var("DOXCER_EXTENSION_PROFILES")
+        .map(|raw| parse_extension_profiles(&raw))
+        .unwrap_or_default();
+
+    configured
+        .iter()
+        .find(|(mapped, _)| *mapped == extension)
+        .map(|(_, profile)| *profile)
+        .or_else(||
+        {
+            DEFAULT_EXTENSION_PROFILES
+                .iter()
+                .find(|(mapped, _)| *mapped == extension)
+                .map(|(_, profile)| *profile)
+        })
+}
+
+fn resolve_active_profile(cli_args: &CliArgs, source: &str) -> PromptProfile
+{
+    //! Returns the profile to use for a run.
+    //!
+    //! # Inputs
+    //! - `cli_args`: Parsed CLI arguments; `file_path` extension is checked with [`profile_from_extension`].
+    //! - `source`: Raw notebook source, inspected with `--auto-profile`.
+    //!
+    //! # Returns
+    //! - The explicit selector profile when one was given, `-default` included.
+    //! - The mapped profile for the input file extension when no selector was given.
+    //! - The detected platform with `--auto-profile` and no selector.
+    //! - `PromptProfile::Default` when nothing was selected or detected.
+
+    if cli_args.profile_selected
+    {
+        return cli_args.profile;
+    }
+
+    let extension = Path::new(&cli_args.file_path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if let Some(profile) = profile_from_extension(&extension)
+    {
+        log_info!(
+            "Selected '{}' profile from the '.{}' extension.",
+            profile_selector_name(profile),
+            extension
+        );
+        return profile;
+    }
+
+    if !cli_args.auto_profile
+    {
+        return PromptProfile::Default;
+    }
+
+    match notebook_analysis::detect_notebook_platform(source)
+    {
+        Some(profile) =>
+        {
+            log_info!("Auto-detected '{}' profile from notebook source.", profile_selector_name(profile));
+            profile
+        }
+        None =>
+        {
+            log_info!("No platform detected from notebook source; using 'default' profile.");
+            PromptProfile::Default
+        }
+    }
+}
+
+fn notebook_comment_prefixes(source: &str, profile: PromptProfile, input_path: &Path) -> &'static [&'static str]
+{
+    //! Returns the line comment prefixes used to recognise notebook markers.
+    //!
+    //! # Notes
+    //! - Follows the language from [`notebook_analysis::detect_language`];
+    //!   `PromptProfile::Scala` always uses Scala comment prefixes.
+
+    let language = if profile == PromptProfile::Scala
+    {
+        notebook_analysis::Language::Scala
+    }
+    else
+    {
+        notebook_analysis::detect_language(source, input_path)
+    };
+    language.comment_prefixes()
+}
+
+fn clean_notebook(
+    source: &str,
+    profile: PromptProfile,
+    input_path: &Path,
+    collapse: &CollapseConfig,
+    adf_json: bool,
+    max_cell_chars: Option<usize>,
+) -> String
+{
+    //! Applies the profile-specific cleaning steps to raw notebook source.
+    //!
+    //! # Inputs
+    //! - `source`: Raw notebook source.
+    //! - `profile`: Active prompt profile.
+    //! - `input_path`: Input path, used to detect `.json` inputs.
+    //! - `collapse`: Blank line rules, see [`collapse_blank_lines`].
+    //! - `adf_json`: Whether `--adf-json` is set.
+    //! - `max_cell_chars`: Per-cell size cap resolved from `MAX_CELL_CHARS`, see [`max_cell_chars`].
+    //!
+    //! # Returns
+    //! - Cleaned source with metadata removed and blank lines collapsed.
+    //! - With `adf_json`, `.json` inputs use [`notebook_formats::strip_adf_metadata`], falling back to the raw JSON with a `[WRN]`.
+    //! - With `STRIP_LEADING_COMMENT_BLOCK=true`, a leading comment header is removed as well.
+    //! - With `max_cell_chars`, oversized cells are cut first, see [`truncate_large_cells`].
+    //! - Comment prefixes come from [`notebook_comment_prefixes`].
+    //! - With `collapse.preserve_line_endings`, the result uses the predominant line ending of the raw `source`.
+
+    let comment_prefixes = notebook_comment_prefixes(source, profile, input_path);
+    // Capping joins cells with LF, so the line ending is detected before any rewrite.
+    let line_ending = predominant_line_ending(source);
+
+    let capped_source = match max_cell_chars
+    {
+        Some(limit) if !is_json_input(input_path) => truncate_large_cells(source, limit, comment_prefixes),
+        _ => source.to_string(),
+    };
+    let source = capped_source.as_str();
+
+    let stripped = if adf_json && is_json_input(input_path)
+    {
+        match notebook_formats::strip_adf_metadata(source)
+        {
+            Ok(stripped) => stripped,
+            Err(err) =>
+            {
+                log_error!("{}", err);
+                log_warn!("Sending the Data Factory JSON without metadata stripping.");
+                source.to_string()
+            }
+        }
+    }
+    else
+    {
+        strip_notebook_metadata(source, comment_prefixes, profile)
+    };
+
+    let collapsed = collapse_blank_lines(&stripped, collapse);
+    let cleaned = if env_flag_enabled("STRIP_LEADING_COMMENT_BLOCK") && !is_json_input(input_path)
+    {
+        strip_leading_comment_block(&collapsed, comment_prefixes)
+    }
+    else
+    {
+        collapsed
+    };
+
+    // Metadata stripping joins lines with LF, so the original line ending is restored on the final text.
+    if collapse.preserve_line_endings
+    {
+        with_line_ending(&cleaned, line_ending)
+    }
+    else
+    {
+        cleaned
+    }
+}
+
+
+// ----------------------------
+// Runtime
+// ----------------------------
+
+fn run(args: &[String]) -> i32
+{
+    //! Parses CLI arguments and dispatches the selected command.
+    //!
+    //! # Inputs
+    //! - `args`: Raw process arguments including executable name.
+    //!
+    //! # Returns
+    //! - Process exit code.
+    //!
+    //! # Notes
+    //! - `--help` and the commands handled by [`run_local_command`] return before `ENVCONFIG`
+    //!   is touched, so they never require the env files.
+
+    if is_help_requested(args)
+    {
+        print_usage();
+        return 0;
+    }
+
+    let cli_args = match parse_cli_args(args)
+    {
+        Ok(parsed) => parsed,
+        Err(err) =>
+        {
+            log_error!("{}", err);
+            print_usage();
+            return 1;
+        }
+    };
+
+    if let Some(exit_code) = run_local_command(&cli_args)
+    {
+        return exit_code;
+    }
+
+    match cli_args.command
+    {
+        CliCommand::Version | CliCommand::ListProfiles | CliCommand::Clean =>
+        {
+            unreachable!("local commands are handled by run_local_command")
+        }
+        CliCommand::ListEnvVars =>
+        {
+            match list_env_vars(&cli_args.env_files, cli_args.strict_env, load_env_file, load_env, |name| env::var(name).ok())
+            {
+                Ok(table) =>
+                {
+                    println!("{}", table);
+                    0
+                }
+                Err(err) =>
+                {
+                    log_error!("{}", err);
+                    1
+                }
+            }
+        }
+        CliCommand::ShowConfig =>
+        {
+            if let Err(err) = load_extra_env_files(&cli_args.env_files, cli_args.strict_env, load_env_file)
+            {
+                log_error!("{}", err);
+                return 1;
+            }
+
+            match render_effective_config(&cli_args)
+            {
+                Ok(rendered) =>
+                {
+                    println!("{}", rendered);
+                    0
+                }
+                Err(err) =>
+                {
+                    log_error!("{}", err);
+                    1
+                }
+            }
+        }
+        CliCommand::DefinitionsOnly =>
+        {
+            // Stdout carries only the definitions table, so progress lines go to stderr.
+            LOGGER.set_info_to_stderr(true);
+            if let Err(err) = load_extra_env_files(&cli_args.env_files, cli_args.strict_env, load_env_file)
+            {
+                log_error!("{}", err);
+                return 1;
+            }
+
+            run_definitions_only(&cli_args, &ENVCONFIG)
+        }
+        CliCommand::Generate =>
+        {
+            if let Err(err) = load_extra_env_files(&cli_args.env_files, cli_args.strict_env, load_env_file)
+            {
+                log_error!("{}", err);
+                return 1;
+            }
+
+            let input_path = Path::new(&cli_args.file_path);
+            let exit_code = if input_path.is_dir() && !notebook_formats::is_part_file_dir(input_path)
+            {
+                run_batch(&cli_args, input_path)
+            }
+            else if cli_args.retry_failures
+            {
+                log_error!("[ERR] - --retry-failures requires a batch directory, got '{}'.", cli_args.file_path);
+                return 1;
+            }
+            else
+            {
+                let mut run_stats = RunStats::default();
+                let mut run_trace = RunTrace::default();
+                let outcome = generate_documentation(&cli_args, &mut run_stats, &mut run_trace);
+                report_run(&cli_args, &run_stats, &run_trace);
+                outcome_exit_code(outcome)
+            };
+
+            apply_fail_on_warning(exit_code, cli_args.fail_on_warning, LOGGER.warning_count())
+        }
+    }
+}
+
+fn run_local_command(cli_args: &CliArgs) -> Option<i32>
+{
+    //! Runs a command that needs no env files.
+    //!
+    //! # Inputs
+    //! - `cli_args`: Parsed CLI arguments.
+    //!
+    //! # Returns
+    //! - `Some(exit_code)` for `--version`, `--list-profiles` and `--clean`.
+    //! - `None` for commands that read the env files; [`run`] dispatches those.
+    //!
+    //! # Notes
+    //! - Nothing here reads `ENVCONFIG` or `AI_ENVCONFIG`.
+
+    match cli_args.command
+    {
+        CliCommand::Version =>
+        {
+            println!("doxcer {}", env!("CARGO_PKG_VERSION"));
+            Some(0)
+        }
+        CliCommand::ListProfiles =>
+        {
+            for spec in PROMPT_PROFILE_SPECS
+            {
+                println!(
+                    "{:<12} {:<14} {}_prompt.md",
+                    spec.name,
+                    spec.selector_flags.first().copied().unwrap_or("(none)"),
+                    spec.template_stem
+                );
+            }
+            Some(0)
+        }
+        CliCommand::Clean => Some(run_clean(cli_args)),
+        CliCommand::ListEnvVars | CliCommand::ShowConfig | CliCommand::DefinitionsOnly | CliCommand::Generate => None,
+    }
+}
+
+fn report_run(cli_args: &CliArgs, stats: &RunStats, trace: &RunTrace)
+{
+    //! Prints the `--explain` trace and `--stats` summary for one notebook when requested.
+
+    LOGGER.suspend(||
+    {
+        if cli_args.explain
+        {
+            println!("{}", format_run_trace(trace));
+        }
+        if cli_args.stats
+        {
+            eprintln!("{}", format_run_stats(stats));
+        }
+    });
+}
+
+fn apply_fail_on_warning(exit_code: i32, fail_on_warning: bool, warning_count: usize) -> i32
+{
+    //! Applies `--fail-on-warning` to a run's exit code.
+    //!
+    //! # Inputs
+    //! - `exit_code`: Exit code of the completed run.
+    //! - `fail_on_warning`: Whether `--fail-on-warning` was passed.
+    //! - `warning_count`: Number of `[WRN]` lines emitted.
+    //!
+    //! # Returns
+    //! - `7` when the run succeeded but warnings were emitted with `--fail-on-warning`.
+    //! - `exit_code` otherwise.
+
+    if exit_code == EXIT_SUCCESS && fail_on_warning && warning_count > 0
+    {
+        log_error!("[ERR] - {} warning(s) emitted with --fail-on-warning.", warning_count);
+        EXIT_WARNINGS
+    }
+    else
+    {
+        exit_code
+    }
+}
+
+fn check_input_size(size: u64, max_input_bytes: Option<u64>) -> std::result::Result<(), String>
+{
+    //! Checks a notebook's raw size against `--max-input-bytes`.
+    //!
+    //! # Inputs
+    //! - `size`: Input size in bytes: the file size, or the summed part file sizes of a part file directory.
+    //! - `max_input_bytes`: Limit, `None` when no limit is configured.
+    //!
+    //! # Returns
+    //! - `Ok(())` when there is no limit or `size` is at most the limit.
+    //! - `Err(String)` with an `[ERR]` message otherwise.
+
+    match max_input_bytes
+    {
+        Some(limit) if size > limit => Err(format!(
+            "[ERR] - Input notebook is {} bytes, above the --max-input-bytes limit of {} bytes. \
+             Split the notebook or raise the limit if this file is expected.",
+            size,
+            limit
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn resolve_rate_limit_delay(cli_delay_ms: Option<u64>, env_delay_ms: Option<&str>) -> std::result::Result<u64, String>
+{
+    //! Resolves the batch inter-request delay.
+    //!
+    //! # Inputs
+    //! - `cli_delay_ms`: `--rate-limit-delay` value.
+    //! - `env_delay_ms`: `DOXCER_RATE_LIMIT_DELAY_MS` value.
+    //!
+    //! # Returns
+    //! - The CLI value, else the env value, else `0` (no delay).
+    //! - `Err(String)` when the env value is not a non-negative number.
+
+    match (cli_delay_ms, env_delay_ms.map(str::trim).filter(|raw| !raw.is_empty()))
+    {
+        (Some(delay_ms), _) => Ok(delay_ms),
+        (None, Some(raw)) => raw
+            .parse::<u64>()
+            .map_err(|_| format!("[ERR] - Invalid DOXCER_RATE_LIMIT_DELAY_MS '{}'.", raw)),
+        (None, None) => Ok(0),
+    }
+}
+
+fn resolve_akv_options(
+    cli_attempts: Option<u32>,
+    cli_timeout_secs: Option<u64>,
+    env_attempts: Option<&str>,
+    env_timeout_secs: Option<&str>,
+) -> std::result::Result<KeyVaultOptions, String>
+{
+    //! Resolves the retry attempts and per-attempt timeout for Key Vault secret lookups.
+    //!
+    //! # Inputs
+    //! - `cli_attempts`, `cli_timeout_secs`: `--akv-retry-attempts` / `--akv-timeout` values.
+    //! - `env_attempts`, `env_timeout_secs`: `AKV_RETRY_ATTEMPTS` / `AKV_TIMEOUT_SECS` values.
+    //!
+    //! # Returns
+    //! - `Ok(KeyVaultOptions)` with CLI values first, then env values, then `3` attempts and a `30` second timeout.
+    //! - `Err(String)` when an env value is not a number or is 0.
+
+    let attempts = match (cli_attempts, env_attempts.map(str::trim).filter(|raw| !raw.is_empty()))
+    {
+        (Some(attempts), _) => attempts,
+        (None, Some(raw)) => raw
+            .parse::<u32>()
+            .ok()
+            .filter(|attempts| *attempts > 0)
+            .ok_or_else(|| format!("[ERR] - Invalid AKV_RETRY_ATTEMPTS '{}'. Expected a number of at least 1.", raw))?,
+        (None, None) => retry::DEFAULT_RETRY_ATTEMPTS,
+    };
+
+    let timeout_secs = match (cli_timeout_secs, env_timeout_secs.map(str::trim).filter(|raw| !raw.is_empty()))
+    {
+        (Some(timeout_secs), _) => timeout_secs,
+        (None, Some(raw)) => raw
+            .parse::<u64>()
+            .ok()
+            .filter(|timeout_secs| *timeout_secs > 0)
+            .ok_or_else(|| format!("[ERR] - Invalid AKV_TIMEOUT_SECS '{}'. Expected a number of at least 1.", raw))?,
+        (None, None) => fetch_secrets::DEFAULT_AKV_TIMEOUT_SECS,
+    };
+
+    Ok(KeyVaultOptions
+    {
+        retry_policy: RetryPolicy { attempts, ..RetryPolicy::default() },
+        timeout: Duration::from_secs(timeout_secs),
+    })
+}
+
+fn resolve_definition_timeout(cli_timeout_secs: Option<u64>, env_timeout_secs: Option<&str>) -> std::result::Result<Option<u64>, String>
+{
+    //! Resolves the ODBC query timeout for definition lookups.
+    //!
+    //! # Inputs
+    //! - `cli_timeout_secs`: `--definition-timeout` value.
+    //! - `env_timeout_secs`: `DEFINITION_TIMEOUT_SECS` value.
+    //!
+    //! # Returns
+    //! - The CLI value, else the env value, else `None` (driver default).
+    //! - `Err(String)` when the env value is not a non-negative number.
+    //!
+    //! # Notes
+    //! - `Some(0)` means no timeout.
+
+    match (cli_timeout_secs, env_timeout_secs.map(str::trim).filter(|raw| !raw.is_empty()))
+    {
+        (Some(timeout_secs), _) => Ok(Some(timeout_secs)),
+        (None, Some(raw)) => raw
+            .parse::<u64>()
+            .map(Some)
+            .map_err(|_| format!("[ERR] - Invalid DEFINITION_TIMEOUT_SECS '{}'.", raw)),
+        (None, None) => Ok(None),
+    }
+}
+
+fn resolve_odbc_encrypt(
+    cli_encrypt: Option<OdbcEncrypt>,
+    env_encrypt: Option<&str>,
+    definition_encrypt: bool,
+) -> std::result::Result<OdbcEncrypt, String>
+{
+    //! Resolves the ODBC `Encrypt` mode for definition lookups.
+    //!
+    //! # Inputs
+    //! - `cli_encrypt`: `--odbc-encrypt` value.
+    //! - `env_encrypt`: `ODBC_ENCRYPT` value.
+    //! - `definition_encrypt`: `DEFINITION_ENCRYPT` (`true` = `yes`, `false` = `no`).
+    //!
+    //! # Returns
+    //! - The CLI value, else the `ODBC_ENCRYPT` value, else the `DEFINITION_ENCRYPT` mode.
+    //! - `Err(String)` when `ODBC_ENCRYPT` is not `yes`, `no` or `strict`.
+
+    match (cli_encrypt, env_encrypt.map(str::trim).filter(|raw| !raw.is_empty()))
+    {
+        (Some(encrypt), _) => Ok(encrypt),
+        (None, Some(raw)) => fetch_definitions::parse_odbc_encrypt(raw)
+            .ok_or_else(|| format!("[ERR] - Invalid ODBC_ENCRYPT '{}'. Expected yes, no or strict.", raw)),
+        (None, None) => Ok(if definition_encrypt { OdbcEncrypt::Yes } else { OdbcEncrypt::No }),
+    }
+}
+
+fn resolve_odbc_trust_cert(
+    cli_trust_cert: Option<bool>,
+    env_trust_cert: Option<&str>,
+    definition_trust_cert: bool,
+) -> std::result::Result<bool, String>
+{
+    //! Resolves the ODBC `TrustServerCertificate` setting for definition lookups.
+    //!
+    //! # Inputs
+    //! - `cli_trust_cert`: `--odbc-trust-cert` value.
+    //! - `env_trust_cert`: `ODBC_TRUST_SERVER_CERTIFICATE` value.
+    //! - `definition_trust_cert`: `DEFINITION_TRUST_CERT` value.
+    //!
+    //! # Returns
+    //! - The CLI value, else the `ODBC_TRUST_SERVER_CERTIFICATE` value, else `DEFINITION_TRUST_CERT`.
+    //! - `Err(String)` when `ODBC_TRUST_SERVER_CERTIFICATE` is not a boolean, see [`parse_bool_value`].
+
+    match (cli_trust_cert, env_trust_cert.map(str::trim).filter(|raw| !raw.is_empty()))
+    {
+        (Some(trust_cert), _) => Ok(trust_cert),
+        (None, Some(raw)) => parse_bool_value(raw)
+            .ok_or_else(|| format!("[ERR] - Invalid ODBC_TRUST_SERVER_CERTIFICATE '{}'. Expected true or false.", raw)),
+        (None, None) => Ok(definition_trust_cert),
+    }
+}
+
+fn save_outcome(write_result: Option<&std::io::Result<()>>) -> GenerateOutcome
+{
+    //! Decides the outcome of the save step.
+    //!
+    //! # Inputs
+    //! - `write_result`: Result of writing the documentation file, `None` with `--stdout-only`.
+    //!
+    //! # Returns
+    //! - `Saved` for a successful write, `WriteFailed` for a failed write, `Printed` when saving was skipped.
+
+    match write_result
+    {
+        None => GenerateOutcome::Printed,
+        Some(Ok(())) => GenerateOutcome::Saved,
+        Some(Err(_)) => GenerateOutcome::WriteFailed,
+    }
+}
+
+fn outcome_exit_code(outcome: GenerateOutcome) -> i32
+{
+    //! Maps a single-notebook outcome to the process exit code.
+    //!
+    //! # Returns
+    //! - `0` when the documentation was saved, printed or skipped.
+    //! - `1` for configuration errors, empty or oversized input, a failed documentation write and other failures.
+    //! - `2` for an empty or blocked response, or output below `MIN_OUTPUT_CHARS`.
+    //! - `3` when the API request failed or its response could not be read.
+    //! - `4` when the definitions lookup is blocked or not configured.
+    //! - `5` when the output does not match `--expect-json-schema`.
+    //! - `6` when `--strict` is set and the output misses a `REQUIRED_SECTIONS` heading.
+
+    match outcome
+    {
+        GenerateOutcome::Saved | GenerateOutcome::Printed | GenerateOutcome::Skipped => EXIT_SUCCESS,
+        GenerateOutcome::ConfigError
+        | GenerateOutcome::EmptyInput
+        | GenerateOutcome::InputTooLarge
+        | GenerateOutcome::WriteFailed
+        | GenerateOutcome::Failed => EXIT_CONFIG_ERROR,
+        GenerateOutcome::EmptyResponse | GenerateOutcome::BelowMinimum => EXIT_EMPTY_RESPONSE,
+        GenerateOutcome::ApiError => EXIT_API_ERROR,
+        GenerateOutcome::DefinitionsError => EXIT_DEFINITIONS_ERROR,
+        GenerateOutcome::SchemaMismatch => EXIT_SCHEMA_MISMATCH,
+        GenerateOutcome::MissingSections => EXIT_MISSING_SECTIONS,
+    }
+}
+
+fn run_batch(cli_args: &CliArgs, batch_dir: &Path) -> i32
+{
+    //! Generates documentation for every notebook in a directory.
+    //!
+    //! # Inputs
+    //! - `cli_args`: Parsed CLI arguments; `file_path` is the batch directory.
+    //! - `batch_dir`: Batch directory.
+    //!
+    //! # Returns
+    //! - `0` when every notebook was documented, `1` otherwise.
+    //!
+    //! # Side Effects
+    //! - Rewrites `<batch_dir>/_failures.txt` with the paths that failed (removed when none failed).
+    //! - With `--retry-failures`, only the paths from the existing failure list are processed.
+    //! - With `--rate-limit-delay`, sleeps between a notebook that called the API and the next one.
+    //! - With `--progress`, advances a progress bar on stderr after each notebook.
+
+    let rate_limit_delay_ms = match resolve_rate_limit_delay(
+        cli_args.rate_limit_delay_ms,
+        env::var("DOXCER_RATE_LIMIT_DELAY_MS").ok().as_deref(),
+    )
+    {
+        Ok(delay_ms) => delay_ms,
+        Err(err) =>
+        {
+            log_error!("{}", err);
+            return 1;
+        }
+    };
+
+    let list_path = batch::failure_list_path(batch_dir);
+    let inputs = if cli_args.retry_failures
+    {
+        batch::read_failure_list(&list_path)
+    }
+    else
+    {
+        batch::collect_batch_inputs(batch_dir).map(|collected|
+        {
+            for skipped in &collected.skipped
+            {
+                log_info!("Skipping internal notebook {}.", skipped.display());
+            }
+            collected.notebooks
+        })
+    };
+    let inputs = match inputs
+    {
+        Ok(inputs) => inputs,
+        Err(err) =>
+        {
+            log_error!("[ERR] - Failed to collect batch inputs from {}: {}", batch_dir.display(), err);
+            return 1;
+        }
+    };
+
+    if inputs.is_empty()
+    {
+        log_info!("No notebooks to process in {}.", batch_dir.display());
+        return 0;
+    }
+    let inputs = match cli_args.batch_order
+    {
+        Some(order) => batch::sort_paths(inputs, order, batch::path_info),
+        None => inputs,
+    };
+    if cli_args.notebook_name.is_some()
+    {
+        log_warn!("--notebook-name is ignored in batch mode.");
+    }
+
+    log_info!("Batch run: {} notebook(s) in {}.", inputs.len(), batch_dir.display());
+    let mut failures: Vec<PathBuf> = Vec::new();
+    let mut previous_called_api = false;
+    let progress_bar = cli_args.progress.then(|| batch::progress_bar(inputs.len()));
+    // Log lines are printed above the bar while it is active.
+    LOGGER.set_progress_bar(progress_bar.clone());
+
+    for input in &inputs
+    {
+        if previous_called_api && rate_limit_delay_ms > 0
+        {
+            log_info!("Rate limit delay: waiting {} ms before the next notebook.", rate_limit_delay_ms);
+            thread::sleep(Duration::from_millis(rate_limit_delay_ms));
+        }
+
+        let mut file_args = cli_args.clone();
+        file_args.file_path = input.display().to_string();
+        file_args.notebook_name = None;
+
+        let mut run_stats = RunStats::default();
+        let mut run_trace = RunTrace::default();
+        let outcome = panic::catch_unwind(panic::AssertUnwindSafe(||
+        {
+            generate_documentation(&file_args, &mut run_stats, &mut run_trace)
+        }))
+        .unwrap_or(GenerateOutcome::Failed);
+        previous_called_api = run_stats.api_response_ms.is_some();
+
+        report_run(cli_args, &run_stats, &run_trace);
+        if !matches!(outcome, GenerateOutcome::Saved | GenerateOutcome::Printed | GenerateOutcome::Skipped)
+        {
+            failures.push(input.clone());
+        }
+        if let Some(progress_bar) = &progress_bar
+        {
+            let (stem, _) = determine_output_names(input);
+            progress_bar.set_message(stem);
+            progress_bar.inc(1);
+        }
+    }
+    if let Some(progress_bar) = &progress_bar
+    {
+        progress_bar.finish();
+    }
+    LOGGER.set_progress_bar(None);
+
+    if let Err(err) = batch::write_failure_list(&list_path, &failures)
+    {
+        log_warn!("Failed to update failure list {}: {}", list_path.display(), err);
+    }
+
+    if failures.is_empty()
+    {
+        log_success!("Batch run completed: {} notebook(s) documented.", inputs.len());
+        0
+    }
+    else
+    {
+        log_warn!(
+            "Batch run completed with {} failure(s); see {}.",
+            failures.len(),
+            list_path.display()
+        );
+        1
+    }
+}
+
+fn run_clean(cli_args: &CliArgs) -> i32
+{
+    //! Prints the cleaned notebook source to stdout without loading env files.
+    //!
+    //! # Inputs
+    //! - `cli_args`: Parsed CLI arguments for the `--clean` command.
+    //!
+    //! # Returns
+    //! - `0` when the notebook was read and cleaned.
+    //! - `1` when the notebook cannot be read.
+
+    let input_path = Path::new(&cli_args.file_path);
+    let cleaner = NotebookCleaner::new(cli_args.profile)
+        .with_collapse(collapse_config(cli_args))
+        .with_adf_json(cli_args.adf_json)
+        .with_max_cell_chars(max_cell_chars(env::var("MAX_CELL_CHARS").ok().as_deref()));
+    match Notebook::load(input_path, &cleaner)
+    {
+        Ok(notebook) =>
+        {
+            println!("{}", notebook.cleaned_source);
+            0
+        }
+        Err(err) =>
+        {
+            log_error!("{}", err);
+            1
+        }
+    }
+}
+
+fn run_definitions_only(cli_args: &CliArgs, env_config: &EnvParameters) -> i32
+{
+    //! Prints the definitions table doxcer would embed for one notebook, without calling the AI endpoint.
+    //!
+    //! # Inputs
+    //! - `cli_args`: Parsed CLI arguments for the `--definitions-only` command.
+    //! - `env_config`: Loaded configuration, normally [`ENVCONFIG`].
+    //!
+    //! # Returns
+    //! - `0` when the definitions section was resolved and printed.
+    //! - `1` when the notebook cannot be read or the definitions lookup is not allowed or configured.
+    //!
+    //! # Notes
+    //! - No AI configuration or AI Key Vault secret is required; only the definition DB settings are used,
+    //!   and only the Fabric or Azure names of the configured database must be set.
+    //! - The table is the only stdout output; `run` sends `[INF]` and `[SUC]` lines to stderr for this command.
+
+    let input_path = Path::new(&cli_args.file_path);
+    if input_path.is_dir() && !notebook_formats::is_part_file_dir(input_path)
+    {
+        log_error!("[ERR] - --definitions-only requires a notebook file, got '{}'.", cli_args.file_path);
+        return 1;
+    }
+
+    let notebook = match Notebook::load(input_path, &NotebookCleaner::new(cli_args.profile))
+    {
+        Ok(notebook) => notebook.with_output_name(cli_args.notebook_name.as_deref()),
+        Err(err) =>
+        {
+            log_error!("{}", err);
+            return 1;
+        }
+    };
+    let profile = resolve_active_profile(cli_args, &notebook.original_source);
+    let akv_options = match resolve_akv_options(
+        cli_args.akv_retry_attempts,
+        cli_args.akv_timeout_secs,
+        env::var("AKV_RETRY_ATTEMPTS").ok().as_deref(),
+        env::var("AKV_TIMEOUT_SECS").ok().as_deref(),
+    )
+    {
+        Ok(options) => options,
+        Err(err) =>
+        {
+            log_error!("{}", err);
+            return 1;
+        }
+    };
+
+    let mut stats = RunStats::default();
+    let mut trace = RunTrace::default();
+    match fetch_notebook_definitions(cli_args, env_config, profile, &notebook, akv_options, &mut stats, &mut trace)
+    {
+        Ok(definitions) =>
+        {
+            println!("{}", definitions);
+            0
+        }
+        Err(_) => 1,
+    }
+}
+
+fn publish_to_wiki(client: &Client, page: &str, content: &str, akv_options: &KeyVaultOptions)
+{
+    //! Publishes saved documentation to `WIKI_API_URL` when `PUBLISH_ENABLED=true`.
+    //!
+    //! # Inputs
+    //! - `client`: HTTP client reused from the API call.
+    //! - `page`: Wiki page name (the output stem).
+    //! - `content`: Saved Markdown.
+    //! - `akv_options`: Retries and timeout for the wiki token lookup.
+    //!
+    //! # Notes
+    //! - Failures, including a failed wiki token lookup, are reported as `[ERR]`; the file on disk is kept either way.
+
+    if ENVCONFIG.wiki_api_url.trim().is_empty() || ENVCONFIG.akv_secret_wiki.trim().is_empty()
+    {
+        log_error!("[ERR] - Wiki publishing enabled but WIKI_API_URL or AKV_SECRET_WIKI is missing in env files");
+        return;
+    }
+
+    log_info!("Publishing documentation to wiki page '{}'", page);
+    let token = match fetch_secrets::try_get_secret_from_key_vault_on(
+        KEY_VAULT_RUNTIME.handle(),
+        &ENVCONFIG.akv_base_url,
+        &ENVCONFIG.akv_secret_wiki,
+        &ENVCONFIG.akv_credential,
+        akv_options,
+    )
+    {
+        Ok(token) => token,
+        Err(err) =>
+        {
+            log_error!("{}", err);
+            log_error!("[ERR] - Skipped publishing to wiki page '{}'; the saved file is kept.", page);
+            return;
+        }
+    };
+
+    match publish::publish_doc(client, &ENVCONFIG.wiki_api_url, &token, page, content)
+    {
+        Ok(()) => log_success!("Published documentation to wiki page '{}'", page),
+        Err(err) => log_error!("{}", err),
+    }
+}
+
+fn build_definition_prefix(template: &str, vars: &[(&str, &str)]) -> String
+{
+    //! Builds the definitions lookup prefix from `DEFINITION_PREFIX_TEMPLATE`.
+    //!
+    //! # Inputs
+    //! - `template`: Prefix template, e.g. `{workspace}/{stem}`; blank means `{stem}`.
+    //! - `vars`: Placeholder names and values (`stem`, `workspace`); blank values count as missing.
+    //!
+    //! # Returns
+    //! - Rendered prefix; placeholders without a value are left as-is with a `[WRN]`.
+
+    let template = if template.trim().is_empty() { "{stem}" } else { template.trim() };
+    let known: Vec<(&str, &str)> = vars
+        .iter()
+        .copied()
+        .filter(|(_, value)| !value.trim().is_empty())
+        .collect();
+
+    let (prefix, unresolved) = render_template(template, &known);
+    for placeholder in &unresolved
+    {
+        log_warn!(
+            "Placeholder '{{{}}}' in DEFINITION_PREFIX_TEMPLATE has no value; left as-is.",
+            placeholder
+        );
+    }
+    prefix
+}
+
+fn fetch_notebook_definitions(
+    cli_args: &CliArgs,
+    env_config: &EnvParameters,
+    profile: PromptProfile,
+    notebook: &Notebook,
+    akv_options: KeyVaultOptions,
+    stats: &mut RunStats,
+    trace: &mut RunTrace,
+) -> std::result::Result<String, GenerateOutcome>
+{
+    //! Fetches the definitions section embedded in the prompt for one notebook.
+    //!
+    //! # Inputs
+    //! - `cli_args`: Parsed CLI arguments (`--allow-prod`, `--definition-timeout`, `--sql-param`).
+    //! - `env_config`: Loaded configuration, normally [`ENVCONFIG`]; no AI settings are read.
+    //! - `profile`: Active prompt profile, used for the profile-specific fetch query.
+    //! - `notebook`: Notebook whose output stem fills `{stem}` in the lookup prefix (see [`build_definition_prefix`]).
+    //! - `akv_options`: Retries and timeout for the Key Vault secret lookups.
+    //! - `stats`: Run statistics; `definition_rows` is set on success.
+    //! - `trace`: Run trace; `definitions` records the decision.
+    //!
+    //! # Returns
+    //! - `Ok(String)` with the Markdown definitions table, or an `[INF]` note when none were loaded.
+    //! - `Err(GenerateOutcome::DefinitionsError)` when the definitions environment is blocked or no supported
+    //!   definition DB is configured.
+    //! - `Err(GenerateOutcome::ConfigError)` when the timeout or an ODBC TLS setting is invalid.
+    //!
+    //! # Notes
+    //! - A failed query is reported with `[WRN]` and does not fail the run.
+
+    if env_config.definition_database_enabled
+    {
+        log_info!("Definition table enabled");
+
+        if let Err(err) = check_definition_environment(&env_config.definition_environment, cli_args.allow_prod)
+        {
+            log_error!("{}", err);
+            return Err(GenerateOutcome::DefinitionsError);
+        }
+
+        let fabric_configured = env_config.definition_fabric_database_enabled
+            && !env_config.akv_secret_definition_fabric_endpoint.trim().is_empty()
+            && !env_config.akv_secret_definition_fabric_client_id.trim().is_empty()
+            && !env_config.akv_secret_definition_fabric_password.trim().is_empty()
+            && !env_config.definition_fabric_database.trim().is_empty();
+        let azure_configured = env_config.definition_azure_database_enabled
+            && !env_config.akv_secret_definition_azure_endpoint.trim().is_empty()
+            && !env_config.akv_secret_definition_azure_client_id.trim().is_empty()
+            && !env_config.akv_secret_definition_azure_password.trim().is_empty()
+            && !env_config.definition_azure_database.trim().is_empty();
+        if !fabric_configured && !azure_configured
+        {
+            log_error!("[ERR] - No supported definition DB configured");
+            return Err(GenerateOutcome::DefinitionsError);
+        }
+
+        let odbc_timeout_secs = match resolve_definition_timeout(
+            cli_args.definition_timeout_secs,
+            env::var("DEFINITION_TIMEOUT_SECS").ok().as_deref(),
+        )
+        {
+            Ok(timeout_secs) => timeout_secs,
+            Err(err) =>
+            {
+                log_error!("{}", err);
+                return Err(GenerateOutcome::ConfigError);
+            }
+        };
+        let definition_encrypt = match resolve_odbc_encrypt(
+            cli_args.odbc_encrypt,
+            env::var("ODBC_ENCRYPT").ok().as_deref(),
+            env_config.definition_encrypt,
+        )
+        {
+            Ok(encrypt) => encrypt,
+            Err(err) =>
+            {
+                log_error!("{}", err);
+                return Err(GenerateOutcome::ConfigError);
+            }
+        };
+        let definition_trust_cert = match resolve_odbc_trust_cert(
+            cli_args.odbc_trust_cert,
+            env::var("ODBC_TRUST_SERVER_CERTIFICATE").ok().as_deref(),
+            env_config.definition_trust_cert,
+        )
+        {
+            Ok(trust_cert) => trust_cert,
+            Err(err) =>
+            {
+                log_error!("{}", err);
+                return Err(GenerateOutcome::ConfigError);
+            }
+        };
+        let repo_root = find_repo_root_path();
+        let workspace = env::var("DEFINITION_WORKSPACE").unwrap_or_default();
+        let allow_partial = env_flag_enabled("DEFINITION_ALLOW_PARTIAL");
+        let definition_prefix = build_definition_prefix(
+            &env::var("DEFINITION_PREFIX_TEMPLATE").unwrap_or_default(),
+            &[("stem", notebook.output_stem.as_str()), ("workspace", workspace.as_str())],
+        );
+
+        let definition_query_started_at = Instant::now();
+        let definitions_result = if fabric_configured
+        {
+            log_success!("Fabric Definition DB config found");
+            let fabric_definition_config = FabricDefinitionConfig
+            {
+                repo_root: repo_root.as_path(),
+                profile_name: prompt_profile_spec(profile).name,
+                sql_params: &cli_args.sql_params,
+                akv_base_url: &env_config.akv_base_url,
+                akv_credential: &env_config.akv_credential,
+                akv_options,
+                akv_runtime: KEY_VAULT_RUNTIME.handle(),
+                definition_fabric_database: &env_config.definition_fabric_database,
+                akv_secret_definition_fabric_endpoint: &env_config.akv_secret_definition_fabric_endpoint,
+                akv_secret_definition_fabric_client_id: &env_config.akv_secret_definition_fabric_client_id,
+                akv_secret_definition_fabric_password: &env_config.akv_secret_definition_fabric_password,
+                odbc_batch_size: env_config.odbc_batch_size,
+                odbc_max_byte_size: env_config.odbc_max_byte_size,
+                definition_encrypt,
+                definition_trust_cert,
+                odbc_timeout_secs,
+                param_layout: prompt_profile_spec(profile).definition_params,
+                workspace: &workspace,
+                allow_partial,
+            };
+
+            // Fetch from Fabric SQL on the blocking thread pool, so a panic in the lookup fails only this step
+            log_info!(
+                "Querying Fabric definitions for prefix '{}'",
+                definition_prefix
+            );
+            KEY_VAULT_RUNTIME.block_on(fetch_definitions::fetch_definitions_from_fabric_async(
+                &definition_prefix,
+                &fabric_definition_config,
+            ))
+        }
+        else
+        {
+            log_success!("Azure Definition DB config found");
+            let azure_definition_config = AzureDefinitionConfig
+            {
+                repo_root: repo_root.as_path(),
+                akv_base_url: &env_config.akv_base_url,
+                akv_credential: &env_config.akv_credential,
+                akv_options,
+                akv_runtime: KEY_VAULT_RUNTIME.handle(),
+                definition_azure_database: &env_config.definition_azure_database,
+                akv_secret_definition_azure_endpoint: &env_config.akv_secret_definition_azure_endpoint,
+                akv_secret_definition_azure_client_id: &env_config.akv_secret_definition_azure_client_id,
+                akv_secret_definition_azure_password: &env_config.akv_secret_definition_azure_password,
+                odbc_batch_size: env_config.odbc_batch_size,
+                odbc_max_byte_size: env_config.odbc_max_byte_size,
+                definition_encrypt,
+                definition_trust_cert,
+                odbc_timeout_secs,
+                allow_partial,
+            };
+
+            // Fetch from Azure SQL
+            log_info!(
+                "Querying Azure SQL definitions for prefix '{}'",
+                definition_prefix
+            );
+            fetch_definitions::fetch_definitions_from_azure(&definition_prefix, &azure_definition_config)
+        };
+
+        match definitions_result
+        {
+            Ok(mut table) if !table.columns.is_empty() && !table.rows.is_empty() =>
+            {
+                log_success!(
+                    "Definitions query completed in {:.1}s.",
+                    definition_query_started_at.elapsed().as_secs_f64()
+                );
+                if let Some(column) = &cli_args.exclude_nulls
+                {
+                    let removed = table.filter_nulls(column);
+                    log_info!("Removed {} rows with NULL in column {}", removed, column);
+                }
+                log_success!("Definitions found: {} row(s).", table.rows.len());
+                stats.definition_rows = table.rows.len();
+                trace.definitions = format!("fetched {} row(s)", table.rows.len());
+                Ok(fetch_definitions::format_definitions_as_markdown_table(&table.columns, &table.text_rows()))
+            }
+            Ok(_) =>
+            {
+                log_info!(
+                    "Definitions query completed in {:.1}s.",
+                    definition_query_started_at.elapsed().as_secs_f64()
+                );
+                log_info!("No definitions found for this notebook.");
+                trace.definitions = "fetched, no rows".to_string();
+                Ok("[INF] - No definitions loaded (query returned no rows).".to_string())
+            }
+            Err(e) =>
+            {
+                log_warn!(
+                    "Definitions query failed after {:.1}s: {e}",
+                    definition_query_started_at.elapsed().as_secs_f64()
+                );
+                trace.definitions = "query failed".to_string();
+                Ok("[INF] - No definitions loaded (query failed).".to_string())
+            }
+        }
+    }
+    else
+    {
+        log_info!("Definition database disabled");
+        trace.definitions = "disabled".to_string();
+        Ok("[INF] - Definition database disabled.".to_string())
+    }
+}
+
+fn generate_documentation(cli_args: &CliArgs, stats: &mut RunStats, trace: &mut RunTrace) -> GenerateOutcome
+{
+    //! Generates documentation for one notebook.
+    //!
+    //! # Inputs
+    //! - `cli_args`: Parsed CLI arguments for the `Generate` command.
+    //! - `stats`: Run statistics, filled in as each step completes.
+    //! - `trace`: Pipeline decisions, filled in as each step completes.
+    //! - Environment variables from the split env files.
+    //!
+    //! # Side Effects
+    //! - Calls the Azure AI Foundry chat endpoint.
+    //! - Writes output Markdown to `docs/newly-documented`, or prints it with `--stdout-only`.
+    //! - With `--per-cell`, makes one API call per cell and writes `docs/newly-documented/<stem>/cell-NN.md`.
+    //!
+    //! # Returns
+    //! - `GenerateOutcome::Saved` when documentation was written.
+    //! - `GenerateOutcome::Printed` when documentation was printed with `--stdout-only`.
+    //! - `GenerateOutcome::Skipped` when the notebook carries a `DOXCER:SKIP` marker, see [`notebook_has_skip_marker`].
+    //! - `GenerateOutcome::WriteFailed` when the documentation file could not be written.
+    //! - `GenerateOutcome::EmptyInput` when the notebook is empty before or after cleaning.
+    //! - `GenerateOutcome::InputTooLarge` when the notebook exceeds `--max-input-bytes`.
+    //! - `GenerateOutcome::BelowMinimum` when the output is shorter than `MIN_OUTPUT_CHARS`.
+    //! - `GenerateOutcome::MissingSections` when `--strict` is set and a `REQUIRED_SECTIONS` heading is missing.
+    //! - `GenerateOutcome::SchemaMismatch` when the output does not match `--expect-json-schema`.
+    //! - `GenerateOutcome::ConfigError` for invalid settings (retry policy, input limit, JSON schema, ODBC options).
+    //! - `GenerateOutcome::EmptyResponse` when the response is empty, echoes the prompt or has no assistant choice.
+    //! - `GenerateOutcome::ApiError` when the API key lookup or the API request fails or its response cannot be parsed.
+    //! - `GenerateOutcome::DefinitionsError` when the definitions lookup is blocked or not configured.
+    //! - With `--per-cell`, the outcome of the first cell that was not saved or printed.
+    //!
+    //! # Process Exit
+    //! - Exits with status code `1` when a template is missing and fallback is disabled, or when the
+    //!   AI or Key Vault configuration is missing (stops a batch run too, as every notebook would fail).
+    //!
+    //! # Panics
+    //! - On unrecoverable runtime or configuration errors.
+
+    let file_path = &cli_args.file_path;
+    if cli_args.auto_profile && !cli_args.profile_selected
+    {
+        log_info!("Processing notebook '{}' with auto-detected profile.", file_path);
+    }
+    else
+    {
+        log_info!(
+            "Processing notebook '{}' with '{}' profile.",
+            file_path,
+            profile_selector_name(cli_args.profile)
+        );
+    }
+
+    // Validate AI & Key Vault config (not needed for a mock response)
+    let needs_api = cli_args.mock_response.is_none();
+    if needs_api && (!AI_ENVCONFIG.ai_enabled
+        || AI_ENVCONFIG.ai_base_url.trim().is_empty()
+        || AI_ENVCONFIG.ai_version.trim().is_empty()
+        || AI_ENVCONFIG.ai_task.trim().is_empty()
+        || AI_ENVCONFIG.ai_model.trim().is_empty())
+    {
+        log_error!("[ERR] - AI Model configuration missing in env files");
+        process::exit(EXIT_CONFIG_ERROR);
+    }
+
+    if needs_api && (!ENVCONFIG.akv_enabled
+        || ENVCONFIG.akv_base_url.trim().is_empty()
+        || AI_ENVCONFIG.akv_secret_ai.trim().is_empty())
+    {
+        log_error!("[ERR] - Azure Key Vault configuration missing in env files");
+        process::exit(EXIT_CONFIG_ERROR);
+    }
+
+    let api_retry_policy = match retry::resolve_retry_policy(
+        cli_args.retry_attempts,
+        cli_args.retry_delay_ms,
+        env::var("DOXCER_RETRY_ATTEMPTS").ok().as_deref(),
+        env::var("DOXCER_RETRY_DELAY_MS").ok().as_deref(),
+    )
+    {
+        Ok(policy) => policy,
+        Err(err) =>
+        {
+            log_error!("{}", err);
+            return GenerateOutcome::ConfigError;
+        }
+    };
+    let akv_options = match resolve_akv_options(
+        cli_args.akv_retry_attempts,
+        cli_args.akv_timeout_secs,
+        env::var("AKV_RETRY_ATTEMPTS").ok().as_deref(),
+        env::var("AKV_TIMEOUT_SECS").ok().as_deref(),
+    )
+    {
+        Ok(options) => options,
+        Err(err) =>
+        {
+            log_error!("{}", err);
+            return GenerateOutcome::ConfigError;
+        }
+    };
+
+    // Fetch notebook content & clean
+    let input_path = Path::new(file_path);
+    log_info!("Loading notebook from {}", file_path);
+    let notebook_load_started_at = Instant::now();
+    let max_input_bytes = match cli_args.max_input_bytes
+    {
+        Some(limit) => Some(limit),
+        None => match env::var("DOXCER_MAX_INPUT_BYTES").ok().filter(|raw| !raw.trim().is_empty())
+        {
+            Some(raw) => match raw.trim().parse::<u64>()
+            {
+                Ok(limit) => Some(limit),
+                Err(_) =>
+                {
+                    log_error!("[ERR] - Invalid DOXCER_MAX_INPUT_BYTES '{}'.", raw);
+                    return GenerateOutcome::ConfigError;
+                }
+            },
+            None => None,
+        },
+    };
+    // A directory's own metadata length is its inode size, so part file directories sum their parts.
+    let input_size = if input_path.is_dir()
+    {
+        notebook_formats::part_files_size(input_path)
+    }
+    else
+    {
+        fs::metadata(input_path).ok().map(|metadata| metadata.len())
+    };
+    if let Some(size) = input_size
+        && let Err(err) = check_input_size(size, max_input_bytes)
+    {
+        log_error!("{}", err);
+        return GenerateOutcome::InputTooLarge;
+    }
+    let notebook_content = Notebook::read_source(input_path).unwrap_or_else(|err| panic!("{}", err));
+    if is_blank_source(&notebook_content)
+    {
+        log_error!("[ERR] - Input notebook is empty: {}", file_path);
+        return GenerateOutcome::EmptyInput;
+    }
+    let profile = resolve_active_profile(cli_args, &notebook_content);
+    trace.profile = profile_selector_name(profile).to_string();
+    let cleaner = NotebookCleaner::new(profile)
+        .with_collapse(collapse_config(cli_args))
+        .with_adf_json(cli_args.adf_json)
+        .with_max_cell_chars(max_cell_chars(env::var("MAX_CELL_CHARS").ok().as_deref()));
+    if notebook_has_skip_marker(
+        &notebook_content,
+        input_path,
+        cleaner.comment_prefixes(&notebook_content, input_path),
+        profile,
+    )
+    {
+        log_info!("Skipped (DOXCER:SKIP marker).");
+        return GenerateOutcome::Skipped;
+    }
+    let notebook_content = match &cli_args.filter_cells
+    {
+        Some(pattern) =>
+        {
+            log_info!("Keeping only cells matching '{}'", pattern);
+            let comment_prefixes = cleaner.comment_prefixes(&notebook_content, input_path);
+            match filter_cells_by_regex(&notebook_content, pattern, comment_prefixes)
+            {
+                Ok(filtered) => filtered,
+                Err(err) =>
+                {
+                    log_error!("{}", err);
+                    return GenerateOutcome::ConfigError;
+                }
+            }
+        }
+        None => notebook_content,
+    };
+    let notebook = Notebook::from_source(input_path, notebook_content, &cleaner)
+        .with_output_name(cli_args.notebook_name.as_deref());
+    if is_blank_source(&notebook.cleaned_source)
+    {
+        match &cli_args.filter_cells
+        {
+            Some(pattern) => log_error!("[ERR] - No cells match --filter-cells '{}': {}", pattern, file_path),
+            None => log_error!("[ERR] - Input notebook is empty after removing metadata: {}", file_path),
+        }
+        return GenerateOutcome::EmptyInput;
+    }
+    stats.input_bytes = notebook.original_source.len() as u64;
+    stats.cleaned_lines = notebook.cleaned_source.lines().count();
+    let compare_notebook = cli_args.compare.as_ref().map(|compare_path|
+    {
+        log_info!("Loading comparison notebook from {}", compare_path);
+        Notebook::load(Path::new(compare_path), &cleaner).unwrap_or_else(|err| panic!("{}", err))
+    });
+    let reference_notebooks: Vec<Notebook> = cli_args
+        .references
+        .iter()
+        .map(|reference_path|
+        {
+            log_info!("Loading reference notebook from {}", reference_path);
+            Notebook::load(Path::new(reference_path), &cleaner).unwrap_or_else(|err| panic!("{}", err))
+        })
+        .collect();
+    log_success!(
+        "Notebook loaded and cleaned in {:.1}s.",
+        notebook_load_started_at.elapsed().as_secs_f64()
+    );
+
+    // Load prompt & context templates
+    log_info!("Resolving prompt and context templates");
+    let allow_template_fallback = !(cli_args.no_template_fallback
+        || env_flag_enabled("DOXCER_NO_TEMPLATE_FALLBACK"));
+    let template_dir = cli_args
+        .profile_template_dir
+        .clone()
+        .or_else(|| env::var("DOXCER_TEMPLATE_DIR").ok().filter(|dir| !dir.trim().is_empty()))
+        .map(PathBuf::from);
+    if let Some(dir) = &template_dir
+        && !dir.is_dir()
+    {
+        log_warn!("Template directory '{}' does not exist; using repo templates.", dir.display());
+    }
+    let (prompt_path, prompt_content) = match load_prompt_template(&profile, template_dir.as_deref(), allow_template_fallback)
+    {
+        Ok(template) => template,
+        Err(err) =>
+        {
+            log_error!("{}", err);
+            log_info!("Add the missing template, or drop --no-template-fallback / DOXCER_NO_TEMPLATE_FALLBACK to use default_prompt.md.");
+            return GenerateOutcome::ConfigError;
+        }
+    };
+    log_info!("Using prompt template {}", prompt_path.display());
+    trace.template = prompt_path.display().to_string();
+    let template_version = parse_template_version(&prompt_content);
+    let prompt_content = match &template_version
+    {
+        Some(version) =>
+        {
+            log_info!("Prompt template version '{}'", version);
+            trace.template_version = Some(version.clone());
+            strip_template_version(&prompt_content)
+        }
+        None => prompt_content,
+    };
+    let client = Client::builder()
+        .timeout(Duration::from_secs(300))
+        .build()
+        .expect("Failed to build HTTP client");
+    let remote_context = env::var("CONTEXT_URL")
+        .ok()
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .and_then(|url|
+        {
+            load_remote_context(&url, &find_context_cache_path(), |url|
+            {
+                fetch_remote_context(&client, url).map_err(|err| err.to_string().trim_start_matches("[ERR] - ").to_string())
+            })
+        });
+    let mut context_content = match remote_context
+    {
+        Some(content) => content,
+        None => load_template(&find_context_path()).expect("[ERR] - Failed to read context template"),
+    };
+    let output_language = cli_args.lang.clone().or_else(|| env::var("OUTPUT_LANGUAGE").ok());
+    if let Some(instruction) = output_language.as_deref().and_then(language_instruction)
+    {
+        log_info!("{}", instruction);
+        context_content = format!("{}\n\n{}", context_content.trim_end(), instruction);
+    }
+    let prompt_layout_path = find_prompt_layout_path();
+    let prompt_layout = if prompt_layout_path.is_file()
+    {
+        let layout = load_template(&prompt_layout_path)
+            .unwrap_or_else(|_| panic!("[ERR] - Failed to read prompt layout {}", prompt_layout_path.display()));
+        log_info!("Using prompt layout {}", prompt_layout_path.display());
+        Some(layout)
+    }
+    else
+    {
+        None
+    };
+    let context_version = cli_args.context_version.as_deref().filter(|version| !version.trim().is_empty());
+    if let Some(version) = context_version
+    {
+        log_info!("Using context version '{}'", version.trim());
+        context_content = append_context_version(&context_content, Some(version));
+        trace.context_version = Some(version.trim().to_string());
+    }
+    log_success!("Prompt and context templates loaded");
+
+    let output_schema = match &cli_args.expect_json_schema
+    {
+        Some(schema_path) => match fs::read_to_string(schema_path)
+            .map_err(|err| err.to_string())
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).map_err(|err| err.to_string()))
+        {
+            Ok(schema) => Some(schema),
+            Err(err) =>
+            {
+                log_error!("[ERR] - Failed to load JSON schema {}: {}", schema_path, err);
+                return GenerateOutcome::ConfigError;
+            }
+        },
+        None => None,
+    };
+
+    // Determine definitions (a mock run stays offline)
+    let fabric_definitions = if cli_args.mock_response.is_some()
+    {
+        log_info!("Definitions fetch skipped for --mock-response");
+        trace.definitions = "skipped (mock response)".to_string();
+        "[INF] - No definitions loaded (mock response).".to_string()
+    }
+    else
+    {
+        match fetch_notebook_definitions(cli_args, &ENVCONFIG, profile, &notebook, akv_options, stats, trace)
+        {
+            Ok(definitions) => definitions,
+            Err(outcome) => return outcome,
+        }
+    };
+
+    let fabric_definitions = match cli_args.truncate_definitions
+    {
+        Some(max_chars) =>
+        {
+            let truncated = io_utils::truncate_at_newline(&fabric_definitions, max_chars);
+            if truncated.len() < fabric_definitions.len()
+            {
+                log_info!("Definition section truncated to {} characters", max_chars);
+            }
+            truncated.to_string()
+        }
+        None => fabric_definitions,
+    };
+
+    let job = DocumentationJob
+    {
+        cli_args,
+        profile,
+        definitions: &fabric_definitions,
+        prompt_template: &prompt_content,
+        prompt_path: &prompt_path,
+        template_version: template_version.as_deref(),
+        context_content: &context_content,
+        prompt_layout: prompt_layout.as_deref(),
+        output_schema: output_schema.as_ref(),
+        reference_notebooks: &reference_notebooks,
+        compare_notebook: compare_notebook.as_ref(),
+        api_retry_policy: &api_retry_policy,
+        client: &client,
+        akv_options,
+        min_output_chars: ENVCONFIG.min_output_chars,
+    };
+
+    if !cli_args.per_cell
+    {
+        return document_notebook(&job, &notebook, stats, trace);
+    }
+
+    let cells = notebook.cells(&cleaner);
+    if cells.is_empty()
+    {
+        log_error!("[ERR] - Input notebook has no non-empty cells: {}", file_path);
+        return GenerateOutcome::EmptyInput;
+    }
+    log_info!("Documenting {} cell(s) separately", cells.len());
+
+    let mut outcome = GenerateOutcome::Saved;
+    for (index, cell) in cells.iter().enumerate()
+    {
+        log_info!("Documenting cell {} of {} ({})", index + 1, cells.len(), cell.output_stem);
+        let cell_outcome = document_notebook(&job, cell, stats, trace);
+        if matches!(outcome, GenerateOutcome::Saved | GenerateOutcome::Printed)
+        {
+            outcome = cell_outcome;
+        }
+    }
+    outcome
+}
+
+fn changelog_entry(generated_at: &str, name: &str, profile: &str) -> String
+{
+    //! Formats the `--changelog` line for one generated doc.
+    //!
+    //! # Inputs
+    //! - `generated_at`: Generation timestamp (`YYYY-MM-DD HH:MM:SS`); only the date is used.
+    //! - `name`: Output name of the doc.
+    //! - `profile`: Selector name of the notebook profile.
+    //!
+    //! # Returns
+    //! - `- YYYY-MM-DD regenerated <name> (<profile>)`.
+
+    let date = generated_at.split_whitespace().next().unwrap_or(generated_at);
+    format!("- {} regenerated {} ({})", date, name, profile)
+}
+
+fn record_changelog_entry(path: &Path, entry: &str, force: bool)
+{
+    //! Writes a `--changelog` entry and reports the result.
+    //!
+    //! # Inputs
+    //! - `path`: Changelog file.
+    //! - `entry`: Line built by [`changelog_entry`].
+    //! - `force`: Append even when the same entry is already present (`--force`).
+    //!
+    //! # Notes
+    //! - A failure is a warning; the documentation itself was already saved.
+
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty())
+        && let Err(e) = fs::create_dir_all(parent)
+    {
+        log_warn!("Failed to create changelog directory {}: {}", parent.display(), e);
+        return;
+    }
+
+    let result = if force
+    {
+        io_utils::append_line(path, entry)
+    }
+    else
+    {
+        io_utils::append_changelog_entry(path, entry)
+    };
+
+    match result
+    {
+        Ok(()) => log_info!("Changelog updated: {}", path.display()),
+        Err(e) => log_warn!("Failed to update changelog {}: {}", path.display(), e),
+    }
 }
 
-fn env_flag_enabled(name: &str) -> bool
+fn load_mock_response(path: &Path) -> Result<ChatResponse, String>
 {
-    //! Reads a boolean-like environment flag.
+    //! Loads a `--mock-response` file.
     //!
     //! # Inputs
-    //! - `name`: Environment variable name.
+    //! - `path`: JSON file shaped like a chat completions response (`choices[].message`).
     //!
     //! # Returns
-    //! - `true` for values: `1`, `true`, `yes`, `on` (case-insensitive).
-    //! - `false` otherwise.
+    //! - `Ok(ChatResponse)` when the file is readable and parses.
+    //! - `Err(String)` with an `[ERR]` message otherwise.
 
-    match env::var(name)
-    {
-        Ok(value) =>
-        {
-            matches!(
-                value.trim().to_ascii_lowercase().as_str(),
-                "1" | "true" | "yes" | "on"
-            )
-        }
-        Err(_) => false,
-    }
+    let body_text = fs::read_to_string(path)
+        .map_err(|err| format!("[ERR] - Failed to read mock response {}: {}", path.display(), err))?;
+    serde_json::from_str::<ChatResponse>(&body_text)
+        .map_err(|err| format!("[ERR] - Mock response {} is not a valid chat response: {}", path.display(), err))
 }
 
-fn determine_output_names(input_path: &Path) -> (String, String)
+fn handle_chat_response(
+    job: &DocumentationJob,
+    notebook: &Notebook,
+    chat_response: &ChatResponse,
+    current_datetime: &str,
+    publish_client: Option<&Client>,
+    stats: &mut RunStats,
+    trace: &mut RunTrace,
+) -> GenerateOutcome
 {
-    //! Derives output names from the input notebook path.
+    //! Turns a parsed API response into documentation: content checks, fence unwrapping, formatting and saving.
     //!
     //! # Inputs
-    //! - `input_path`: Input notebook path.
+    //! - `job`: Run-wide inputs resolved by [`generate_documentation`].
+    //! - `notebook`: Notebook or cell the response documents.
+    //! - `chat_response`: Parsed API response, or the `--mock-response` file.
+    //! - `current_datetime`: Generation timestamp used in the front matter and changelog.
+    //! - `publish_client`: HTTP client used to publish to the wiki; `None` skips publishing.
+    //! - `stats`: Run statistics, filled in as each step completes.
+    //! - `trace`: Pipeline decisions, filled in as each step completes.
     //!
     //! # Returns
-    //! - `(output_file_name, output_file_name_ext)`.
-    //! - Uses parent directory name when file is `notebook-content.py`.
-    //!
-    //! # Panics
-    //! - If `input_path` has no filename.
-
-    let filename_os = input_path
-        .file_name()
-        .expect("[ERR] - Input file has no filename");
+    //! - The [`GenerateOutcome`] for this response, see [`generate_documentation`].
 
-    let filename = filename_os.to_string_lossy();
+    let cli_args = job.cli_args;
+    let file_path = &cli_args.file_path;
+    let profile = job.profile;
+    let template_version = job.template_version;
+    let output_schema = job.output_schema;
 
-    if filename != "notebook-content.py"
+    let Some(first_choice) = chat_response
+        .choices
+        .iter()
+        .find(|choice| is_assistant_role(choice.message.role.as_deref()))
+    else
     {
-        let output_file_name = input_path
-            .file_stem()
-            .unwrap_or(filename_os)
-            .to_string_lossy()
-            .to_string();
+        log_info!("No assistant 'choices' found in response.");
+        return GenerateOutcome::EmptyResponse;
+    };
 
-        let output_file_name_ext = filename.to_string();
+    let outcome;
 
-        return (output_file_name, output_file_name_ext);
+    let content = &first_choice.message.content;
+    trace.response_chars = Some(content.chars().count());
+    if content.trim().is_empty()
+    {
+        log_info!("API response was empty.");
+        return GenerateOutcome::EmptyResponse;
     }
 
-    // Special case: notebook-content.py
-    let parent_dir_name = input_path
-        .parent()
-        .and_then(|p| p.file_name())
-        .map(|os| os.to_string_lossy().to_string())
-        .unwrap_or_else(|| "notebook-content".to_string());
-
-    let output_file_name = parent_dir_name
-        .trim_end_matches(".Notebook")
-        .to_string();
-
-    let output_file_name_ext = format!("{}.py", output_file_name);
-
-    (output_file_name, output_file_name_ext)
-}
-
+    if looks_like_internal_prompt(content)
+    {
+        log_warn!("API response appears to echo the internal prompt. Output suppressed.");
+        return GenerateOutcome::EmptyResponse;
+    }
 
-// ----------------------------
-// Runtime
-// ----------------------------
+    if let Some(schema) = &output_schema
+    {
+        let validation = serde_json::from_str::<serde_json::Value>(
+            output_validation::strip_json_code_fence(content),
+        )
+        .map_err(|err| vec![format!("$: output is not valid JSON ({})", err)])
+        .and_then(|value| output_validation::validate_against_schema(&value, schema));
 
-fn main()
-{
-    //! Application entry point for generating notebook documentation.
-    //!
-    //! # Inputs
-    //! - CLI args: `doxcer [selector] <path/to/notebook.py>`.
-    //! - Environment variables from the split env files.
-    //!
-    //! # Side Effects
-    //! - Calls the Azure AI Foundry chat endpoint.
-    //! - Prints generated Markdown to stdout.
-    //! - Writes output Markdown to `docs/newly-documented`.
-    //!
-    //! # Process Exit
-    //! - Exits with status code `1` for invalid CLI arguments.
-    //!
-    //! # Panics
-    //! - On unrecoverable runtime or configuration errors.
+        if let Err(errors) = validation
+        {
+            log_error!("[ERR] - Output does not match the expected JSON schema; not saved.");
+            for error in &errors
+            {
+                log_error!("[ERR] -   {}", error);
+            }
+            return GenerateOutcome::SchemaMismatch;
+        }
+        log_success!("Output matches the expected JSON schema");
+    }
 
-    // CLI args
-    let args: Vec<String> = env::args().collect();
-    if is_help_requested(&args)
+    let unwrapped = output_validation::unwrap_outer_fence(content);
+    if unwrapped != *content
     {
-        print_usage();
-        process::exit(0);
+        log_info!("Removed the code fence wrapping the whole response.");
     }
+    let content = &unwrapped;
 
-    let cli_args = match parse_cli_args(&args)
+    let content = match &cli_args.format_cmd
     {
-        Ok(parsed) => parsed,
-        Err(err) =>
+        Some(format_cmd) =>
         {
-            eprintln!("{}", err);
-            print_usage();
-            process::exit(1);
+            log_info!("Formatting documentation with '{}'", format_cmd);
+            match pipe_through_formatter(format_cmd, content)
+            {
+                Ok(formatted) => formatted,
+                Err(err) =>
+                {
+                    log_warn!("Formatter failed ({}). Saving unformatted documentation.", err);
+                    content.clone()
+                }
+            }
         }
+        None => content.clone(),
     };
 
-    let file_path = &cli_args.file_path;
-    println!(
-        "[INF] - Processing notebook '{}' with '{}' profile.",
-        file_path,
-        profile_selector_name(cli_args.profile)
-    );
-
-    // Validate AI & Key Vault config
-    if !ENVCONFIG.ai_enabled == true
-        || ENVCONFIG.ai_base_url.trim().is_empty()
-        || ENVCONFIG.ai_version.trim().is_empty()
-        || ENVCONFIG.ai_task.trim().is_empty()
-        || ENVCONFIG.ai_model.trim().is_empty()
+    if is_below_min_output(&content, job.min_output_chars)
     {
-        eprintln!("[ERR] - AI Model configuration missing in env files");
-        return;
+        log_warn!("Output below minimum length; not saved.");
+        return GenerateOutcome::BelowMinimum;
     }
 
-    if !ENVCONFIG.akv_enabled == true
-        || ENVCONFIG.akv_base_url.trim().is_empty()
-        || ENVCONFIG.akv_secret_ai.trim().is_empty()
+    let required_sections = output_validation::parse_required_sections(
+        &env::var("REQUIRED_SECTIONS").unwrap_or_default(),
+    );
+    let missing_sections = output_validation::check_required_sections(&content, &required_sections);
+    if !missing_sections.is_empty()
     {
-        eprintln!("[ERR] - Azure Key Vault configuration missing in env files");
-        return;
+        if cli_args.strict
+        {
+            log_error!(
+                "[ERR] - Output is missing required section(s): {}; not saved.",
+                missing_sections.join(", ")
+            );
+            return GenerateOutcome::MissingSections;
+        }
+        for section in &missing_sections
+        {
+            log_warn!("Output is missing required section '{}'.", section);
+        }
     }
 
-    // Determine notebook output names
-    let input_path = Path::new(file_path);
-    let (output_file_name, output_file_name_ext) = determine_output_names(input_path);
+    let content = if cli_args.include_source
+    {
+        let language = notebook_analysis::detect_language(&notebook.original_source, &notebook.input_path);
+        append_source_section(&content, &notebook.cleaned_source, language.code_fence_tag())
+    }
+    else
+    {
+        content
+    };
 
-    // Fetch notebook content & clean
-    println!("[INF] - Loading notebook from {}", file_path);
-    let notebook_load_started_at = Instant::now();
-    let notebook_content = fs::read_to_string(file_path)
-        .unwrap_or_else(|_| panic!("[ERR] - Failed to read file {}", file_path));
-    let cleaned_notebook = collapse_blank_lines(&strip_notebook_metadata(&notebook_content));
-    println!(
-        "[SUC] - Notebook loaded and cleaned in {:.1}s.",
-        notebook_load_started_at.elapsed().as_secs_f64()
-    );
+    let content = match template_version
+    {
+        Some(version) if cli_args.template_version_comment =>
+        {
+            format!("{}\n\n{}", template_version_comment(version), content)
+        }
+        _ => content,
+    };
 
-    // Load prompt & context templates
-    println!("[INF] - Resolving prompt and context templates");
-    let prompt_path = find_prompt_path(&cli_args.profile);
-    println!("[INF] - Using prompt template {}", prompt_path.display());
-    let prompt_content = fs::read_to_string(&prompt_path)
-        .unwrap_or_else(|_| panic!("[ERR] - Failed to read prompt template {}", prompt_path.display()));
-    let context_content = fs::read_to_string(find_context_path())
-        .expect("[ERR] - Failed to read context template");
-    println!("[SUC] - Prompt and context templates loaded");
+    let content = if cli_args.frontmatter || !cli_args.frontmatter_fields.is_empty()
+    {
+        let mut fields = vec![
+            ("title", notebook.output_stem.clone()),
+            ("profile", profile_selector_name(profile).to_string()),
+            ("generated_at", current_datetime.to_string()),
+            ("source", file_path.to_string()),
+        ];
+        if let Some(version) = template_version
+        {
+            fields.push(("template_version", version.to_string()));
+        }
+        prepend_frontmatter(&content, &fields, &cli_args.frontmatter_fields)
+    }
+    else
+    {
+        content
+    };
 
-    // Determine definitions
-    let fabric_definitions = if ENVCONFIG.definition_database_enabled == true
+    if cli_args.stdout_only
+    {
+        LOGGER.suspend(|| println!("{}", content));
+        outcome = save_outcome(None);
+    }
+    else
     {
-        println!("[INF] - Definition table enabled");
+        // Save to wiki
+        let mut output_path = find_docs_path();
+        output_path.push(format!("{}.md", notebook.output_stem));
 
-        if ENVCONFIG.definition_fabric_database_enabled == true
-            && !ENVCONFIG.akv_secret_definition_fabric_endpoint.trim().is_empty()
-            && !ENVCONFIG.akv_secret_definition_fabric_client_id.trim().is_empty()
-            && !ENVCONFIG.akv_secret_definition_fabric_password.trim().is_empty()
-            && !ENVCONFIG.definition_fabric_database.trim().is_empty()
+        if let Some(parent) = output_path.parent()
+            && let Err(e) = fs::create_dir_all(parent)
         {
-            println!("[SUC] - Fabric Definition DB config found");
+            log_warn!("Failed to create wiki directory {}: {}", parent.display(), e);
+        }
 
-            let repo_root = find_repo_root_path();
-            let fabric_definition_config = FabricDefinitionConfig
+        if cli_args.diff_against_head
+            && let Some(previous) = git_head_content(&find_repo_root_path(), &output_path, run_git)
+        {
+            let diff = io_utils::line_diff(&previous, &content);
+            if diff.is_empty()
             {
-                repo_root: repo_root.as_path(),
-                akv_base_url: &ENVCONFIG.akv_base_url,
-                definition_fabric_database: &ENVCONFIG.definition_fabric_database,
-                akv_secret_definition_fabric_endpoint: &ENVCONFIG.akv_secret_definition_fabric_endpoint,
-                akv_secret_definition_fabric_client_id: &ENVCONFIG.akv_secret_definition_fabric_client_id,
-                akv_secret_definition_fabric_password: &ENVCONFIG.akv_secret_definition_fabric_password,
-                odbc_batch_size: ENVCONFIG.odbc_batch_size,
-                odbc_max_byte_size: ENVCONFIG.odbc_max_byte_size,
-            };
-
-            // Fetch from Fabric SQL
-            println!(
-                "[INF] - Querying Fabric definitions for notebook '{}'",
-                output_file_name
-            );
-            let definition_query_started_at = Instant::now();
-            match fetch_definitions::fetch_definitions_from_fabric(
-                &output_file_name,
-                &fabric_definition_config,
-            )
+                log_info!("No changes against HEAD for {}.", output_path.display());
+            }
+            else
             {
-                Ok((cols, rows)) if !cols.is_empty() && !rows.is_empty() =>
-                {
-                    println!(
-                        "[SUC] - Definitions query completed in {:.1}s.",
-                        definition_query_started_at.elapsed().as_secs_f64()
-                    );
-                    println!("[SUC] - Definitions found: {} row(s).", rows.len());
-                    fetch_definitions::format_definitions_as_markdown_table(&cols, &rows)
-                }
-                Ok(_) =>
-                {
-                    println!(
-                        "[INF] - Definitions query completed in {:.1}s.",
-                        definition_query_started_at.elapsed().as_secs_f64()
-                    );
-                    println!("[INF] - No definitions found for this notebook.");
-                    "[INF] - No definitions loaded (query returned no rows).".to_string()
-                }
-                Err(e) =>
-                {
-                    eprintln!(
-                        "[WRN] - Definitions query failed after {:.1}s: {e}",
-                        definition_query_started_at.elapsed().as_secs_f64()
-                    );
-                    "[INF] - No definitions loaded (query failed).".to_string()
-                }
+                log_info!("Changes against HEAD for {}:\n{}", output_path.display(), diff.trim_end());
             }
         }
-        else if ENVCONFIG.definition_azure_database_enabled == true
+
+        let write_result = io_utils::atomic_write_bytes(
+            &output_path,
+            &io_utils::encode_output(&content, cli_args.output_encoding),
+        );
+        outcome = save_outcome(Some(&write_result));
+        if let Err(e) = write_result
         {
-            println!("[SUC] - Azure Definition DB config found");
-            "[INF] - Azure SQL definitions not implemented yet.".to_string()
+            log_error!("[ERR] - Failed to save documentation to {}: {}", output_path.display(), e);
         }
         else
         {
-            println!("[ERR] - No supported definition DB configured");
-            return;
+            log_success!("Saved documentation to: {}", output_path.display());
+            stats.output_bytes = Some(content.len() as u64);
+            let (lines, words, chars) = doc_stats(&content);
+            log_info!("Generated doc: {} lines, {} words, {} chars.", lines, words, chars);
+            if let Some(limit) = cli_args.echo_limit
+            {
+                LOGGER.suspend(|| println!("{}", io_utils::truncate_for_echo(&content, limit)));
+            }
+
+            if let Some(changelog_path) = &cli_args.changelog
+            {
+                record_changelog_entry(
+                    Path::new(changelog_path),
+                    &changelog_entry(current_datetime, &notebook.output_stem, profile_selector_name(profile)),
+                    cli_args.force,
+                );
+            }
+
+            if let Some(client) = publish_client
+            {
+                publish_to_wiki(client, &notebook.output_stem, &content, &job.akv_options);
+            }
         }
     }
-    else
-    {
-        println!("[INF] - Definition database disabled");
-        "[INF] - Definition database disabled.".to_string()
-    };
+
+    outcome
+}
+
+fn document_notebook(job: &DocumentationJob, notebook: &Notebook, stats: &mut RunStats, trace: &mut RunTrace) -> GenerateOutcome
+{
+    //! Builds the prompt for one notebook (or one cell with `--per-cell`), calls the API and saves the result.
+    //!
+    //! # Inputs
+    //! - `job`: Run-wide inputs resolved by [`generate_documentation`].
+    //! - `notebook`: Notebook or cell to document; its `output_stem` names the output file.
+    //! - `stats`: Run statistics, filled in as each step completes.
+    //! - `trace`: Pipeline decisions, filled in as each step completes.
+    //!
+    //! # Returns
+    //! - The [`GenerateOutcome`] for this notebook, see [`generate_documentation`].
+
+    let cli_args = job.cli_args;
+    let fabric_definitions = job.definitions;
+    let prompt_content = job.prompt_template;
+    let prompt_path = job.prompt_path;
+    let context_content = job.context_content;
+    let reference_notebooks = job.reference_notebooks;
+    let compare_notebook = job.compare_notebook;
+    let api_retry_policy = job.api_retry_policy;
+    let akv_options = job.akv_options;
 
     // Build prompt
-    println!("[INF] - Building prompt payload");
+    log_info!("Building prompt payload");
     let current_datetime = Utc::now().with_timezone(&Amsterdam)
         .format("%Y-%m-%d %H:%M:%S")
         .to_string();
 
-    let prompt = format!(
-        "Current date time: {}\n\nNotebook filename: {}\n\nDefinitions: {}\n\nDocumentation template: {}\n\nCode: {}",
-        current_datetime,
-        output_file_name_ext,
-        fabric_definitions,
+    let (prompt_content, unresolved_placeholders) = render_template(
         prompt_content,
-        cleaned_notebook
+        &[
+            ("filename", notebook.output_display_name.as_str()),
+            ("date", current_datetime.as_str()),
+            ("definitions", fabric_definitions),
+        ],
     );
+    for placeholder in &unresolved_placeholders
+    {
+        log_warn!(
+            "Unknown placeholder '{{{}}}' in prompt template {} was left as-is.",
+            placeholder,
+            prompt_path.display()
+        );
+    }
+
+    let mut prompt_builder = PromptBuilder::new(&current_datetime, notebook)
+        .with_definitions(fabric_definitions)
+        .with_template(&prompt_content);
+    if let Some(layout) = job.prompt_layout
+    {
+        prompt_builder = prompt_builder.with_layout(layout);
+    }
+    if let Some(definition_template) = &cli_args.definition_format_template
+    {
+        if !definition_template.contains("{definitions}")
+        {
+            log_warn!("--definition-format-template has no {{definitions}} placeholder; definitions are left out of the prompt.");
+        }
+        prompt_builder = prompt_builder.with_definition_template(definition_template);
+    }
+    if let Some(section) = &cli_args.section
+    {
+        log_info!("Restricting documentation to the '{}' section", section);
+        prompt_builder = prompt_builder.with_section_filter(section);
+    }
+    for reference_notebook in reference_notebooks
+    {
+        prompt_builder = prompt_builder.with_reference(reference_notebook);
+    }
+    if cli_args.include_imports
+    {
+        let imports = notebook_analysis::extract_imports(&notebook.cleaned_source);
+        log_info!("Found {} import statement(s)", imports.len());
+        prompt_builder = prompt_builder.with_imports(&imports);
+    }
+    if let Some(old_notebook) = &compare_notebook
+    {
+        prompt_builder = prompt_builder.with_compare_source(old_notebook);
+    }
+    let prompt = prompt_builder.build();
+    stats.estimated_prompt_tokens = estimate_tokens(context_content) + estimate_tokens(&prompt);
+    trace.prompt_chars = context_content.chars().count() + prompt.chars().count();
+    // A mock run does not load the AI settings, so only `--model` names the model.
+    let configured_model = match &cli_args.mock_response
+    {
+        Some(_) => "",
+        None => AI_ENVCONFIG.ai_model.as_str(),
+    };
+    let model = resolve_model(cli_args.model.as_deref(), configured_model);
+    if !configured_model.is_empty() && model != configured_model.trim()
+    {
+        log_info!("Using model override '{}'", model);
+    }
+    stats.model = model.clone();
+    trace.model = model.clone();
+
+    if let Some(mock_path) = &cli_args.mock_response
+    {
+        log_info!("Using mock API response from {} (no API request)", mock_path);
+        return match load_mock_response(Path::new(mock_path))
+        {
+            Ok(chat_response) => handle_chat_response(job, notebook, &chat_response, &current_datetime, None, stats, trace),
+            Err(err) =>
+            {
+                log_error!("{}", err);
+                GenerateOutcome::ConfigError
+            }
+        };
+    }
 
     // Call API
-    println!("[INF] - Resolving API key from Azure Key Vault");
+    log_info!("Resolving API key from Azure Key Vault");
     let key_vault_lookup_started_at = Instant::now();
-    let api_key = fetch_secrets::get_secret_from_key_vault(&ENVCONFIG.akv_base_url, &ENVCONFIG.akv_secret_ai);
-    println!(
-        "[SUC] - API key resolved in {:.1}s.",
+    let api_key = match fetch_secrets::try_get_secret_from_key_vault_on(
+        KEY_VAULT_RUNTIME.handle(),
+        &ENVCONFIG.akv_base_url,
+        &AI_ENVCONFIG.akv_secret_ai,
+        &ENVCONFIG.akv_credential,
+        &akv_options,
+    )
+    {
+        Ok(api_key) => api_key,
+        Err(err) =>
+        {
+            log_error!("{}", err);
+            return GenerateOutcome::ApiError;
+        }
+    };
+    log_success!(
+        "API key resolved in {:.1}s.",
         key_vault_lookup_started_at.elapsed().as_secs_f64()
     );
+    let api_version = resolve_api_version(
+        cli_args.api_version.as_deref(),
+        env::var("AI_VERSION_OVERRIDE").ok().as_deref(),
+        &AI_ENVCONFIG.ai_version,
+    );
+    if api_version != AI_ENVCONFIG.ai_version.trim()
+    {
+        log_info!("Using API version override '{}'", api_version);
+    }
     let api_url = format!(
         "{base}/models/chat/{task}?api-version={version}",
-        base = ENVCONFIG.ai_base_url,
-        task = ENVCONFIG.ai_task,
-        version = ENVCONFIG.ai_version
+        base = AI_ENVCONFIG.ai_base_url,
+        task = AI_ENVCONFIG.ai_task,
+        version = api_version
     );
 
+    let seed = resolve_seed(cli_args.seed, env::var("AI_SEED").ok().as_deref());
+    if let Some(seed) = seed
+    {
+        log_info!("Using sampling seed {}", seed);
+    }
+
     let request = ChatRequest
     {
-        model: ENVCONFIG.ai_model.clone(),
-        messages: vec![
-            Message { role: "system".to_string(), content: context_content },
-            Message { role: "user".to_string(), content: prompt },
-        ],
+        model: model.clone(),
+        messages: build_messages(
+            context_content,
+            &prompt,
+            cli_args.context_format,
+            &AI_ENVCONFIG.ai_system_role,
+            &AI_ENVCONFIG.ai_user_role,
+        ),
+        seed,
     };
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(300))
-        .build()
-        .expect("Failed to build HTTP client");
+    let client = job.client;
+    let extra_headers = parse_extra_headers(&AI_ENVCONFIG.ai_extra_headers);
 
     // Handle response
-    println!(
-        "[INF] - Submitting API request for task '{}' (model '{}')",
-        ENVCONFIG.ai_task,
-        ENVCONFIG.ai_model
+    log_info!(
+        "Submitting API request for task '{}' (model '{}')",
+        AI_ENVCONFIG.ai_task,
+        model
     );
-    println!("[INF] - Waiting for API response (timeout: 300s)");
+    log_info!("Waiting for API response (timeout: 300s)");
     let api_request_started_at = Instant::now();
-    match client.post(&api_url)
-        .header("Content-Type", "application/json")
-        .header("api-key", api_key)
-        .json(&request)
-        .send()
+    let outcome;
+    let api_response = api_retry_policy.run(
+        "API request",
+        || client.post(&api_url)
+            .header("Content-Type", "application/json")
+            .header("api-key", &api_key)
+            .headers(extra_headers.clone())
+            .json(&request)
+            .send(),
+        |result| match result
+        {
+            Ok(res) => retry::is_retryable_status(res.status().as_u16()),
+            Err(_) => true,
+        },
+    );
+    stats.api_response_ms = Some(api_request_started_at.elapsed().as_millis());
+
+    match api_response
     {
         Ok(res) if res.status().is_success() =>
         {
-            println!(
-                "[SUC] - API request completed in {:.1}s with status {}.",
+            log_success!(
+                "API request completed in {:.1}s with status {}.",
                 api_request_started_at.elapsed().as_secs_f64(),
                 res.status()
             );
             let body_text = res.text().unwrap_or_default();
-            println!("[INF] - Parsing API response");
+            log_info!("Parsing API response");
             match serde_json::from_str::<ChatResponse>(&body_text)
             {
                 Ok(chat_response) =>
                 {
-                    println!(
-                        "[SUC] - API response parsed ({} choice(s)).",
+                    log_success!(
+                        "API response parsed ({} choice(s)).",
                         chat_response.choices.len()
                     );
-                    if let Some(first_choice) = chat_response
-                        .choices
-                        .iter()
-                        .find(|choice| is_assistant_role(choice.message.role.as_deref()))
-                    {
-                        let content = &first_choice.message.content;
-                        if content.trim().is_empty()
-                        {
-                            println!("[INF] - API response was empty.");
-                            return;
-                        }
-
-                        if looks_like_internal_prompt(content)
-                        {
-                            eprintln!("[WRN] - API response appears to echo the internal prompt. Output suppressed.");
-                            return;
-                        }
-
-                        // Save to wiki
-                        let mut output_path = find_docs_path();
-                        output_path.push(format!("{}.md", output_file_name));
-
-                        if let Some(parent) = output_path.parent()
-                        {
-                            if let Err(e) = fs::create_dir_all(parent)
-                            {
-                                eprintln!("[WRN] - Failed to create wiki directory {}: {}", parent.display(), e);
-                            }
-                        }
-
-                        if let Err(e) = fs::write(&output_path, content)
-                        {
-                            eprintln!("[WRN] - Failed to save documentation to {}: {}", output_path.display(), e);
-                        }
-                        else
-                        {
-                            println!("[SUC] - Saved documentation to: {}", output_path.display());
-                        }
-                    }
-                    else
-                    {
-                        println!("[INF] - No assistant 'choices' found in response.");
-                    }
+                    outcome = handle_chat_response(
+                        job,
+                        notebook,
+                        &chat_response,
+                        &current_datetime,
+                        ENVCONFIG.publish_enabled.then_some(client),
+                        stats,
+                        trace,
+                    );
                 }
                 Err(e) =>
                 {
-                    if env_flag_enabled("DOXCER_DEBUG_API")
+                    outcome = GenerateOutcome::ApiError;
+                    if let Some(api_error) = describe_api_error(&body_text)
+                    {
+                        log_error!("{}", api_error);
+                    }
+                    else if env_flag_enabled("DOXCER_DEBUG_API")
                     {
-                        eprintln!("[ERR] - Failed to deserialize response: {e}\n[INF] - Raw response: {body_text}");
+                        log_error!("[ERR] - Failed to deserialize response: {e}\n[INF] - Raw response: {body_text}");
                     }
                     else
                     {
-                        eprintln!("[ERR] - Failed to deserialize response: {e}");
-                        eprintln!("[INF] - Set DOXCER_DEBUG_API=true to print the raw API response body.");
+                        log_error!("[ERR] - Failed to deserialize response: {e}");
+                        log_info!("Set DOXCER_DEBUG_API=true to print the raw API response body.");
                     }
                 }
             }
         }
         Ok(res) =>
         {
+            outcome = GenerateOutcome::ApiError;
             let status = res.status();
             let body_text = res.text().unwrap_or_default();
             let elapsed = api_request_started_at.elapsed().as_secs_f64();
 
             if env_flag_enabled("DOXCER_DEBUG_API")
             {
-                eprintln!("[ERR] - API request failed after {elapsed:.1}s ({status}): {body_text}");
+                log_error!("[ERR] - API request failed after {elapsed:.1}s ({status}): {body_text}");
+            }
+            else if let Some(api_error) = describe_api_error(&body_text)
+            {
+                log_error!("[ERR] - API request failed after {elapsed:.1}s with status: {status}");
+                log_error!("{}", api_error);
             }
             else
             {
-                eprintln!("[ERR] - API request failed after {elapsed:.1}s with status: {status}");
-                eprintln!("[INF] - Set DOXCER_DEBUG_API=true to print the raw API error body.");
+                log_error!("[ERR] - API request failed after {elapsed:.1}s with status: {status}");
+                log_info!("Set DOXCER_DEBUG_API=true to print the raw API error body.");
             }
         }
-        Err(e) => eprintln!(
-            "[ERR] - Request error after {:.1}s: {}",
-            api_request_started_at.elapsed().as_secs_f64(),
-            e
-        ),
+        Err(e) =>
+        {
+            outcome = GenerateOutcome::ApiError;
+            log_error!(
+                "[ERR] - Request error after {:.1}s: {}",
+                api_request_started_at.elapsed().as_secs_f64(),
+                e
+            );
+        }
     }
+
+    outcome
+}
+
+fn main()
+{
+    //! Application entry point for generating notebook documentation.
+    //!
+    //! # Inputs
+    //! - CLI args: `doxcer [selector] <path/to/notebook.py>` or a local command (see [`run`]).
+    //!
+    //! # Process Exit
+    //! - Exits with the status code returned by [`run`] (`1` for invalid CLI arguments).
+
+    let args: Vec<String> = env::args().collect();
+    process::exit(run(&args));
 }
@@ -0,0 +1,249 @@
+//////////////////////////////////////////////////////////
+// AUTHOR   : Stefan B. J. Meeuwessen
+// CREATION : 2026-10-16
+// VERSION  : 0.0.1
+//////////////////////////////////////////////////////////
+
+
+// ----------------------------
+// Compiler Directives
+// ----------------------------
+
+// #![allow(unused)]
+// #![allow(unused_doc_comments)]
+
+
+// ----------------------------
+// Imports
+// ----------------------------
+
+// Standard Libraries
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Internal Modules
+use crate::notebook_formats;
+use crate::{
+    cell_output_stem, clean_notebook, determine_output_names, is_blank_source, notebook_comment_prefixes,
+    resolve_output_names, split_cells, CollapseConfig, PromptProfile,
+};
+
+
+// ----------------------------
+// Data Structures
+// ----------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NotebookCleaner
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Active prompt profile, blank line rules, the `--adf-json` switch and the `MAX_CELL_CHARS` cap.
+    ///
+    /// Output:
+    /// - Cleaning rules applied to every notebook of a run (main, compare and reference notebooks).
+    ///
+    /// Exceptions:
+    /// - None.
+
+    profile: PromptProfile,
+    collapse: CollapseConfig,
+    adf_json: bool,
+    max_cell_chars: Option<usize>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Notebook
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Notebook path and raw source.
+    ///
+    /// Output:
+    /// - Raw and cleaned source plus the output names derived from the path.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    pub original_source: String,
+    pub cleaned_source: String,
+    pub input_path: PathBuf,
+    pub output_stem: String,
+    pub output_display_name: String,
+}
+
+
+// ----------------------------
+// Notebook Cleaner
+// ----------------------------
+
+impl NotebookCleaner
+{
+    pub fn new(profile: PromptProfile) -> Self
+    {
+        //! Creates a cleaner for the given profile with the default blank line rules.
+
+        NotebookCleaner { profile, collapse: CollapseConfig::default(), adf_json: false, max_cell_chars: None }
+    }
+
+    pub fn with_collapse(mut self, collapse: CollapseConfig) -> Self
+    {
+        //! Sets the blank line rules, see [`crate::collapse_blank_lines`].
+
+        self.collapse = collapse;
+        self
+    }
+
+    pub fn with_adf_json(mut self, adf_json: bool) -> Self
+    {
+        //! Enables Data Factory JSON metadata stripping for `.json` inputs (`--adf-json`).
+
+        self.adf_json = adf_json;
+        self
+    }
+
+    pub fn with_max_cell_chars(mut self, max_cell_chars: Option<usize>) -> Self
+    {
+        //! Sets the per-cell size cap, see [`crate::truncate_large_cells`].
+
+        self.max_cell_chars = max_cell_chars;
+        self
+    }
+
+    pub fn clean(&self, source: &str, input_path: &Path) -> String
+    {
+        //! Cleans notebook source, see [`clean_notebook`].
+
+        clean_notebook(source, self.profile, input_path, &self.collapse, self.adf_json, self.max_cell_chars)
+    }
+
+    pub fn comment_prefixes(&self, source: &str, input_path: &Path) -> &'static [&'static str]
+    {
+        //! Returns the comment prefixes used for notebook markers, see [`notebook_comment_prefixes`].
+
+        notebook_comment_prefixes(source, self.profile, input_path)
+    }
+}
+
+
+// ----------------------------
+// Notebook
+// ----------------------------
+
+impl Notebook
+{
+    pub fn from_source(input_path: &Path, source: String, cleaner: &NotebookCleaner) -> Self
+    {
+        //! Builds a notebook from source that is already in memory.
+        //!
+        //! # Inputs
+        //! - `input_path`: Notebook path, used for language detection and output names.
+        //! - `source`: Raw notebook source.
+        //! - `cleaner`: Cleaning rules for the run.
+        //!
+        //! # Returns
+        //! - Notebook with the cleaned source and the names from [`determine_output_names`].
+        //!
+        //! # Panics
+        //! - If `input_path` has no filename.
+
+        let cleaned_source = cleaner.clean(&source, input_path);
+        let (output_stem, output_display_name) = determine_output_names(input_path);
+
+        Notebook
+        {
+            original_source: source,
+            cleaned_source,
+            input_path: input_path.to_path_buf(),
+            output_stem,
+            output_display_name,
+        }
+    }
+
+    pub fn load(path: &Path, cleaner: &NotebookCleaner) -> Result<Notebook, String>
+    {
+        //! Reads and cleans a notebook file.
+        //!
+        //! # Inputs
+        //! - `path`: Notebook path, or a directory of part files (see [`notebook_formats::is_part_file_dir`]).
+        //! - `cleaner`: Cleaning rules for the run.
+        //!
+        //! # Returns
+        //! - `Ok(Notebook)` when the file (or every part file) can be read; part files are joined in lexical order.
+        //! - `Err(String)` with an `[ERR]` message otherwise.
+
+        let source = Notebook::read_source(path)?;
+
+        Ok(Notebook::from_source(path, source, cleaner))
+    }
+
+    pub fn read_source(path: &Path) -> Result<String, String>
+    {
+        //! Reads the raw source of a notebook file, or joins the part files of a part file directory.
+        //!
+        //! # Inputs
+        //! - `path`: Notebook path, or a directory of part files (see [`notebook_formats::is_part_file_dir`]).
+        //!
+        //! # Returns
+        //! - `Ok(String)` with the raw source; part files are joined in lexical order.
+        //! - `Err(String)` with an `[ERR]` message otherwise.
+
+        if path.is_dir()
+        {
+            notebook_formats::concat_part_files(path)
+        }
+        else
+        {
+            fs::read_to_string(path)
+                .map_err(|err| format!("[ERR] - Failed to read file {}: {}", path.display(), err))
+        }
+    }
+
+    pub fn with_output_name(mut self, notebook_name: Option<&str>) -> Self
+    {
+        //! Applies a `--notebook-name` override to the output names, see [`resolve_output_names`].
+
+        if notebook_name.is_some()
+        {
+            (self.output_stem, self.output_display_name) = resolve_output_names(&self.input_path, notebook_name);
+        }
+        self
+    }
+
+    pub fn cells(&self, cleaner: &NotebookCleaner) -> Vec<Notebook>
+    {
+        //! Splits the notebook into one notebook per cell for `--per-cell`.
+        //!
+        //! # Inputs
+        //! - `cleaner`: Cleaning rules for the run; each cell is cleaned on its own.
+        //!
+        //! # Returns
+        //! - Non-empty cells in order, see [`split_cells`], named `<stem>/cell-NN` (numbered from `01`).
+        //! - The whole notebook as `cell-01` when it has no cell markers.
+
+        let comment_prefixes = cleaner.comment_prefixes(&self.original_source, &self.input_path);
+
+        split_cells(&self.original_source, comment_prefixes)
+            .into_iter()
+            .map(|source| Notebook::from_source(&self.input_path, source, cleaner))
+            .filter(|cell| !is_blank_source(&cell.cleaned_source))
+            .enumerate()
+            .map(|(index, mut cell)|
+            {
+                cell.output_stem = cell_output_stem(&self.output_stem, index + 1);
+                cell.output_display_name = format!("{} (cell {})", self.output_display_name, index + 1);
+                cell
+            })
+            .collect()
+    }
+
+    pub fn file_name(&self) -> String
+    {
+        //! Returns the input file name, falling back to the full path.
+
+        self.input_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.input_path.display().to_string())
+    }
+}
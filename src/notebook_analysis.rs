@@ -0,0 +1,277 @@
+//////////////////////////////////////////////////////////
+// AUTHOR   : Stefan B. J. Meeuwessen
+// CREATION : 2026-10-16
+// VERSION  : 0.0.1
+//////////////////////////////////////////////////////////
+
+
+// ----------------------------
+// Compiler Directives
+// ----------------------------
+
+// #![allow(unused)]
+// #![allow(unused_doc_comments)]
+
+
+// ----------------------------
+// Imports
+// ----------------------------
+
+// Standard Libraries
+use std::path::Path;
+
+// Internal Modules
+use crate::PromptProfile;
+
+
+// ----------------------------
+// Static Platform Patterns
+// ----------------------------
+
+static PLATFORM_PATTERNS: &[(PromptProfile, &[&str])] = &[
+    (PromptProfile::Databricks, &["spark.sql", "display(df)"]),
+    (PromptProfile::Synapse, &["%%sql", "synapsesql"]),
+    (PromptProfile::Fabric, &["import msal", "fabric."]),
+    (PromptProfile::Aws, &["import boto3", "s3://"]),
+    (PromptProfile::Gcp, &["bq.query", "from google.cloud"]),
+];
+
+
+// ----------------------------
+// Platform Detection
+// ----------------------------
+
+pub fn detect_notebook_platform(source: &str) -> Option<PromptProfile>
+{
+    //! Guesses the notebook platform from platform-specific source patterns.
+    //!
+    //! # Inputs
+    //! - `source`: Raw or cleaned notebook source.
+    //!
+    //! # Returns
+    //! - `Some(profile)` for the platform with the most pattern occurrences.
+    //! - `None` when no pattern matches, or when two platforms tie for the highest count.
+
+    let mut best: Option<(PromptProfile, usize)> = None;
+    let mut tied = false;
+
+    for (profile, patterns) in PLATFORM_PATTERNS
+    {
+        let hits: usize = patterns
+            .iter()
+            .map(|pattern| source.matches(pattern).count())
+            .sum();
+
+        if hits == 0
+        {
+            continue;
+        }
+
+        match best
+        {
+            Some((_, best_hits)) if hits < best_hits => {}
+            Some((_, best_hits)) if hits == best_hits => tied = true,
+            _ =>
+            {
+                best = Some((*profile, hits));
+                tied = false;
+            }
+        }
+    }
+
+    if tied
+    {
+        None
+    }
+    else
+    {
+        best.map(|(profile, _)| profile)
+    }
+}
+
+
+// ----------------------------
+// Language Detection
+// ----------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Language
+{
+    /// Type: Enum.
+    /// Input:
+    /// - Notebook extension or dominant cell content, see [`detect_language`].
+    ///
+    /// Output:
+    /// - Language whose comment prefixes are used when stripping metadata and comment headers.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    Python,
+    Sql,
+    Scala,
+    R,
+    Unknown,
+}
+
+impl Language
+{
+    pub fn comment_prefixes(self) -> &'static [&'static str]
+    {
+        //! Returns the line comment prefixes for this language.
+        //!
+        //! # Returns
+        //! - `#` for Python/R, `--` for SQL, `//` for Scala, all three for `Unknown`.
+
+        match self
+        {
+            Language::Python | Language::R => &["#"],
+            Language::Sql => &["--"],
+            Language::Scala => &["//"],
+            Language::Unknown => &["#", "--", "//"],
+        }
+    }
+
+    pub fn code_fence_tag(self) -> &'static str
+    {
+        //! Returns the Markdown code fence language tag for this language.
+        //!
+        //! # Returns
+        //! - `python`, `sql`, `scala` or `r`; an empty tag for `Unknown`.
+
+        match self
+        {
+            Language::Python => "python",
+            Language::Sql => "sql",
+            Language::Scala => "scala",
+            Language::R => "r",
+            Language::Unknown => "",
+        }
+    }
+}
+
+fn classify_line(line: &str) -> Option<Language>
+{
+    //! Guesses the language of a single source line from its leading keyword.
+
+    let trimmed = line.trim_start();
+    let lower = trimmed.to_ascii_lowercase();
+
+    if lower.starts_with("%%sql")
+        || ["select ", "insert ", "create ", "merge ", "update ", "delete ", "drop ", "with ", "where ", "join "]
+            .iter()
+            .any(|keyword| lower.starts_with(keyword))
+        || (lower.starts_with("from ") && !lower.contains(" import "))
+    {
+        return Some(Language::Sql);
+    }
+
+    if lower.starts_with("%%spark")
+        || ["val ", "var ", "object ", "case class ", "import org.", "import scala.", "import java."]
+            .iter()
+            .any(|keyword| trimmed.starts_with(keyword))
+        || (trimmed.starts_with("def ") && trimmed.contains('='))
+    {
+        return Some(Language::Scala);
+    }
+
+    if lower.starts_with("%%pyspark")
+        || ["import ", "print(", "class "].iter().any(|keyword| trimmed.starts_with(keyword))
+        || (trimmed.starts_with("from ") && trimmed.contains(" import "))
+        || (trimmed.starts_with("def ") && trimmed.trim_end().ends_with(':'))
+    {
+        return Some(Language::Python);
+    }
+
+    if trimmed.contains("<- ") || trimmed.starts_with("library(")
+    {
+        return Some(Language::R);
+    }
+
+    None
+}
+
+pub fn detect_language(source: &str, path: &Path) -> Language
+{
+    //! Detects the dominant language of a notebook.
+    //!
+    //! # Inputs
+    //! - `source`: Raw notebook source.
+    //! - `path`: Notebook path; a language-specific extension wins over content sniffing.
+    //!
+    //! # Returns
+    //! - The language for `.py`, `.sql`, `.scala`, `.r` and `.rmd` files.
+    //! - Otherwise the language with the most matching lines (keywords such as `import`, `SELECT`, `val`).
+    //! - `Language::Unknown` when no line matches or the top languages tie.
+
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str()
+    {
+        "py" => return Language::Python,
+        "sql" => return Language::Sql,
+        "scala" => return Language::Scala,
+        "r" | "rmd" => return Language::R,
+        _ => {}
+    }
+
+    let mut counts = [
+        (Language::Python, 0usize),
+        (Language::Sql, 0usize),
+        (Language::Scala, 0usize),
+        (Language::R, 0usize),
+    ];
+
+    for language in source.lines().filter_map(classify_line)
+    {
+        if let Some(entry) = counts.iter_mut().find(|(candidate, _)| *candidate == language)
+        {
+            entry.1 += 1;
+        }
+    }
+
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    match counts
+    {
+        [(_, 0), ..] => Language::Unknown,
+        [(_, top), (_, second), ..] if top == second => Language::Unknown,
+        [(language, _), ..] => language,
+    }
+}
+
+
+// ----------------------------
+// Import Extraction
+// ----------------------------
+
+pub fn extract_imports(source: &str) -> Vec<String>
+{
+    //! Collects the Python import statements of a notebook.
+    //!
+    //! # Inputs
+    //! - `source`: Cleaned notebook source.
+    //!
+    //! # Returns
+    //! - Each `import ...` and `from ... import ...` line, trimmed, in first-seen order without duplicates.
+    //! - An empty list when the notebook has no imports.
+    //!
+    //! # Notes
+    //! - Indented imports (inside functions) are included; only the first line of a multi-line import is kept.
+
+    let mut imports: Vec<String> = Vec::new();
+    for line in source.lines().map(str::trim)
+    {
+        let is_import = line.starts_with("import ")
+            || (line.starts_with("from ") && line.contains(" import "));
+
+        if is_import && !imports.iter().any(|existing| existing == line)
+        {
+            imports.push(line.to_string());
+        }
+    }
+    imports
+}
@@ -0,0 +1,205 @@
+//////////////////////////////////////////////////////////
+// AUTHOR   : Stefan B. J. Meeuwessen
+// CREATION : 2026-10-16
+// VERSION  : 0.0.1
+//////////////////////////////////////////////////////////
+
+
+// ----------------------------
+// Compiler Directives
+// ----------------------------
+
+// #![allow(unused)]
+// #![allow(unused_doc_comments)]
+
+
+// ----------------------------
+// Imports
+// ----------------------------
+
+// Standard Libraries
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// External Libraries
+use serde_json::Value;
+
+
+// ----------------------------
+// Static Format Settings
+// ----------------------------
+
+/// Top-level Data Factory keys that only carry service metadata, removed with `--adf-json`.
+pub const ADF_METADATA_KEYS: &[&str] = &["etag", "lastModifiedAt", "id", "type"];
+
+/// File name prefix of the ordered part files some exports split a notebook into (`part-00.py`, `part-01.py`).
+const PART_FILE_PREFIX: &str = "part-";
+
+
+// ----------------------------
+// Data Factory JSON
+// ----------------------------
+
+pub fn strip_adf_metadata(json_source: &str) -> Result<String, String>
+{
+    //! Removes Data Factory service metadata from a pipeline JSON document.
+    //!
+    //! # Inputs
+    //! - `json_source`: Raw Data Factory pipeline JSON.
+    //!
+    //! # Returns
+    //! - `Ok(String)` with the top-level [`ADF_METADATA_KEYS`] removed, pretty-printed.
+    //! - `Err(String)` when the source is not valid JSON.
+    //!
+    //! # Notes
+    //! - Only top-level keys are removed; `activities`, `parameters`, `variables` and `annotations`
+    //!   (and nested `id`/`type` keys) are kept for the documentation.
+
+    let mut document: Value = serde_json::from_str(json_source)
+        .map_err(|err| format!("[ERR] - Failed to parse Data Factory JSON: {}", err))?;
+
+    if let Some(object) = document.as_object_mut()
+    {
+        for key in ADF_METADATA_KEYS
+        {
+            object.remove(*key);
+        }
+    }
+
+    serde_json::to_string_pretty(&document)
+        .map_err(|err| format!("[ERR] - Failed to serialize Data Factory JSON: {}", err))
+}
+
+
+// ----------------------------
+// Jupyter Notebooks
+// ----------------------------
+
+pub fn ipynb_cell_sources(ipynb_source: &str) -> Option<Vec<String>>
+{
+    //! Reads the cell sources of a Jupyter (`.ipynb`) notebook.
+    //!
+    //! # Inputs
+    //! - `ipynb_source`: Raw `.ipynb` JSON.
+    //!
+    //! # Returns
+    //! - `Some(cells)` with the source of each cell in order; list-form sources are joined as stored.
+    //! - `None` when the source is not valid JSON or has no `cells` array.
+
+    let document: Value = serde_json::from_str(ipynb_source).ok()?;
+    let cells = document.get("cells")?.as_array()?;
+
+    Some(
+        cells
+            .iter()
+            .map(|cell| match cell.get("source")
+            {
+                Some(Value::String(source)) => source.clone(),
+                Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+                _ => String::new(),
+            })
+            .collect(),
+    )
+}
+
+
+// ----------------------------
+// Part File Notebooks
+// ----------------------------
+
+fn is_part_file_name(name: &str) -> bool
+{
+    //! Returns `true` for `part-<digits>` file names with an optional extension, e.g. `part-01.py`.
+
+    let stem = name.split_once('.').map_or(name, |(stem, _)| stem);
+    stem.strip_prefix(PART_FILE_PREFIX)
+        .is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn part_file_entries(dir: &Path) -> Option<Vec<PathBuf>>
+{
+    //! Lists the visible entries of a directory when every one of them is a part file.
+    //!
+    //! # Returns
+    //! - `Some(paths)` sorted by file name (lexical order) when the directory holds at least one
+    //!   part file and nothing else besides hidden entries.
+    //! - `None` otherwise, or when the directory cannot be read.
+
+    let mut parts = Vec::new();
+    for entry in fs::read_dir(dir).ok()?
+    {
+        let path = entry.ok()?.path();
+        let name = path.file_name()?.to_string_lossy().to_string();
+        if name.starts_with('.')
+        {
+            continue;
+        }
+        if !path.is_file() || !is_part_file_name(&name)
+        {
+            return None;
+        }
+        parts.push(path);
+    }
+
+    if parts.is_empty()
+    {
+        return None;
+    }
+    parts.sort_by_key(|path| path.file_name().map(|name| name.to_os_string()));
+    Some(parts)
+}
+
+pub fn is_part_file_dir(path: &Path) -> bool
+{
+    //! Returns `true` when `path` is a directory holding only the part files of one notebook.
+    //!
+    //! # Notes
+    //! - Fabric item folders (`<name>.Notebook/notebook-content.py`) and batch directories are not part file directories.
+
+    path.is_dir() && part_file_entries(path).is_some()
+}
+
+pub fn part_files_size(dir: &Path) -> Option<u64>
+{
+    //! Sums the sizes of the part files of a notebook.
+    //!
+    //! # Inputs
+    //! - `dir`: Directory holding `part-NN.*` files, see [`is_part_file_dir`].
+    //!
+    //! # Returns
+    //! - `Some(bytes)` with the total size of the part files.
+    //! - `None` when the directory is not a part file directory or a part's metadata cannot be read.
+
+    part_file_entries(dir)?
+        .iter()
+        .map(|part| fs::metadata(part).ok().map(|metadata| metadata.len()))
+        .sum()
+}
+
+pub fn concat_part_files(dir: &Path) -> Result<String, String>
+{
+    //! Joins the part files of a notebook into a single source.
+    //!
+    //! # Inputs
+    //! - `dir`: Directory holding `part-NN.*` files, see [`is_part_file_dir`].
+    //!
+    //! # Returns
+    //! - `Ok(String)` with the parts in lexical file name order, each ending with a newline.
+    //! - `Err(String)` when the directory is not a part file directory or a part cannot be read.
+
+    let parts = part_file_entries(dir)
+        .ok_or_else(|| format!("[ERR] - {} is not a directory of part files (part-00, part-01, ...).", dir.display()))?;
+
+    let mut source = String::new();
+    for part in parts
+    {
+        let content = fs::read_to_string(&part)
+            .map_err(|err| format!("[ERR] - Failed to read part file {}: {}", part.display(), err))?;
+        source.push_str(&content);
+        if !source.is_empty() && !source.ends_with('\n')
+        {
+            source.push('\n');
+        }
+    }
+    Ok(source)
+}
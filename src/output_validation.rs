@@ -0,0 +1,296 @@
+//////////////////////////////////////////////////////////
+// AUTHOR   : Stefan B. J. Meeuwessen
+// CREATION : 2026-10-16
+// VERSION  : 0.0.1
+//////////////////////////////////////////////////////////
+
+
+// ----------------------------
+// Compiler Directives
+// ----------------------------
+
+// #![allow(unused)]
+// #![allow(unused_doc_comments)]
+
+
+// ----------------------------
+// Imports
+// ----------------------------
+
+// External Libraries
+use serde_json::Value;
+
+
+// ----------------------------
+// JSON Schema Helper Functions
+// ----------------------------
+
+fn type_matches(value: &Value, expected: &str) -> bool
+{
+    //! Checks a JSON value against a JSON Schema `type` name.
+
+    match expected
+    {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn validate_node(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>)
+{
+    //! Validates one JSON node and recurses into `properties` and `items`.
+
+    if let Some(expected) = schema.get("type")
+    {
+        let allowed: Vec<&str> = match expected
+        {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|name| type_matches(value, name))
+        {
+            errors.push(format!("{}: expected type {}, got {}", path, allowed.join(" | "), type_name(value)));
+            return;
+        }
+    }
+
+    if let Some(object) = value.as_object()
+    {
+        if let Some(required) = schema.get("required").and_then(Value::as_array)
+        {
+            for key in required.iter().filter_map(Value::as_str)
+            {
+                if !object.contains_key(key)
+                {
+                    errors.push(format!("{}: missing required key '{}'", path, key));
+                }
+            }
+        }
+
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object)
+        {
+            for (key, property_schema) in properties
+            {
+                if let Some(property_value) = object.get(key)
+                {
+                    validate_node(property_value, property_schema, &format!("{}.{}", path, key), errors);
+                }
+            }
+        }
+    }
+
+    if let (Some(items), Some(item_schema)) = (value.as_array(), schema.get("items"))
+    {
+        for (index, item) in items.iter().enumerate()
+        {
+            validate_node(item, item_schema, &format!("{}[{}]", path, index), errors);
+        }
+    }
+}
+
+fn type_name(value: &Value) -> &'static str
+{
+    //! Returns the JSON Schema type name of a value.
+
+    match value
+    {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+
+// ----------------------------
+// Output Validation
+// ----------------------------
+
+pub fn validate_against_schema(value: &Value, schema: &Value) -> Result<(), Vec<String>>
+{
+    //! Validates model output against a lightweight JSON Schema subset.
+    //!
+    //! # Inputs
+    //! - `value`: Parsed model output.
+    //! - `schema`: JSON Schema document.
+    //!
+    //! # Returns
+    //! - `Ok(())` when the document matches.
+    //! - `Err(Vec<String>)` with one message per mismatch, prefixed with a `$.path`.
+    //!
+    //! # Notes
+    //! - Supports `type` (string or list), `required`, `properties` and `items`; other keywords are ignored.
+
+    let mut errors = Vec::new();
+    validate_node(value, schema, "$", &mut errors);
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+pub fn strip_json_code_fence(content: &str) -> &str
+{
+    //! Removes a surrounding Markdown code fence (```` ```json ````) from model output.
+    //!
+    //! # Returns
+    //! - The fenced body when `content` is wrapped in a fence, otherwise the trimmed content.
+
+    let trimmed = content.trim();
+    let Some(after_open) = trimmed.strip_prefix("```")
+    else
+    {
+        return trimmed;
+    };
+
+    match (after_open.find('\n'), after_open.strip_suffix("```"))
+    {
+        (Some(newline), Some(_)) => after_open[newline + 1..after_open.len() - 3].trim(),
+        _ => trimmed,
+    }
+}
+
+
+// ----------------------------
+// Output Normalization
+// ----------------------------
+
+fn fence_marker(line: &str) -> Option<(char, usize, &str)>
+{
+    //! Parses a Markdown code fence line.
+    //!
+    //! # Returns
+    //! - `Some((marker, length, info))` for a line starting with three or more backticks or tildes.
+    //! - `None` for any other line.
+
+    let trimmed = line.trim();
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = trimmed.chars().take_while(|c| *c == marker).count();
+    if length < 3
+    {
+        return None;
+    }
+    Some((marker, length, trimmed[length..].trim()))
+}
+
+pub fn unwrap_outer_fence(content: &str) -> String
+{
+    //! Unwraps model output that wraps the whole document in one Markdown code fence.
+    //!
+    //! # Inputs
+    //! - `content`: Model output.
+    //!
+    //! # Returns
+    //! - The fenced body when the output is a single ```` ```markdown ````, ```` ```md ```` or bare fence
+    //!   spanning the first to the last line.
+    //! - `content` unchanged otherwise, including docs that only contain fenced code among prose.
+    //!
+    //! # Notes
+    //! - Inner fences are allowed when they open with a language (```` ```python ````) and are closed;
+    //!   a bare inner fence at the top level would close the outer fence, so such output is left alone.
+
+    let trimmed = content.trim();
+    let lines: Vec<&str> = trimmed.lines().collect();
+    let [first, inner @ .., last] = lines.as_slice()
+    else
+    {
+        return content.to_string();
+    };
+
+    let Some((marker, length, info)) = fence_marker(first)
+    else
+    {
+        return content.to_string();
+    };
+    if !["", "markdown", "md"].contains(&info.to_ascii_lowercase().as_str())
+    {
+        return content.to_string();
+    }
+
+    let closes_outer = |line: &str| matches!(fence_marker(line), Some((m, l, "")) if m == marker && l >= length);
+    if !closes_outer(last)
+    {
+        return content.to_string();
+    }
+
+    let mut inner_open = false;
+    for line in inner
+    {
+        match fence_marker(line)
+        {
+            Some((m, _, "")) if inner_open && m == marker => inner_open = false,
+            Some(_) if inner_open => {}
+            Some((_, _, "")) if closes_outer(line) => return content.to_string(),
+            Some((m, l, _)) if m == marker && l >= length => inner_open = true,
+            _ => {}
+        }
+    }
+
+    if inner_open
+    {
+        return content.to_string();
+    }
+    inner.join("\n")
+}
+
+
+// ----------------------------
+// Section Validation
+// ----------------------------
+
+pub fn parse_required_sections(raw: &str) -> Vec<String>
+{
+    //! Parses the `REQUIRED_SECTIONS` value.
+    //!
+    //! # Inputs
+    //! - `raw`: Comma-separated heading titles, e.g. `Overview, Inputs, Outputs, Lineage`.
+    //!
+    //! # Returns
+    //! - Trimmed, non-empty titles in order.
+
+    raw.split(',')
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn check_required_sections(content: &str, required: &[String]) -> Vec<String>
+{
+    //! Checks that generated documentation contains the required section headings.
+    //!
+    //! # Inputs
+    //! - `content`: Generated Markdown.
+    //! - `required`: Heading titles that must be present.
+    //!
+    //! # Returns
+    //! - The required titles without a matching `##` or `###` heading, in the order given.
+    //!
+    //! # Notes
+    //! - Titles match case-insensitively; surrounding whitespace and closing `#`s are ignored.
+
+    let headings: Vec<String> = content
+        .lines()
+        .filter_map(|line|
+        {
+            let trimmed = line.trim_start();
+            trimmed
+                .strip_prefix("### ")
+                .or_else(|| trimmed.strip_prefix("## "))
+                .map(|title| title.trim().trim_end_matches('#').trim().to_lowercase())
+        })
+        .collect();
+
+    required
+        .iter()
+        .filter(|title| !headings.contains(&title.trim().to_lowercase()))
+        .cloned()
+        .collect()
+}
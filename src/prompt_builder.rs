@@ -0,0 +1,315 @@
+//////////////////////////////////////////////////////////
+// AUTHOR   : Stefan B. J. Meeuwessen
+// CREATION : 2026-10-16
+// VERSION  : 0.0.1
+//////////////////////////////////////////////////////////
+
+
+// ----------------------------
+// Compiler Directives
+// ----------------------------
+
+// #![allow(unused)]
+// #![allow(unused_doc_comments)]
+
+
+// ----------------------------
+// Imports
+// ----------------------------
+
+// Internal Modules
+use crate::logger::log_warn;
+use crate::notebook::Notebook;
+use crate::render_template;
+
+
+// ----------------------------
+// Static Prompt Text
+// ----------------------------
+
+const COMPARE_INSTRUCTIONS: &str = "Compare the old and new version of this code. \
+Document the new version using the documentation template, and add a section that describes \
+what changed from the old version to the new version (added, removed and modified logic).";
+
+/// Definition section used when no `--definition-format-template` is given, see [`PromptBuilder::with_definition_template`].
+pub const DEFAULT_DEFINITION_TEMPLATE: &str = "Definitions: {definitions}";
+
+/// Prompt layout used when `templates/prompt_layout.md` is missing, see [`assemble_prompt`].
+pub const DEFAULT_PROMPT_LAYOUT: &str = "Current date time: {date}
+
+Notebook filename: {filename}
+
+{definition_section}
+
+Documentation template: {template}
+
+Section filter: Generate only the {section} section of the documentation.
+
+{references}
+
+Imports:
+{imports}
+
+Instructions: {instructions}
+
+Code: {code}
+
+New Code: {new_code}
+
+Old Code: {old_code}";
+
+
+// ----------------------------
+// Data Structures
+// ----------------------------
+
+#[derive(Debug, Default)]
+pub struct PromptBuilder
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Prompt sections set through the `with_*` methods.
+    ///
+    /// Output:
+    /// - User prompt text sent to the chat endpoint.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    current_datetime: String,
+    notebook_filename: String,
+    definitions: String,
+    definition_template: Option<String>,
+    template: String,
+    source: String,
+    compare_source: Option<String>,
+    section_filter: Option<String>,
+    references: Vec<(String, String)>,
+    imports: Option<String>,
+    layout: Option<String>,
+}
+
+
+// ----------------------------
+// Prompt Builder
+// ----------------------------
+
+impl PromptBuilder
+{
+    pub fn new(current_datetime: &str, notebook: &Notebook) -> Self
+    {
+        //! Starts a prompt for one notebook.
+        //!
+        //! # Inputs
+        //! - `current_datetime`: Timestamp shown to the model.
+        //! - `notebook`: Notebook to document; its display name and cleaned source are used.
+
+        PromptBuilder
+        {
+            current_datetime: current_datetime.to_string(),
+            notebook_filename: notebook.output_display_name.clone(),
+            source: notebook.cleaned_source.clone(),
+            ..PromptBuilder::default()
+        }
+    }
+
+    pub fn with_definitions(mut self, definitions: &str) -> Self
+    {
+        //! Sets the definitions section.
+
+        self.definitions = definitions.to_string();
+        self
+    }
+
+    pub fn with_definition_template(mut self, definition_template: &str) -> Self
+    {
+        //! Replaces [`DEFAULT_DEFINITION_TEMPLATE`] with a custom definition section (`--definition-format-template`).
+        //!
+        //! # Inputs
+        //! - `definition_template`: Section text where `{definitions}` is replaced by the definitions table,
+        //!   e.g. `Column Dictionary:\n{definitions}`.
+
+        self.definition_template = Some(definition_template.to_string());
+        self
+    }
+
+    pub fn with_template(mut self, template: &str) -> Self
+    {
+        //! Sets the rendered documentation template.
+
+        self.template = template.to_string();
+        self
+    }
+
+    pub fn with_compare_source(mut self, old: &Notebook) -> Self
+    {
+        //! Switches the prompt to change documentation between two notebook versions.
+        //!
+        //! # Inputs
+        //! - `old`: Previous version of the notebook; the notebook passed to [`PromptBuilder::new`] is the new one.
+        //!
+        //! # Notes
+        //! - Replaces the `Code:` section with `New Code:` and `Old Code:` sections.
+
+        self.compare_source = Some(old.cleaned_source.clone());
+        self
+    }
+
+    pub fn with_section_filter(mut self, name: &str) -> Self
+    {
+        //! Restricts the generated documentation to one named template section.
+        //!
+        //! # Inputs
+        //! - `name`: Section name, e.g. `Parameters`.
+
+        self.section_filter = Some(name.trim().to_string());
+        self
+    }
+
+    pub fn with_reference(mut self, reference: &Notebook) -> Self
+    {
+        //! Adds a companion notebook as context only.
+        //!
+        //! # Inputs
+        //! - `reference`: Reference notebook; its file name and cleaned source are shown to the model.
+        //!
+        //! # Notes
+        //! - Each call adds one `Reference (not the subject)` section; the model is told to document only the code.
+
+        self.references.push((reference.file_name(), reference.cleaned_source.clone()));
+        self
+    }
+
+    pub fn with_imports(mut self, imports: &[String]) -> Self
+    {
+        //! Adds an `Imports:` section listing the notebook's import statements (`--include-imports`).
+        //!
+        //! # Inputs
+        //! - `imports`: Import lines, see [`crate::notebook_analysis::extract_imports`].
+        //!
+        //! # Notes
+        //! - An empty list leaves the section out.
+
+        self.imports = (!imports.is_empty()).then(|| imports.join("\n"));
+        self
+    }
+
+    pub fn with_layout(mut self, layout: &str) -> Self
+    {
+        //! Replaces [`DEFAULT_PROMPT_LAYOUT`] with a custom section layout (`templates/prompt_layout.md`).
+
+        self.layout = Some(layout.to_string());
+        self
+    }
+
+    pub fn build(&self) -> String
+    {
+        //! Builds the user prompt.
+        //!
+        //! # Returns
+        //! - Prompt text with date, filename, definitions, template and code sections,
+        //!   plus the section filter instruction, reference and imports sections when set,
+        //!   arranged by the layout (see [`assemble_prompt`]).
+
+        let references = self
+            .references
+            .iter()
+            .map(|(name, source)| format!(
+                "Reference (not the subject) {}: Use this only as context; do not document it.\n{}",
+                name,
+                source
+            ))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let definition_section = self
+            .definition_template
+            .as_deref()
+            .unwrap_or(DEFAULT_DEFINITION_TEMPLATE)
+            .replace("{definitions}", &self.definitions);
+
+        let (code, new_code, old_code, instructions) = match &self.compare_source
+        {
+            Some(old) => (None, Some(self.source.as_str()), Some(old.as_str()), Some(COMPARE_INSTRUCTIONS)),
+            None => (Some(self.source.as_str()), None, None, None),
+        };
+
+        assemble_prompt(
+            self.layout.as_deref().unwrap_or(DEFAULT_PROMPT_LAYOUT),
+            &[
+                ("date", Some(self.current_datetime.as_str())),
+                ("filename", Some(self.notebook_filename.as_str())),
+                ("definitions", Some(self.definitions.as_str())),
+                ("definition_section", Some(definition_section.as_str())),
+                ("template", Some(self.template.as_str())),
+                ("section", self.section_filter.as_deref()),
+                ("references", (!self.references.is_empty()).then_some(references.as_str())),
+                ("imports", self.imports.as_deref()),
+                ("instructions", instructions),
+                ("code", code),
+                ("new_code", new_code),
+                ("old_code", old_code),
+            ],
+        )
+    }
+}
+
+
+// ----------------------------
+// Prompt Layout
+// ----------------------------
+
+pub fn assemble_prompt(layout: &str, parts: &[(&str, Option<&str>)]) -> String
+{
+    //! Fills a prompt layout with the prompt sections.
+    //!
+    //! # Inputs
+    //! - `layout`: Layout text with `{name}` placeholders, e.g. [`DEFAULT_PROMPT_LAYOUT`].
+    //! - `parts`: Section names and values; `None` marks a section that is not part of this prompt.
+    //!
+    //! # Returns
+    //! - The layout with placeholders replaced, where each blank-line separated block that uses
+    //!   a `None` section is left out and the remaining blocks are joined with one blank line.
+    //! - Unknown placeholders are left as-is with a `[WRN]`.
+    //!
+    //! # Notes
+    //! - Blocks are taken from the layout before substitution, so blank lines inside values are kept.
+
+    let absent: Vec<String> = parts
+        .iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(name, _)| format!("{{{}}}", name))
+        .collect();
+
+    let mut blocks: Vec<Vec<&str>> = vec![Vec::new()];
+    for line in layout.lines()
+    {
+        if line.trim().is_empty()
+        {
+            blocks.push(Vec::new());
+        }
+        else if let Some(block) = blocks.last_mut()
+        {
+            block.push(line);
+        }
+    }
+
+    let kept_layout = blocks
+        .into_iter()
+        .filter(|block| !block.is_empty())
+        .map(|block| block.join("\n"))
+        .filter(|block| !absent.iter().any(|placeholder| block.contains(placeholder.as_str())))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let present: Vec<(&str, &str)> = parts
+        .iter()
+        .filter_map(|(name, value)| value.map(|value| (*name, value)))
+        .collect();
+    let (prompt, unresolved) = render_template(&kept_layout, &present);
+    for placeholder in &unresolved
+    {
+        log_warn!("Unknown placeholder '{{{}}}' in prompt layout was left as-is.", placeholder);
+    }
+    prompt
+}
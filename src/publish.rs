@@ -0,0 +1,137 @@
+//////////////////////////////////////////////////////////
+// AUTHOR   : Stefan B. J. Meeuwessen
+// CREATION : 2026-10-16
+// VERSION  : 0.0.1
+//////////////////////////////////////////////////////////
+
+
+// ----------------------------
+// Compiler Directives
+// ----------------------------
+
+// #![allow(unused)]
+// #![allow(unused_doc_comments)]
+
+
+// ----------------------------
+// Imports
+// ----------------------------
+
+// External Libraries
+use reqwest::Url;
+use reqwest::blocking::Client;
+use serde_json::{json, Value};
+
+
+// ----------------------------
+// Data Structures
+// ----------------------------
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WikiPageRequest
+{
+    /// Type: Struct.
+    /// Input:
+    /// - `WIKI_API_URL`, the wiki token and the generated page.
+    ///
+    /// Output:
+    /// - HTTP `PUT` request sent by a [`WikiSender`].
+    ///
+    /// Exceptions:
+    /// - None.
+
+    pub url: String,
+    pub token: String,
+    pub body: Value,
+}
+
+
+// ----------------------------
+// Senders
+// ----------------------------
+
+pub trait WikiSender
+{
+    //! Sends a wiki page request and returns the HTTP status code.
+    //!
+    //! # Notes
+    //! - Implemented for the blocking `reqwest` client; tests inject a recording sender.
+
+    fn put(&self, request: &WikiPageRequest) -> Result<u16, String>;
+}
+
+impl WikiSender for Client
+{
+    fn put(&self, request: &WikiPageRequest) -> Result<u16, String>
+    {
+        self.put(&request.url)
+            .bearer_auth(&request.token)
+            .json(&request.body)
+            .send()
+            .map(|response| response.status().as_u16())
+            .map_err(|err| err.to_string())
+    }
+}
+
+
+// ----------------------------
+// Publishing
+// ----------------------------
+
+pub fn build_wiki_page_request(url: &str, token: &str, page: &str, content: &str) -> Result<WikiPageRequest, String>
+{
+    //! Builds the `PUT` request for one wiki page.
+    //!
+    //! # Inputs
+    //! - `url`: `WIKI_API_URL` base endpoint.
+    //! - `token`: Wiki access token from Azure Key Vault.
+    //! - `page`: Page name (the output stem).
+    //! - `content`: Generated Markdown.
+    //!
+    //! # Returns
+    //! - Request with the page appended as a percent-encoded path segment and `{"content": ...}` as body.
+    //! - `Err(String)` when `url` is not a valid base URL.
+
+    let mut page_url = Url::parse(url.trim())
+        .map_err(|err| format!("[ERR] - Invalid WIKI_API_URL '{}': {}", url, err))?;
+
+    page_url
+        .path_segments_mut()
+        .map_err(|_| format!("[ERR] - WIKI_API_URL '{}' cannot be used as a base URL", url))?
+        .pop_if_empty()
+        .push(page);
+
+    Ok(WikiPageRequest
+    {
+        url: page_url.to_string(),
+        token: token.to_string(),
+        body: json!({ "content": content }),
+    })
+}
+
+pub fn publish_doc(client: &impl WikiSender, url: &str, token: &str, page: &str, content: &str) -> Result<(), String>
+{
+    //! Publishes generated Markdown to the wiki endpoint.
+    //!
+    //! # Inputs
+    //! - `client`: Sender used for the HTTP `PUT`.
+    //! - `url`, `token`, `page`, `content`: See [`build_wiki_page_request`].
+    //!
+    //! # Returns
+    //! - `Ok(())` on a 2xx status.
+    //! - `Err(String)` when the request cannot be built or sent, or the status is not 2xx.
+
+    let request = build_wiki_page_request(url, token, page, content)?;
+    let status = client
+        .put(&request)
+        .map_err(|err| format!("[ERR] - Failed to publish '{}' to wiki: {}", page, err))?;
+
+    if (200..300).contains(&status)
+    {
+        Ok(())
+    }
+    else
+    {
+        Err(format!("[ERR] - Wiki rejected '{}' with HTTP status {}", page, status))
+    }
+}
@@ -0,0 +1,159 @@
+//////////////////////////////////////////////////////////
+// AUTHOR   : Stefan B. J. Meeuwessen
+// CREATION : 2026-10-16
+// VERSION  : 0.0.1
+//////////////////////////////////////////////////////////
+
+
+// ----------------------------
+// Compiler Directives
+// ----------------------------
+
+// #![allow(unused)]
+// #![allow(unused_doc_comments)]
+
+
+// ----------------------------
+// Imports
+// ----------------------------
+
+// Standard Libraries
+use std::thread;
+use std::time::Duration;
+
+// Internal Modules
+use crate::logger::log_warn;
+
+
+// ----------------------------
+// Defaults
+// ----------------------------
+
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+pub const DEFAULT_RETRY_DELAY_MS: u64 = 1000;
+
+
+// ----------------------------
+// Data Structures
+// ----------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RetryPolicy
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Attempt count (at least 1) and fixed delay between attempts.
+    ///
+    /// Output:
+    /// - Retry behaviour for one kind of remote call (AI API or Key Vault).
+    ///
+    /// Exceptions:
+    /// - None.
+
+    pub attempts: u32,
+    pub delay_ms: u64,
+}
+
+impl Default for RetryPolicy
+{
+    fn default() -> Self
+    {
+        Self { attempts: DEFAULT_RETRY_ATTEMPTS, delay_ms: DEFAULT_RETRY_DELAY_MS }
+    }
+}
+
+
+// ----------------------------
+// Retry Functions
+// ----------------------------
+
+impl RetryPolicy
+{
+    pub fn run<T, E>(
+        &self,
+        label: &str,
+        mut operation: impl FnMut() -> Result<T, E>,
+        should_retry: impl Fn(&Result<T, E>) -> bool,
+    ) -> Result<T, E>
+    {
+        //! Runs `operation` until it succeeds, is not retryable, or attempts are exhausted.
+        //!
+        //! # Inputs
+        //! - `label`: Call name used in `[WRN]` retry messages.
+        //! - `operation`: The remote call.
+        //! - `should_retry`: Whether a result warrants another attempt.
+        //!
+        //! # Returns
+        //! - The result of the last attempt.
+
+        let mut attempt = 1;
+        loop
+        {
+            let result = operation();
+            if attempt >= self.attempts || !should_retry(&result)
+            {
+                return result;
+            }
+
+            log_warn!(
+                "{} attempt {}/{} failed; retrying in {} ms.",
+                label,
+                attempt,
+                self.attempts,
+                self.delay_ms
+            );
+            thread::sleep(Duration::from_millis(self.delay_ms));
+            attempt += 1;
+        }
+    }
+}
+
+pub fn is_retryable_status(status: u16) -> bool
+{
+    //! Returns `true` for HTTP statuses worth retrying (429 and 500, 502, 503, 504).
+
+    matches!(status, 429 | 500 | 502 | 503 | 504)
+}
+
+pub fn resolve_retry_policy(
+    cli_attempts: Option<u32>,
+    cli_delay_ms: Option<u64>,
+    env_attempts: Option<&str>,
+    env_delay_ms: Option<&str>,
+) -> Result<RetryPolicy, String>
+{
+    //! Resolves the AI API retry policy from CLI flags and env fallbacks.
+    //!
+    //! # Inputs
+    //! - `cli_attempts`, `cli_delay_ms`: `--retry-attempts` / `--retry-delay` values.
+    //! - `env_attempts`, `env_delay_ms`: `DOXCER_RETRY_ATTEMPTS` / `DOXCER_RETRY_DELAY_MS` values.
+    //!
+    //! # Returns
+    //! - `Ok(RetryPolicy)` with CLI values first, then env values, then the defaults.
+    //! - `Err(String)` when an env value is not a number or the attempt count is 0.
+
+    let attempts = match (cli_attempts, env_attempts.map(str::trim).filter(|raw| !raw.is_empty()))
+    {
+        (Some(attempts), _) => attempts,
+        (None, Some(raw)) => raw
+            .parse::<u32>()
+            .map_err(|_| format!("[ERR] - Invalid DOXCER_RETRY_ATTEMPTS '{}'.", raw))?,
+        (None, None) => DEFAULT_RETRY_ATTEMPTS,
+    };
+
+    let delay_ms = match (cli_delay_ms, env_delay_ms.map(str::trim).filter(|raw| !raw.is_empty()))
+    {
+        (Some(delay_ms), _) => delay_ms,
+        (None, Some(raw)) => raw
+            .parse::<u64>()
+            .map_err(|_| format!("[ERR] - Invalid DOXCER_RETRY_DELAY_MS '{}'.", raw))?,
+        (None, None) => DEFAULT_RETRY_DELAY_MS,
+    };
+
+    if attempts == 0
+    {
+        return Err("[ERR] - Retry attempts must be at least 1.".to_string());
+    }
+
+    Ok(RetryPolicy { attempts, delay_ms })
+}
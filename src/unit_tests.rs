@@ -19,7 +19,10 @@
 
 // Standard Libraries
 use super::*;
-use std::path::Path;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 
 // ----------------------------
@@ -39,6 +42,67 @@ fn make_args(raw: &[&str]) -> Vec<String>
     raw.iter().map(|v| v.to_string()).collect()
 }
 
+/// Temp directories created by this test process, used to keep [`TestDir`] names unique.
+static TEST_DIR_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+struct TestDir
+{
+    /// Type: Struct.
+    /// Input:
+    /// - Label of the test that needs a scratch directory.
+    ///
+    /// Output:
+    /// - Fresh directory under the system temp dir, removed again on drop (also when the test panics).
+    ///
+    /// Exceptions:
+    /// - Panics when the directory cannot be created.
+
+    path: PathBuf,
+}
+
+impl TestDir
+{
+    fn new(label: &str) -> Self
+    {
+        //! Creates `doxcer-<label>-<pid>-<n>` in the system temp dir.
+
+        let path = std::env::temp_dir().join(format!(
+            "doxcer-{}-{}-{}",
+            label,
+            std::process::id(),
+            TEST_DIR_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path).unwrap();
+        TestDir { path }
+    }
+}
+
+impl Deref for TestDir
+{
+    type Target = Path;
+
+    fn deref(&self) -> &Path
+    {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TestDir
+{
+    fn as_ref(&self) -> &Path
+    {
+        &self.path
+    }
+}
+
+impl Drop for TestDir
+{
+    fn drop(&mut self)
+    {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
 
 // ----------------------------
 // main.rs
@@ -148,6 +212,45 @@ fn parse_pipeline_flag()
     assert_eq!(parsed.profile, PromptProfile::Pipeline);
 }
 
+#[test]
+fn parse_azureml_flag()
+{
+    //! Passes when `-azureml` resolves to `PromptProfile::AzureML`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "-azureml", "test/example.py"])).unwrap();
+    assert_eq!(parsed.file_path, "test/example.py");
+    assert_eq!(parsed.profile, PromptProfile::AzureML);
+}
+
+#[test]
+fn parse_gcp_flag()
+{
+    //! Passes when `-gcp` resolves to `PromptProfile::Gcp`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "-gcp", "test/example.py"])).unwrap();
+    assert_eq!(parsed.profile, PromptProfile::Gcp);
+    assert_eq!(parsed.file_path, "test/example.py");
+}
+
+#[test]
+fn parse_scala_flag()
+{
+    //! Passes when `-scala` resolves to `PromptProfile::Scala`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "-scala", "jobs/etl_pipeline.scala"])).unwrap();
+    assert_eq!(parsed.profile, PromptProfile::Scala);
+    assert_eq!(parsed.file_path, "jobs/etl_pipeline.scala");
+}
+
 #[test]
 fn parse_accepts_any_argument_order()
 {
@@ -171,7 +274,11 @@ fn parse_conflicting_selectors_fail()
 
     let err = parse_cli_args(&make_args(&["doxcer", "-fabric", "test/example.py", "-synapse"]))
         .unwrap_err();
-    assert!(err.contains("Conflicting selectors"));
+    assert_eq!(
+        err,
+        ParseError::ConflictingSelectors { first: "fabric".to_string(), second: "synapse".to_string() }
+    );
+    assert!(err.to_string().contains("Conflicting selectors"));
 }
 
 #[test]
@@ -184,20 +291,22 @@ fn parse_unknown_selector_fails()
 
     let err = parse_cli_args(&make_args(&["doxcer", "-unknown", "test/example.py"]))
         .unwrap_err();
-    assert!(err.contains("Unknown selector"));
+    assert_eq!(err, ParseError::UnknownSelector { arg: "-unknown".to_string() });
+    assert!(err.to_string().contains("Unknown selector '-unknown'. Supported selectors: -default, -fabric, -synapse"));
 }
 
 #[test]
 fn parse_double_dash_selector_fails()
 {
-    //! Passes when GNU-style profile selectors remain unsupported.
+    //! Passes when GNU-style profile selectors remain unsupported and are reported as unknown flags.
     //!
     //! # Panics
     //! - If assertions fail.
 
     let err = parse_cli_args(&make_args(&["doxcer", "--fabric", "test/example.py"]))
         .unwrap_err();
-    assert!(err.contains("Unknown selector"));
+    assert_eq!(err, ParseError::UnknownFlag { arg: "--fabric".to_string() });
+    assert!(err.to_string().contains("Unknown flag '--fabric'"));
 }
 
 #[test]
@@ -216,7 +325,7 @@ fn help_request_is_detected_only_for_single_help_token()
 #[test]
 fn usage_text_contains_help_and_selector_list()
 {
-    //! Passes when usage text is built from the shared template and selector list.
+    //! Passes when usage text is built from the shared template, selector list and exit code constants.
     //!
     //! # Panics
     //! - If assertions fail.
@@ -224,6 +333,37 @@ fn usage_text_contains_help_and_selector_list()
     let usage = usage_text();
     assert!(usage.contains("doxcer --help"));
     assert!(usage.contains("-fabric | -synapse"));
+    assert!(usage.contains("Exit with code 7 when any [WRN] was emitted."));
+    assert!(usage.contains("Fail (exit code 6) instead of warning"));
+    assert!(!usage.contains("{exit_"));
+}
+
+#[test]
+fn usage_text_aligns_option_descriptions()
+{
+    //! Passes when every flag description starts in the shared column, on the flag's line
+    //! or on the next line for flags too long for the column.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    const DESCRIPTION_COLUMN: usize = "[INF] -   --no-template-fallback   ".len();
+
+    let usage = usage_text();
+    let lines: Vec<&str> = usage.lines().collect();
+    for (index, line) in lines.iter().enumerate()
+    {
+        let Some(flag) = line.strip_prefix("[INF] -   --")
+        else
+        {
+            continue;
+        };
+        // A flag without a two-space gap is too long for the column; its description is on the next line.
+        let description_line = if flag.contains("  ") { line } else { lines[index + 1] };
+        let (padding, description) = description_line.split_at(DESCRIPTION_COLUMN);
+        assert!(padding.ends_with("  ") || padding.trim_start_matches("[INF] - ").trim().is_empty(), "misaligned: {}", line);
+        assert!(!description.starts_with(' '), "misaligned: {}", line);
+    }
 }
 
 #[test]
@@ -237,10 +377,25 @@ fn supported_selector_list_uses_canonical_only()
     let supported = supported_selector_list();
     assert_eq!(
         supported,
-        "-fabric, -synapse, -databricks, -powerbi, -aws, -datafactory, -pipeline"
+        "-default, -fabric, -synapse, -databricks, -powerbi, -aws, -datafactory, -pipeline, -azureml, -gcp, -scala, -lakehouse"
     );
 }
 
+#[test]
+fn supported_selectors_agree_with_selector_list()
+{
+    //! Passes when the selector flags and the display list contain the same selectors in the same order.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let selectors = supported_selectors();
+    assert_eq!(selectors.join(", "), supported_selector_list());
+    assert_eq!(selectors.first(), Some(&"-default"));
+    assert!(selectors.contains(&"-datafactory"));
+    assert!(selectors.iter().all(|selector| parse_profile_selector(selector).is_some()));
+}
+
 #[test]
 fn parse_missing_path_fails()
 {
@@ -250,7 +405,8 @@ fn parse_missing_path_fails()
     //! - If assertions fail.
 
     let err = parse_cli_args(&make_args(&["doxcer", "-fabric"])).unwrap_err();
-    assert!(err.contains("Missing required notebook path argument"));
+    assert_eq!(err, ParseError::MissingNotebookPath);
+    assert!(err.to_string().contains("Missing required notebook path argument"));
 }
 
 #[test]
@@ -262,7 +418,11 @@ fn parse_multiple_paths_fail()
     //! - If assertions fail.
 
     let err = parse_cli_args(&make_args(&["doxcer", "test/a.py", "test/b.py"])).unwrap_err();
-    assert!(err.contains("Multiple input paths"));
+    assert_eq!(
+        err,
+        ParseError::MultipleNotebookPaths { first: "test/a.py".to_string(), second: "test/b.py".to_string() }
+    );
+    assert!(err.to_string().contains("Multiple input paths"));
 }
 
 #[test]
@@ -315,270 +475,4588 @@ fn prompt_profile_spec_returns_expected_datafactory_metadata()
 }
 
 #[test]
-fn is_metadata_line_detects_supported_prefixes()
+fn render_template_substitutes_known_placeholders()
 {
-    //! Passes when metadata prefixes are detected and non-metadata lines are ignored.
+    //! Passes when known placeholders are replaced and nothing is reported unresolved.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    assert!(is_metadata_line("# METADATA x"));
-    assert!(is_metadata_line("  # META y"));
-    assert!(is_metadata_line("\t# CELL 2"));
-    assert!(!is_metadata_line("print('hello')"));
-    assert!(!is_metadata_line("#METADATA"));
+    let (rendered, unresolved) = render_template(
+        "File {filename} on {date}\n{definitions}",
+        &[("filename", "nb.py"), ("date", "2026-01-01"), ("definitions", "| a |")],
+    );
+    assert_eq!(rendered, "File nb.py on 2026-01-01\n| a |");
+    assert!(unresolved.is_empty());
 }
 
 #[test]
-fn strip_notebook_metadata_removes_only_metadata_lines()
+fn render_template_reports_unknown_placeholders_once()
 {
-    //! Passes when metadata lines are removed and normal lines remain.
+    //! Passes when unknown placeholders stay literal and are reported once each.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let source = "# METADATA a\nprint('x')\n# META b\n# CELL c\nprint('y')";
-    let cleaned = strip_notebook_metadata(source);
-    assert_eq!(cleaned, "print('x')\nprint('y')");
+    let (rendered, unresolved) = render_template(
+        "{owner} wrote {filename}; ask {owner} or {team_2}",
+        &[("filename", "nb.py")],
+    );
+    assert_eq!(rendered, "{owner} wrote nb.py; ask {owner} or {team_2}");
+    assert_eq!(unresolved, vec!["owner".to_string(), "team_2".to_string()]);
 }
 
 #[test]
-fn collapse_blank_lines_reduces_consecutive_blank_runs()
+fn render_template_ignores_free_text_braces()
 {
-    //! Passes when multiple consecutive blank lines collapse to one.
+    //! Passes when instruction text in braces and unbalanced braces are not treated as placeholders.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let source = "line1\n\n\nline2\n   \n\t\nline3";
-    let collapsed = collapse_blank_lines(source);
-    assert_eq!(collapsed, "line1\n\nline2\n   \nline3");
+    let source = "| {Notebook bestandsnaam} | {Huidige datum en tijd} | {{filename}} | open {";
+    let (rendered, unresolved) = render_template(source, &[("filename", "nb.py")]);
+    assert_eq!(rendered, "| {Notebook bestandsnaam} | {Huidige datum en tijd} | {nb.py} | open {");
+    assert!(unresolved.is_empty());
 }
 
 #[test]
-fn is_assistant_role_accepts_missing_or_assistant_and_rejects_others()
+fn interpolate_env_substitutes_defined_variables()
 {
-    //! Passes when assistant role filtering is backward-compatible and strict for non-assistant values.
+    //! Passes when `${NAME}` is replaced with the environment value.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    assert!(is_assistant_role(None));
-    assert!(is_assistant_role(Some("assistant")));
-    assert!(is_assistant_role(Some("AsSiStAnT")));
-    assert!(!is_assistant_role(Some("user")));
+    let lookup = |name: &str| (name == "TEAM_NAME").then(|| "Data Platform".to_string());
+
+    let (rendered, undefined) = interpolate_env("Team: ${TEAM_NAME} costs $5", false, lookup);
+    assert_eq!(rendered, "Team: Data Platform costs $5");
+    assert!(undefined.is_empty());
 }
 
 #[test]
-fn looks_like_internal_prompt_detects_only_full_prompt_envelope()
+fn interpolate_env_keeps_or_blanks_undefined_variables()
 {
-    //! Passes when prompt-echo detection requires all internal prompt markers.
+    //! Passes when undefined variables are reported once and kept literal or blanked.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let prompt_echo = "Current date time: 2026-02-20 14:03:25\n\nNotebook filename: nb.py\n\nDefinitions: none\n\nDocumentation template: # template\n\nCode: print('x')";
-    let normal_markdown = "# Title\n\nThis is generated documentation.";
+    let content = "Wiki: ${DOXCER_TEST_UNDEFINED_WIKI}/${DOXCER_TEST_UNDEFINED_WIKI}";
 
-    assert!(looks_like_internal_prompt(prompt_echo));
-    assert!(!looks_like_internal_prompt(normal_markdown));
+    let (kept, undefined) = interpolate_env(content, false, |_| None);
+    assert_eq!(kept, content);
+    assert_eq!(undefined, vec!["DOXCER_TEST_UNDEFINED_WIKI".to_string()]);
+
+    let (blanked, _) = interpolate_env(content, true, |_| None);
+    assert_eq!(blanked, "Wiki: /");
 }
 
 #[test]
-fn determine_output_names_for_standard_file()
+fn interpolate_env_unescapes_double_dollar_literal()
 {
-    //! Passes when standard files resolve to stem + original filename.
+    //! Passes when `$${literal}` renders as `${literal}` and is not reported or rendered as a placeholder.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let (name, ext_name) = determine_output_names(Path::new("x/y/pipeline.py"));
-    assert_eq!(name, "pipeline");
-    assert_eq!(ext_name, "pipeline.py");
+    let (interpolated, undefined) = interpolate_env("Use $${literal} here", false, |_| Some("x".to_string()));
+    assert_eq!(interpolated, "Use ${literal} here");
+    assert!(undefined.is_empty());
+
+    let (rendered, unresolved) = render_template(&interpolated, &[("literal", "x")]);
+    assert_eq!(rendered, "Use ${literal} here");
+    assert!(unresolved.is_empty());
 }
 
 #[test]
-fn determine_output_names_for_notebook_content_uses_parent_folder_name()
+fn is_blank_source_detects_empty_and_whitespace_only_input()
 {
-    //! Passes when notebook-content.py uses parent folder name.
+    //! Passes when zero-length and whitespace-only sources are blank and code is not.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let (name, ext_name) = determine_output_names(Path::new("Sales.Notebook/notebook-content.py"));
-    assert_eq!(name, "Sales");
-    assert_eq!(ext_name, "Sales.py");
+    assert!(is_blank_source(""));
+    assert!(is_blank_source(" \n\t\r\n"));
+    assert!(!is_blank_source("\n# comment\n"));
+    assert!(!is_blank_source("print(1)"));
 }
 
 #[test]
-fn determine_output_names_for_root_notebook_content_uses_fallback()
+fn is_metadata_line_detects_supported_prefixes()
 {
-    //! Passes when root notebook-content.py uses fallback output names.
+    //! Passes when metadata prefixes are detected and non-metadata lines are ignored.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let (name, ext_name) = determine_output_names(Path::new("notebook-content.py"));
-    assert_eq!(name, "notebook-content");
-    assert_eq!(ext_name, "notebook-content.py");
+    assert!(is_metadata_line("# METADATA x", &["#"], PromptProfile::Default));
+    assert!(is_metadata_line("  # META y", &["#"], PromptProfile::Default));
+    assert!(is_metadata_line("\t# CELL 2", &["#"], PromptProfile::Default));
+    assert!(is_metadata_line("-- METADATA x", &["--"], PromptProfile::Default));
+    assert!(!is_metadata_line("-- METADATA x", &["#"], PromptProfile::Default));
+    assert!(!is_metadata_line("print('hello')", &["#"], PromptProfile::Default));
+    assert!(!is_metadata_line("#METADATA", &["#"], PromptProfile::Default));
 }
 
 #[test]
-fn find_repo_root_path_contains_project_markers()
+fn is_metadata_line_ignores_case_and_whitespace_around_keyword()
 {
-    //! Passes when resolved repo root contains expected marker files.
+    //! Passes when lowercase and mixed-case `METADATA` markers, trailing spaces and tab separators are detected
+    //! while `CELL` and `META` stay case-sensitive and words that merely start with a keyword are kept.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let root = find_repo_root_path();
-    assert!(has_repo_markers(&root));
+    assert!(is_metadata_line("# metadata", &["#"], PromptProfile::Default));
+    assert!(is_metadata_line("# MetaData {", &["#"], PromptProfile::Default));
+    assert!(is_metadata_line("# METADATA   ", &["#"], PromptProfile::Default));
+    assert!(is_metadata_line("# META\t", &["#"], PromptProfile::Default));
+    assert!(is_metadata_line("#\tMETADATA", &["#"], PromptProfile::Default));
+    assert!(is_metadata_line("-- \t CELL ****", &["--"], PromptProfile::Default));
+    assert!(!is_metadata_line("# metaprogramming helpers", &["#"], PromptProfile::Default));
+    assert!(!is_metadata_line("# CELLULAR data", &["#"], PromptProfile::Default));
+
+    assert!(!is_metadata_line("# Cell 3 loads the dim tables", &["#"], PromptProfile::Default));
+    assert!(!is_metadata_line("# cell", &["#"], PromptProfile::Default));
+    assert!(!is_metadata_line("# Meta info for reviewers", &["#"], PromptProfile::Default));
+
+    assert_eq!(
+        split_cells("# CELL\nx = 1\n# Cell 3 loads the dim tables\n#\tCELL\ny = 2", &["#"]),
+        vec!["x = 1\n# Cell 3 loads the dim tables", "y = 2"]
+    );
 }
 
 #[test]
-fn find_env_paths_returns_expected_files_in_order()
+fn has_skip_marker_detects_marker_at_top()
 {
-    //! Passes when env paths are returned in the expected order.
+    //! Passes when a `DOXCER:SKIP` comment in the first lines is detected for each comment style.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let env_paths = find_env_paths();
-    let names: Vec<String> = env_paths
-        .iter()
-        .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
-        .collect();
+    assert!(has_skip_marker("# DOXCER:SKIP\nimport pandas as pd"));
+    assert!(has_skip_marker("# Fabric notebook source\n\n  #DOXCER:SKIP  \nprint(1)"));
+    assert!(has_skip_marker("-- DOXCER:SKIP\nSELECT 1"));
+    assert!(has_skip_marker("// DOXCER:SKIP\nval x = 1"));
+}
 
-    assert_eq!(
-        names,
-        vec![
-            "system.env".to_string(),
-            "definitions.env".to_string(),
-            "azure_key_vault.env".to_string(),
-            "ai_model.env".to_string()
-        ]
-    );
+#[test]
+fn has_skip_marker_ignores_absent_or_partial_marker()
+{
+    //! Passes when notebooks without the exact marker comment are not skipped.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert!(!has_skip_marker("import pandas as pd\nprint(1)"));
+    assert!(!has_skip_marker(""));
+    assert!(!has_skip_marker("# DOXCER:SKIP later, once reviewed\nprint(1)"));
+    assert!(!has_skip_marker("print('DOXCER:SKIP')"));
 }
 
 #[test]
-fn find_prompt_path_finds_existing_profile_prompt()
+fn has_skip_marker_ignores_marker_buried_deep()
 {
-    //! Passes when a profile with a template resolves to that template.
+    //! Passes when a marker below the first `SKIP_MARKER_SCAN_LINES` lines is not counted.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let path = find_prompt_path(&PromptProfile::Fabric);
-    assert_eq!(path.file_name().unwrap().to_string_lossy(), "fabric_prompt.md");
-    assert!(path.exists());
+    let mut lines = vec!["print(1)"; SKIP_MARKER_SCAN_LINES];
+    lines.push("# DOXCER:SKIP");
+    assert!(!has_skip_marker(&lines.join("\n")));
+
+    lines.remove(0);
+    assert!(has_skip_marker(&lines.join("\n")));
+    assert_eq!(outcome_exit_code(GenerateOutcome::Skipped), 0);
 }
 
 #[test]
-fn find_context_and_docs_paths_point_to_expected_locations()
+fn notebook_has_skip_marker_looks_past_fabric_header()
 {
-    //! Passes when context and docs paths resolve to expected names.
+    //! Passes when a marker in the first Fabric cell is found below a long `# METADATA` header,
+    //! and a marker in a later cell is not counted.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let context_path = find_context_path();
-    assert_eq!(context_path.file_name().unwrap().to_string_lossy(), "context.md");
-    assert!(context_path.exists());
+    let mut header = vec!["# Fabric notebook source", "", "# METADATA ********************", ""];
+    header.extend(vec!["# META {}"; SKIP_MARKER_SCAN_LINES]);
+    let header = header.join("\n");
+    let path = Path::new("Sales.Notebook/notebook-content.py");
 
-    let docs_path = find_docs_path();
-    assert_eq!(docs_path.file_name().unwrap().to_string_lossy(), "newly-documented");
-    assert_eq!(
-        docs_path.parent().and_then(|p| p.file_name()).unwrap().to_string_lossy(),
-        "docs"
+    let marked = format!("{}\n\n# CELL ********************\n\n# DOXCER:SKIP\nprint(1)", header);
+    assert!(notebook_has_skip_marker(&marked, path, &["#"], PromptProfile::Fabric));
+
+    let later = format!(
+        "{}\n\n# CELL ********************\n\nprint(1)\n\n# CELL ********************\n\n# DOXCER:SKIP",
+        header
     );
+    assert!(!notebook_has_skip_marker(&later, path, &["#"], PromptProfile::Fabric));
 }
 
+#[test]
+fn notebook_has_skip_marker_reads_ipynb_cells()
+{
+    //! Passes when the marker is read from the first `.ipynb` cell source, in string and list form.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let path = Path::new("lab/experiment.ipynb");
+    let listed = r##"{"cells": [{"cell_type": "code", "source": ["# DOXCER:SKIP\n", "print(1)"]}], "nbformat": 4}"##;
+    let single = r##"{"cells": [{"cell_type": "markdown", "source": ""}, {"cell_type": "code", "source": "# DOXCER:SKIP"}]}"##;
+    let later = r##"{"cells": [{"cell_type": "code", "source": "print(1)"}, {"cell_type": "code", "source": "# DOXCER:SKIP"}]}"##;
 
-// ----------------------------
-// fetch_definitions.rs
-// ----------------------------
+    assert!(notebook_has_skip_marker(listed, path, &["#"], PromptProfile::Default));
+    assert!(notebook_has_skip_marker(single, path, &["#"], PromptProfile::Default));
+    assert!(!notebook_has_skip_marker(later, path, &["#"], PromptProfile::Default));
+    assert!(!notebook_has_skip_marker("# DOXCER:SKIP", path, &["#"], PromptProfile::Default));
+}
 
 #[test]
-fn definitions_markdown_table_returns_info_for_empty_columns()
+fn strip_notebook_metadata_removes_only_metadata_lines()
 {
-    //! Passes when empty columns return the no-definitions info message.
+    //! Passes when metadata lines are removed and normal lines remain.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let result = crate::fetch_definitions::format_definitions_as_markdown_table(&[], &[]);
-    assert_eq!(result, "[INF] - No definition rows returned.");
+    let source = "# METADATA a\nprint('x')\n# META b\n# CELL c\nprint('y')";
+    let cleaned = strip_notebook_metadata(source, &["#"], PromptProfile::Default);
+    assert_eq!(cleaned, "print('x')\nprint('y')");
 }
 
 #[test]
-fn definitions_markdown_table_renders_header_and_separator()
+fn is_metadata_line_detects_databricks_scala_markers_only_for_scala_profile()
 {
-    //! Passes when only header/separator are rendered for empty rows.
+    //! Passes when `// MAGIC`, `// COMMAND ----------` and `// DBTITLE` are metadata only with `PromptProfile::Scala`.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let columns = vec!["col_a".to_string(), "col_b".to_string()];
-    let rows: Vec<Vec<String>> = Vec::new();
+    for line in ["// MAGIC %md", "// COMMAND ----------", "  // DBTITLE 1,Load orders"]
+    {
+        assert!(is_metadata_line(line, &["//"], PromptProfile::Scala), "{}", line);
+        assert!(!is_metadata_line(line, &["//"], PromptProfile::Default), "{}", line);
+    }
+    assert!(is_metadata_line("// CELL 1", &["//"], PromptProfile::Scala));
+    assert!(!is_metadata_line("// COMMAND to run next", &["//"], PromptProfile::Scala));
+    assert!(!is_metadata_line("val df = spark.table(\"orders\")", &["//"], PromptProfile::Scala));
+}
 
-    let result = crate::fetch_definitions::format_definitions_as_markdown_table(&columns, &rows);
-    let expected = concat!("| col_a | col_b |\n", "| --- | --- |\n");
+#[test]
+fn strip_leading_comment_block_removes_header_block()
+{
+    //! Passes when a license header and the blank lines after it are removed.
+    //!
+    //! # Panics
+    //! - If assertions fail.
 
-    assert_eq!(result, expected);
+    let source = "# Copyright (c) Contoso\n# Licensed under MIT\n#\n\nimport pandas as pd\n# inline comment\nprint(1)";
+    assert_eq!(
+        strip_leading_comment_block(source, &["#"]),
+        "import pandas as pd\n# inline comment\nprint(1)"
+    );
 }
 
 #[test]
-fn definitions_markdown_table_escapes_pipe_and_newline_characters()
+fn strip_leading_comment_block_keeps_source_without_header()
 {
-    //! Passes when cell values are escaped/sanitized for Markdown.
+    //! Passes when source that starts with code is returned unchanged.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let columns = vec!["name|raw".to_string(), "description".to_string()];
-    let rows = vec![vec![
-        "left|value".to_string(),
-        "line1\nline2\rline3".to_string(),
-    ]];
+    let source = "SELECT 1\n-- trailing comment";
+    assert_eq!(strip_leading_comment_block(source, &["--"]), source);
+}
 
-    let result = crate::fetch_definitions::format_definitions_as_markdown_table(&columns, &rows);
-    let expected = concat!(
-        "| name\\|raw | description |\n",
-        "| --- | --- |\n",
-        "| left\\|value | line1 line2 line3 |\n"
+#[test]
+fn strip_leading_comment_block_stops_at_code_directly_after_header()
+{
+    //! Passes when code immediately after the header is kept, using the language's prefix.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = "-- Banner\n-- Owner: data team\nSELECT * FROM dbo.orders";
+    assert_eq!(
+        strip_leading_comment_block(source, crate::notebook_analysis::Language::Sql.comment_prefixes()),
+        "SELECT * FROM dbo.orders"
     );
+}
 
-    assert_eq!(result, expected);
+#[test]
+fn collapse_blank_lines_reduces_consecutive_blank_runs()
+{
+    //! Passes when multiple consecutive blank lines collapse to one.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = "line1\n\n\nline2\n   \n\t\nline3";
+    let collapsed = collapse_blank_lines(source, &CollapseConfig::default());
+    assert_eq!(collapsed, "line1\n\nline2\n   \nline3");
 }
 
 #[test]
-fn definitions_markdown_table_pads_missing_cells_and_ignores_extra_cells()
+fn collapse_blank_lines_honours_max_consecutive_and_trim_trailing()
 {
-    //! Passes when short rows are padded and extra row columns are ignored.
+    //! Passes when `max_consecutive` limits blank runs, `0` removes blank lines and
+    //! `trim_trailing` drops only the trailing blank lines.
     //!
     //! # Panics
     //! - If assertions fail.
 
-    let columns = vec!["a".to_string(), "b".to_string()];
-    let rows = vec![
-        vec!["only-a".to_string()],
-        vec!["a2".to_string(), "b2".to_string(), "extra".to_string()],
-    ];
+    let source = "line1\n\n\n\nline2\n\n\n";
+    let config = |max_consecutive, trim_trailing| CollapseConfig { max_consecutive, trim_trailing, preserve_line_endings: false };
 
-    let result = crate::fetch_definitions::format_definitions_as_markdown_table(&columns, &rows);
-    let expected = concat!(
-        "| a | b |\n",
-        "| --- | --- |\n",
-        "| only-a |  |\n",
-        "| a2 | b2 |\n"
-    );
+    assert_eq!(collapse_blank_lines(source, &config(1, false)), "line1\n\nline2\n");
+    assert_eq!(collapse_blank_lines(source, &config(2, false)), "line1\n\n\nline2\n\n");
+    assert_eq!(collapse_blank_lines(source, &config(0, false)), "line1\nline2");
+    assert_eq!(collapse_blank_lines(source, &config(1, true)), "line1\n\nline2");
+    assert_eq!(CollapseConfig::default(), config(1, false));
 
-    assert_eq!(result, expected);
+    let cli_args = parse_cli_args(&make_args(&["doxcer", "--trim-trailing-blanks", "test/example.py"])).unwrap();
+    assert!(cli_args.trim_trailing_blanks);
+    assert_eq!(collapse_config(&cli_args), config(1, true));
+}
+
+#[test]
+fn collapse_blank_lines_preserves_predominant_crlf_line_endings()
+{
+    //! Passes when CRLF input keeps CRLF with `preserve_line_endings`, mixed input follows the
+    //! majority ending and the default always joins with LF.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let preserve = CollapseConfig { preserve_line_endings: true, ..CollapseConfig::default() };
+    let crlf_source = "line1\r\n\r\n\r\nline2\r\nline3\r\n";
+
+    assert_eq!(collapse_blank_lines(crlf_source, &preserve), "line1\r\n\r\nline2\r\nline3");
+    assert_eq!(collapse_blank_lines(crlf_source, &CollapseConfig::default()), "line1\n\nline2\nline3");
+
+    assert_eq!(collapse_blank_lines("a\r\nb\r\nc\nd", &preserve), "a\r\nb\r\nc\r\nd");
+    assert_eq!(collapse_blank_lines("a\nb\nc\r\nd", &preserve), "a\nb\nc\nd");
+
+    let cli_args = parse_cli_args(&make_args(&["doxcer", "--preserve-line-endings", "test/example.py"])).unwrap();
+    assert_eq!(collapse_config(&cli_args), preserve);
+
+    let cleaned = clean_notebook("# CELL\r\nx = 1\r\n\r\n\r\ny = 2\r\n", PromptProfile::Default, Path::new("nb.py"), &preserve, false, None);
+    assert!(cleaned.contains("x = 1\r\n\r\ny = 2"));
+    assert!(!cleaned.replace("\r\n", "").contains('\n'));
 }
 
+#[test]
+fn clean_notebook_keeps_crlf_when_cells_are_capped()
+{
+    //! Passes when the line ending is detected on the raw source, so capping an oversized cell does not
+    //! switch a CRLF notebook to LF.
+    //!
+    //! # Panics
+    //! - If assertions fail.
 
-// ----------------------------
-// fetch_secrets.rs
-// ----------------------------
+    let preserve = CollapseConfig { max_consecutive: 1, trim_trailing: false, preserve_line_endings: true };
+    let big_cell = format!("data = [{}]", "1, ".repeat(40));
+    let source = format!("# CELL\r\nx = 1\r\n# CELL\r\n{}\r\n{}\r\n{}\r\n", big_cell, big_cell, big_cell);
 
-// No deterministic unit-test surface is currently exposed without adding seams or mocks.
+    let cleaned = clean_notebook(&source, PromptProfile::Default, Path::new("nb.py"), &preserve, false, Some(50));
+    assert!(cleaned.contains("[truncated]"));
+    assert!(cleaned.contains("x = 1\r\n"));
+    assert!(!cleaned.replace("\r\n", "").contains('\n'));
+}
+
+#[test]
+fn is_assistant_role_accepts_missing_or_assistant_and_rejects_others()
+{
+    //! Passes when assistant role filtering is backward-compatible and strict for non-assistant values.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert!(is_assistant_role(None));
+    assert!(is_assistant_role(Some("assistant")));
+    assert!(is_assistant_role(Some("AsSiStAnT")));
+    assert!(!is_assistant_role(Some("user")));
+}
+
+#[test]
+fn looks_like_internal_prompt_detects_only_full_prompt_envelope()
+{
+    //! Passes when prompt-echo detection requires all internal prompt markers.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let prompt_echo = "Current date time: 2026-02-20 14:03:25\n\nNotebook filename: nb.py\n\nDefinitions: none\n\nDocumentation template: # template\n\nCode: print('x')";
+    let normal_markdown = "# Title\n\nThis is generated documentation.";
+
+    assert!(looks_like_internal_prompt(prompt_echo));
+    assert!(!looks_like_internal_prompt(normal_markdown));
+}
+
+#[test]
+fn determine_output_names_for_standard_file()
+{
+    //! Passes when standard files resolve to stem + original filename.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let (name, ext_name) = determine_output_names(Path::new("x/y/pipeline.py"));
+    assert_eq!(name, "pipeline");
+    assert_eq!(ext_name, "pipeline.py");
+}
+
+#[test]
+fn determine_output_names_for_notebook_content_uses_parent_folder_name()
+{
+    //! Passes when notebook-content.py uses parent folder name.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let (name, ext_name) = determine_output_names(Path::new("Sales.Notebook/notebook-content.py"));
+    assert_eq!(name, "Sales");
+    assert_eq!(ext_name, "Sales.py");
+}
+
+#[test]
+fn determine_output_names_for_root_notebook_content_uses_fallback()
+{
+    //! Passes when root notebook-content.py uses fallback output names.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let (name, ext_name) = determine_output_names(Path::new("notebook-content.py"));
+    assert_eq!(name, "notebook-content");
+    assert_eq!(ext_name, "notebook-content.py");
+}
+
+#[test]
+fn determine_output_names_strips_notebook_extensions()
+{
+    //! Passes when `.Rmd`, `.scala`, `.sql` and `.ipynb` notebooks resolve to their bare name.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let cases = [
+        ("analysis/my_analysis.Rmd", "my_analysis", "my_analysis.Rmd"),
+        ("jobs/etl_pipeline.scala", "etl_pipeline", "etl_pipeline.scala"),
+        ("models/gold_model.sql", "gold_model", "gold_model.sql"),
+        ("lab/experiment.ipynb", "experiment", "experiment.ipynb"),
+        ("reports/summary.v2.qmd", "summary.v2", "summary.v2.qmd"),
+        ("scripts/cleanup.R", "cleanup", "cleanup.R"),
+    ];
+
+    for (path, expected_name, expected_ext_name) in cases
+    {
+        let (name, ext_name) = determine_output_names(Path::new(path));
+        assert_eq!(name, expected_name, "{}", path);
+        assert_eq!(ext_name, expected_ext_name, "{}", path);
+    }
+}
+
+#[test]
+fn doc_stats_counts_empty_content_as_zero()
+{
+    //! Passes when empty content reports zero lines, words, and chars.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(doc_stats(""), (0, 0, 0));
+}
+
+#[test]
+fn doc_stats_ignores_trailing_newline_for_line_count()
+{
+    //! Passes when a trailing newline does not add an extra line.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(doc_stats("# Title\n\nSome text here.\n"), (3, 5, 25));
+    assert_eq!(doc_stats("# Title\n\nSome text here."), (3, 5, 24));
+}
+
+#[test]
+fn doc_stats_counts_multibyte_characters_as_chars()
+{
+    //! Passes when multi-byte characters are counted as chars rather than bytes.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let content = "Überblick — café";
+    assert_eq!(doc_stats(content), (1, 3, 16));
+    assert!(content.len() > 16);
+}
+
+#[test]
+fn parse_notebook_name_flag()
+{
+    //! Passes when `--notebook-name` takes the following token as its value.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--notebook-name", "sales", "notebook-content.py"]))
+        .unwrap();
+    assert_eq!(parsed.notebook_name.as_deref(), Some("sales"));
+    assert_eq!(parsed.file_path, "notebook-content.py");
+
+    let err = parse_cli_args(&make_args(&["doxcer", "notebook-content.py", "--notebook-name"])).unwrap_err();
+    assert!(err.to_string().contains("Missing value for '--notebook-name'"));
+}
+
+#[test]
+fn resolve_output_names_prefers_notebook_name_over_inference()
+{
+    //! Passes when `--notebook-name` overrides inferred names and inference applies otherwise.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let input = Path::new("notebook-content.py");
+    assert_eq!(
+        resolve_output_names(input, Some("sales")),
+        ("sales".to_string(), "sales.py".to_string())
+    );
+    assert_eq!(
+        resolve_output_names(Path::new("Sales.Notebook/notebook-content.py"), None),
+        ("Sales".to_string(), "Sales.py".to_string())
+    );
+    assert_eq!(
+        resolve_output_names(Path::new("scripts/run"), Some("runner")),
+        ("runner".to_string(), "runner".to_string())
+    );
+}
+
+#[test]
+fn determine_output_names_for_json_pipelines()
+{
+    //! Passes when plain JSON pipelines keep their stem and Fabric pipeline content uses the folder name.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let (name, ext_name) = determine_output_names(Path::new("adf/pipeline/pl_load_sales.json"));
+    assert_eq!(name, "pl_load_sales");
+    assert_eq!(ext_name, "pl_load_sales.json");
+
+    let (name, ext_name) = determine_output_names(Path::new("Orchestrate.DataPipeline/pipeline-content.json"));
+    assert_eq!(name, "Orchestrate");
+    assert_eq!(ext_name, "Orchestrate.json");
+}
+
+#[test]
+fn parse_format_cmd_flag()
+{
+    //! Passes when `--format-cmd` keeps the full command string as one value.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "test/example.py", "--format-cmd", "mdformat -"]))
+        .unwrap();
+    assert_eq!(parsed.format_cmd.as_deref(), Some("mdformat -"));
+}
+
+#[cfg(unix)]
+#[test]
+fn pipe_through_formatter_passes_content_through_cat()
+{
+    //! Passes when a passthrough formatter returns the input unchanged.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let content = "# Title\n\n| a | b |\n";
+    assert_eq!(pipe_through_formatter("cat", content).unwrap(), content);
+}
+
+#[test]
+fn split_command_line_keeps_quoted_arguments_together()
+{
+    //! Passes when quoted paths and arguments with spaces stay one argument and Windows backslashes are kept.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(split_command_line("mdformat  -").unwrap(), vec!["mdformat", "-"]);
+    assert_eq!(
+        split_command_line(r#""C:\Program Files\fmt.exe" --wrap 'no limit' --title="a \"b\"" ''"#).unwrap(),
+        vec![r"C:\Program Files\fmt.exe", "--wrap", "no limit", r#"--title=a "b""#, ""]
+    );
+    assert!(split_command_line("   ").unwrap().is_empty());
+    assert!(split_command_line("fmt 'open").unwrap_err().contains("unclosed"));
+}
+
+#[test]
+fn pipe_through_formatter_fails_for_missing_command()
+{
+    //! Passes when a nonexistent formatter returns an error so `main` can fall back.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let err = pipe_through_formatter("doxcer-no-such-formatter --check", "# Title").unwrap_err();
+    assert!(err.contains("failed to start 'doxcer-no-such-formatter'"));
+    assert!(pipe_through_formatter("   ", "# Title").is_err());
+}
+
+#[test]
+fn parse_truncate_definitions_flag()
+{
+    //! Passes when `--truncate-definitions` parses a number and rejects non-numeric values.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--truncate-definitions", "500", "test/example.py"]))
+        .unwrap();
+    assert_eq!(parsed.truncate_definitions, Some(500));
+
+    let err = parse_cli_args(&make_args(&["doxcer", "--truncate-definitions", "lots", "test/example.py"]))
+        .unwrap_err();
+    assert!(err.to_string().contains("Invalid value 'lots' for '--truncate-definitions'"));
+}
+
+#[test]
+fn parse_stats_flag()
+{
+    //! Passes when `--stats` is off by default and enabled by the flag.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert!(!parse_cli_args(&make_args(&["doxcer", "test/example.py"])).unwrap().stats);
+    assert!(parse_cli_args(&make_args(&["doxcer", "test/example.py", "--stats"])).unwrap().stats);
+}
+
+#[test]
+fn is_below_min_output_checks_threshold_boundaries()
+{
+    //! Passes when content is rejected only below the threshold and the gate is off at `0`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert!(!is_below_min_output("", 0));
+    assert!(is_below_min_output("abcd", 5));
+    assert!(!is_below_min_output("abcde", 5));
+    assert!(!is_below_min_output("abcdef", 5));
+    assert!(is_below_min_output("  abcd \n", 5));
+}
+
+#[test]
+fn api_error_deserializes_typical_azure_error_json()
+{
+    //! Passes when a typical Azure error body maps to `ApiError` and a clean `[ERR]` line.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let body = r#"{"error":{"code":"DeploymentNotFound","message":"The API deployment for this resource does not exist.","status":404}}"#;
+
+    let api_error: ApiError = serde_json::from_str(body).unwrap();
+    assert_eq!(api_error.error.message, "The API deployment for this resource does not exist.");
+
+    assert_eq!(
+        describe_api_error(body).as_deref(),
+        Some("[ERR] - API returned error 'DeploymentNotFound': The API deployment for this resource does not exist.")
+    );
+    assert_eq!(
+        describe_api_error(r#"{"error":{"code":429,"message":"Rate limit exceeded."}}"#).as_deref(),
+        Some("[ERR] - API returned error '429': Rate limit exceeded.")
+    );
+    assert!(describe_api_error(r#"{"choices":"unexpected"}"#).is_none());
+}
+
+#[test]
+fn estimate_tokens_rounds_up_per_four_chars()
+{
+    //! Passes when token estimates use four characters per token, rounded up.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(estimate_tokens(""), 0);
+    assert_eq!(estimate_tokens("abcd"), 1);
+    assert_eq!(estimate_tokens("abcde"), 2);
+    assert_eq!(estimate_tokens_for_chars(4001), 1001);
+}
+
+#[test]
+fn format_run_stats_shows_all_fields_and_missing_steps()
+{
+    //! Passes when the summary lists every statistic and marks skipped steps as `n/a`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let stats = RunStats
+    {
+        input_bytes: 2048,
+        cleaned_lines: 40,
+        estimated_prompt_tokens: 900,
+        definition_rows: 3,
+        api_response_ms: Some(1500),
+        output_bytes: None,
+        model: "gpt-4o".to_string(),
+    };
+    let summary = format_run_stats(&stats);
+
+    assert!(summary.starts_with("[INF] - Run statistics:"));
+    assert!(summary.contains("Input file size:         2048 bytes"));
+    assert!(summary.contains("Cleaned notebook lines:  40"));
+    assert!(summary.contains("Estimated prompt tokens: 900"));
+    assert!(summary.contains("Definition rows:         3"));
+    assert!(summary.contains("Model:                   gpt-4o"));
+    assert!(summary.contains("API response time:       1500 ms"));
+    assert!(summary.contains("Output file size:        n/a"));
+    assert_eq!(summary.lines().count(), 8);
+}
+
+#[test]
+fn parse_local_command_flags()
+{
+    //! Passes when `--clean`/`--no-env`, `--list-profiles` and `--version` select their commands.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "test/example.py"])).unwrap();
+    assert_eq!(parsed.command, CliCommand::Generate);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "-fabric", "--clean", "test/example.py"])).unwrap();
+    assert_eq!(parsed.command, CliCommand::Clean);
+    assert_eq!(parsed.profile, PromptProfile::Fabric);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--no-env", "test/example.py"])).unwrap();
+    assert_eq!(parsed.command, CliCommand::Clean);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--list-profiles"])).unwrap();
+    assert_eq!(parsed.command, CliCommand::ListProfiles);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--version"])).unwrap();
+    assert_eq!(parsed.command, CliCommand::Version);
+}
+
+#[test]
+fn parse_list_env_vars_command()
+{
+    //! Passes when `--list-env-vars` selects its command without a notebook path.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--list-env-vars"])).unwrap();
+    assert_eq!(parsed.command, CliCommand::ListEnvVars);
+    assert!(parse_cli_args(&make_args(&["doxcer", "--list-env-vars", "--version"])).is_err());
+}
+
+#[test]
+fn parse_definitions_only_command()
+{
+    //! Passes when `--definitions-only` selects its command, requires a notebook path and conflicts with other commands.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--definitions-only", "-fabric", "test/example.py"])).unwrap();
+    assert_eq!(parsed.command, CliCommand::DefinitionsOnly);
+    assert_eq!(parsed.profile, PromptProfile::Fabric);
+    assert_eq!(parsed.file_path, "test/example.py");
+
+    assert!(parse_cli_args(&make_args(&["doxcer", "--definitions-only"])).is_err());
+    assert!(parse_cli_args(&make_args(&["doxcer", "--definitions-only", "--clean", "test/example.py"])).is_err());
+}
+
+#[test]
+fn run_definitions_only_short_circuits_without_ai_config()
+{
+    //! Passes when `--definitions-only` resolves the definitions section from a configuration without any
+    //! AI settings and fails cleanly when no definition DB is configured, without reaching the API path.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let cli_args = parse_cli_args(&make_args(&["doxcer", "--definitions-only", "examples/scripts/example.py"])).unwrap();
+
+    let disabled = EnvParameters { definition_database_enabled: false, ..EnvParameters::default() };
+    assert_eq!(run_definitions_only(&cli_args, &disabled), 0);
+
+    let unconfigured = EnvParameters
+    {
+        definition_database_enabled: true,
+        definition_environment: "dev".to_string(),
+        definition_azure_database_enabled: true,
+        ..EnvParameters::default()
+    };
+    assert_eq!(run_definitions_only(&cli_args, &unconfigured), 1);
+}
+
+#[test]
+fn parse_show_config_command()
+{
+    //! Passes when `--show-config` selects its command without a notebook path.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--show-config"])).unwrap();
+    assert_eq!(parsed.command, CliCommand::ShowConfig);
+    assert!(parse_cli_args(&make_args(&["doxcer", "--show-config", "--list-env-vars"])).is_err());
+}
+
+#[test]
+fn serialize_secret_masks_set_values_in_toml()
+{
+    //! Passes when a set secret name renders as `<SECRET>` and an unset one as an empty string.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    #[derive(serde::Serialize)]
+    struct Secrets
+    {
+        #[serde(serialize_with = "serialize_secret")]
+        set: String,
+        #[serde(serialize_with = "serialize_secret")]
+        unset: String,
+    }
+
+    let rendered = toml::to_string(&Secrets { set: "azure-secret-ai".to_string(), unset: " ".to_string() }).unwrap();
+    assert_eq!(rendered, "set = \"<SECRET>\"\nunset = \"\"\n");
+}
+
+#[test]
+fn format_env_var_table_masks_secrets()
+{
+    //! Passes when plain values are shown, secrets are masked and unset variables are marked.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let table = format_env_var_table(|name| match name
+    {
+        "AI_MODEL" => Some("gpt-4o".to_string()),
+        "AKV_SECRET_AI" => Some("azure-secret-ai-foundry".to_string()),
+        _ => None,
+    });
+
+    let value_of = |name: &str| -> String
+    {
+        table
+            .lines()
+            .find_map(|line| line.strip_prefix(name).filter(|rest| rest.starts_with(' ')))
+            .unwrap()
+            .trim()
+            .to_string()
+    };
+
+    assert!(table.starts_with("variable name"));
+    assert_eq!(table.lines().count(), ENV_VARS.len() + 1);
+    assert_eq!(value_of("AI_MODEL"), "gpt-4o");
+    assert_eq!(value_of("AKV_SECRET_AI"), "<SET>");
+    assert_eq!(value_of("AKV_SECRET_WIKI"), "<NOT SET>");
+    assert!(!table.contains("azure-secret-ai-foundry"));
+}
+
+#[test]
+fn env_vars_list_matches_env_lookups_in_source()
+{
+    //! Passes when `ENV_VARS` lists exactly the variables looked up in the source files.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let sources = [include_str!("main.rs"), include_str!("fetch_definitions.rs"), include_str!("fetch_secrets.rs")];
+    let mut looked_up: Vec<String> = Vec::new();
+
+    for source in sources
+    {
+        for call in ["env::var(\"", "env_flag_enabled(\""]
+        {
+            for (index, _) in source.match_indices(call)
+            {
+                let rest = &source[index + call.len()..];
+                let name = &rest[..rest.find('"').unwrap()];
+                if !name.is_empty() && name.chars().all(|c| c.is_ascii_uppercase() || c == '_')
+                {
+                    looked_up.push(name.to_string());
+                }
+            }
+        }
+    }
+    looked_up.sort();
+    looked_up.dedup();
+
+    let mut listed: Vec<String> = ENV_VARS.iter().map(|(name, _)| name.to_string()).collect();
+    listed.sort();
+
+    assert_eq!(listed, looked_up);
+}
+
+#[test]
+fn parse_local_command_rules()
+{
+    //! Passes when `--clean` still needs a path and different commands cannot be combined.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let err = parse_cli_args(&make_args(&["doxcer", "--clean"])).unwrap_err();
+    assert!(err.to_string().contains("Missing required notebook path"));
+
+    let err = parse_cli_args(&make_args(&["doxcer", "--clean", "--version", "test/example.py"])).unwrap_err();
+    assert!(err.to_string().contains("Conflicting commands"));
+
+    assert!(parse_cli_args(&make_args(&["doxcer", "--clean", "--no-env", "test/example.py"])).is_ok());
+}
+
+#[test]
+fn local_commands_return_before_env_loading()
+{
+    //! Passes when `--clean`, `--list-profiles` and `--version` finish in `run_local_command`, before
+    //! `run` reaches the env-loading commands, and the env-loading commands are left to `run`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let local = |args: &[&str]| run_local_command(&parse_cli_args(&make_args(args)).unwrap());
+
+    assert_eq!(local(&["doxcer", "--clean", "examples/scripts/example.py"]), Some(0));
+    assert_eq!(local(&["doxcer", "--clean", "examples/scripts/missing.py"]), Some(1));
+    assert_eq!(local(&["doxcer", "--list-profiles"]), Some(0));
+    assert_eq!(local(&["doxcer", "--version"]), Some(0));
+    assert_eq!(local(&["doxcer", "test/example.py"]), None);
+    assert_eq!(local(&["doxcer", "--show-config"]), None);
+}
+
+#[test]
+fn parse_sql_param_flags()
+{
+    //! Passes when repeated `--sql-param` values build a map and malformed values fail.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&[
+        "doxcer",
+        "--sql-param",
+        "schema_name=dbo",
+        "test/example.py",
+        "--sql-param",
+        "environment=prd=eu",
+    ]))
+    .unwrap();
+
+    assert_eq!(parsed.sql_params.len(), 2);
+    assert_eq!(parsed.sql_params.get("schema_name").map(String::as_str), Some("dbo"));
+    assert_eq!(parsed.sql_params.get("environment").map(String::as_str), Some("prd=eu"));
+
+    let err = parse_cli_args(&make_args(&["doxcer", "--sql-param", "=dbo", "test/example.py"])).unwrap_err();
+    assert!(err.to_string().contains("Expected KEY=VALUE"));
+
+    let err = parse_cli_args(&make_args(&["doxcer", "--sql-param", "schema_name", "test/example.py"])).unwrap_err();
+    assert!(err.to_string().contains("Expected KEY=VALUE"));
+}
+
+#[test]
+fn parse_compare_flag()
+{
+    //! Passes when `--compare` stores the older notebook path.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "test/new.py", "--compare", "test/old.py"])).unwrap();
+    assert_eq!(parsed.file_path, "test/new.py");
+    assert_eq!(parsed.compare.as_deref(), Some("test/old.py"));
+}
+
+#[test]
+fn parse_retry_failures_flag()
+{
+    //! Passes when `--retry-failures` is parsed alongside a batch directory.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--retry-failures", "examples/fabric"])).unwrap();
+    assert!(parsed.retry_failures);
+    assert_eq!(parsed.file_path, "examples/fabric");
+}
+
+#[test]
+fn parse_section_flag()
+{
+    //! Passes when `--section` stores the section name and rejects blank names.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--section", "Parameters", "test/example.py"])).unwrap();
+    assert_eq!(parsed.section.as_deref(), Some("Parameters"));
+
+    assert!(parse_cli_args(&make_args(&["doxcer", "--section", " ", "test/example.py"])).is_err());
+}
+
+#[test]
+fn parse_single_reference_flag()
+{
+    //! Passes when one `--reference` path is stored without being taken as the notebook path.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--reference", "test/config.py", "test/example.py"])).unwrap();
+    assert_eq!(parsed.file_path, "test/example.py");
+    assert_eq!(parsed.references, vec!["test/config.py".to_string()]);
+}
+
+#[test]
+fn parse_multiple_reference_flags_accumulate()
+{
+    //! Passes when repeated `--reference` paths accumulate and do not trip the multiple-path check.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&[
+        "doxcer",
+        "test/example.py",
+        "--reference",
+        "test/config.py",
+        "--reference",
+        "test/params.py",
+    ]))
+    .unwrap();
+
+    assert_eq!(parsed.file_path, "test/example.py");
+    assert_eq!(parsed.references, vec!["test/config.py".to_string(), "test/params.py".to_string()]);
+}
+
+#[test]
+fn parse_fail_on_warning_flag()
+{
+    //! Passes when `--fail-on-warning` is off by default and enabled by the flag.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert!(!parse_cli_args(&make_args(&["doxcer", "test/example.py"])).unwrap().fail_on_warning);
+    assert!(parse_cli_args(&make_args(&["doxcer", "--fail-on-warning", "test/example.py"])).unwrap().fail_on_warning);
+}
+
+#[test]
+fn outcome_exit_code_distinguishes_failure_classes()
+{
+    //! Passes when success, configuration, empty response, API and definitions failures map to 0 to 4.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    for success in [GenerateOutcome::Saved, GenerateOutcome::Printed, GenerateOutcome::Skipped]
+    {
+        assert_eq!(outcome_exit_code(success), 0);
+    }
+    for config_error in [GenerateOutcome::ConfigError, GenerateOutcome::EmptyInput, GenerateOutcome::WriteFailed]
+    {
+        assert_eq!(outcome_exit_code(config_error), 1);
+    }
+    assert_eq!(outcome_exit_code(GenerateOutcome::EmptyResponse), 2);
+    assert_eq!(outcome_exit_code(GenerateOutcome::BelowMinimum), 2);
+    assert_eq!(outcome_exit_code(GenerateOutcome::ApiError), 3);
+    assert_eq!(outcome_exit_code(GenerateOutcome::DefinitionsError), 4);
+    assert_eq!(outcome_exit_code(GenerateOutcome::SchemaMismatch), 5);
+    assert_ne!(outcome_exit_code(GenerateOutcome::Failed), 0);
+}
+
+#[test]
+fn apply_fail_on_warning_maps_warnings_to_exit_code_seven()
+{
+    //! Passes when warnings turn a successful run into exit code 7 only with the flag set.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(apply_fail_on_warning(0, true, 2), 7);
+    assert_eq!(apply_fail_on_warning(0, true, 0), 0);
+    assert_eq!(apply_fail_on_warning(0, false, 2), 0);
+    assert_eq!(apply_fail_on_warning(2, true, 2), 2);
+}
+
+#[test]
+fn parse_context_format_flag()
+{
+    //! Passes when `--context-format` defaults to `system`, parses known values and rejects others.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "test/example.py"])).unwrap();
+    assert_eq!(parsed.context_format, ContextFormat::System);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--context-format", "USER", "test/example.py"])).unwrap();
+    assert_eq!(parsed.context_format, ContextFormat::User);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--context-format", "both", "test/example.py"])).unwrap();
+    assert_eq!(parsed.context_format, ContextFormat::Both);
+
+    let err = parse_cli_args(&make_args(&["doxcer", "--context-format", "inline", "test/example.py"])).unwrap_err();
+    assert!(err.to_string().contains("Expected system, user or both"));
+}
+
+#[test]
+fn build_messages_places_context_per_format()
+{
+    //! Passes when the context goes to the system message, the user message, or both.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let roles_and_content = |messages: Vec<Message>| -> Vec<(String, String)>
+    {
+        messages.into_iter().map(|m| (m.role, m.content)).collect()
+    };
+
+    assert_eq!(
+        roles_and_content(build_messages("CTX", "PROMPT", ContextFormat::System, "system", "user")),
+        vec![("system".to_string(), "CTX".to_string()), ("user".to_string(), "PROMPT".to_string())]
+    );
+    assert_eq!(
+        roles_and_content(build_messages("CTX", "PROMPT", ContextFormat::User, "system", "user")),
+        vec![("user".to_string(), "CTX\n\nPROMPT".to_string())]
+    );
+    assert_eq!(
+        roles_and_content(build_messages("CTX", "PROMPT", ContextFormat::Both, "system", "user")),
+        vec![("system".to_string(), "CTX".to_string()), ("user".to_string(), "CTX\n\nPROMPT".to_string())]
+    );
+}
+
+#[test]
+fn build_messages_uses_configured_roles()
+{
+    //! Passes when `AI_SYSTEM_ROLE`/`AI_USER_ROLE` values are used as the message roles.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let roles = |messages: Vec<Message>| -> Vec<String> { messages.into_iter().map(|m| m.role).collect() };
+
+    assert_eq!(
+        roles(build_messages("CTX", "PROMPT", ContextFormat::System, "developer", "human")),
+        vec!["developer".to_string(), "human".to_string()]
+    );
+    assert_eq!(
+        roles(build_messages("CTX", "PROMPT", ContextFormat::User, "developer", "human")),
+        vec!["human".to_string()]
+    );
+}
+
+#[test]
+fn parse_extra_headers_reads_valid_entries()
+{
+    //! Passes when `key=value` entries separated by `;` become headers with trimmed names and values.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let headers = parse_extra_headers("x-team-id=data ; x-cost-center = 1234;");
+
+    assert_eq!(headers.len(), 2);
+    assert_eq!(headers.get("x-team-id").unwrap(), "data");
+    assert_eq!(headers.get("x-cost-center").unwrap(), "1234");
+}
+
+#[test]
+fn parse_extra_headers_skips_malformed_entries()
+{
+    //! Passes when entries without `=`, with invalid names or with invalid values are skipped.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let headers = parse_extra_headers("no-separator;bad name=1;=empty;x-bad-value=a\nb;x-ok=yes");
+
+    assert_eq!(headers.len(), 1);
+    assert_eq!(headers.get("x-ok").unwrap(), "yes");
+}
+
+#[test]
+fn parse_extra_headers_skips_reserved_headers()
+{
+    //! Passes when `api-key` and `Content-Type` cannot be set through `AI_EXTRA_HEADERS`, in any case.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let headers = parse_extra_headers("API-Key=stolen;content-type=text/plain;x-team-id=data");
+
+    assert_eq!(headers.len(), 1);
+    assert!(headers.get("api-key").is_none());
+    assert_eq!(headers.get("x-team-id").unwrap(), "data");
+}
+
+#[test]
+fn parse_extra_headers_returns_empty_map_for_blank_input()
+{
+    //! Passes when an empty or whitespace-only value yields no headers.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert!(parse_extra_headers("").is_empty());
+    assert!(parse_extra_headers("  ;  ").is_empty());
+}
+
+#[test]
+fn format_run_trace_renders_decisions_and_missing_steps()
+{
+    //! Passes when the trace lists each decision and marks unreached steps as `n/a`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let trace = RunTrace
+    {
+        profile: "fabric".to_string(),
+        template: "templates/fabric_prompt.md".to_string(),
+        definitions: "fetched 3 row(s)".to_string(),
+        prompt_chars: 4001,
+        model: "gpt-4o".to_string(),
+        response_chars: None,
+        context_version: None,
+        template_version: None,
+    };
+
+    assert_eq!(
+        format_run_trace(&trace),
+        concat!(
+            "[INF] - Run trace:\n",
+            "[INF] -   Profile:       fabric\n",
+            "[INF] -   Template:      templates/fabric_prompt.md\n",
+            "[INF] -   Definitions:   fetched 3 row(s)\n",
+            "[INF] -   Prompt size:   4001 chars (~1001 tokens)\n",
+            "[INF] -   Model:         gpt-4o\n",
+            "[INF] -   Response size: n/a"
+        )
+    );
+
+    let empty = format_run_trace(&RunTrace::default());
+    assert_eq!(empty.matches("n/a").count(), 6);
+}
+
+#[test]
+fn append_context_version_adds_marker_to_context()
+{
+    //! Passes when a version is appended after a `---` rule, a blank version is a no-op,
+    //! and the version shows in the run trace.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(
+        append_context_version("You are a documentation generator.", Some("v2.1")),
+        "You are a documentation generator.\n\n---\nPrompt version: v2.1"
+    );
+    assert_eq!(append_context_version("Context", Some("  ")), "Context");
+    assert_eq!(append_context_version("Context", None), "Context");
+
+    let trace = RunTrace { context_version: Some("v2.1".to_string()), ..RunTrace::default() };
+    assert!(format_run_trace(&trace).ends_with("\n[INF] -   Context ver.:  v2.1"));
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--context-version", "v2.1", "test/example.py"])).unwrap();
+    assert_eq!(parsed.context_version.as_deref(), Some("v2.1"));
+}
+
+#[test]
+fn parse_template_version_reads_front_line()
+{
+    //! Passes when a leading `<!-- template-version: X -->` line is parsed and stripped,
+    //! and absent or malformed version lines yield `None`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let template = "<!-- template-version: 2024.3 -->\n\n# Overview\n{filename}";
+    assert_eq!(parse_template_version(template).as_deref(), Some("2024.3"));
+    assert_eq!(parse_template_version("\n  <!--template-version:v2-->  \r\n# Doc").as_deref(), Some("v2"));
+    assert_eq!(strip_template_version(template), "# Overview\n{filename}");
+
+    assert_eq!(parse_template_version("# Overview\n<!-- template-version: 1 -->"), None);
+    assert_eq!(parse_template_version(""), None);
+    assert_eq!(parse_template_version("<!-- template-version: 1.0"), None);
+    assert_eq!(parse_template_version("<!-- template-version: -->"), None);
+    assert_eq!(parse_template_version("<!-- template-version: 1 2 -->"), None);
+    assert_eq!(parse_template_version("<!-- version: 1.0 -->"), None);
+    assert_eq!(strip_template_version("# Overview"), "# Overview");
+
+    assert_eq!(template_version_comment("2024.3"), "<!-- template-version: 2024.3 -->");
+    let trace = RunTrace { template_version: Some("2024.3".to_string()), ..RunTrace::default() };
+    assert!(format_run_trace(&trace).ends_with("\n[INF] -   Template ver.: 2024.3"));
+    assert!(parse_cli_args(&make_args(&["doxcer", "--template-version-comment", "test/example.py"])).unwrap().template_version_comment);
+}
+
+#[test]
+fn parse_api_version_flag()
+{
+    //! Passes when `--api-version` stores the version and requires a value.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--api-version", "2024-02-01", "test/example.py"])).unwrap();
+    assert_eq!(parsed.api_version.as_deref(), Some("2024-02-01"));
+
+    assert!(parse_cli_args(&make_args(&["doxcer", "test/example.py", "--api-version"])).is_err());
+}
+
+#[test]
+fn resolve_api_version_prefers_cli_then_env_override_then_config()
+{
+    //! Passes when the CLI flag wins over `AI_VERSION_OVERRIDE`, which wins over `AI_VERSION`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(resolve_api_version(Some("2025-01-01"), Some("2024-10-21"), "2024-05-01-preview"), "2025-01-01");
+    assert_eq!(resolve_api_version(None, Some("2024-10-21"), "2024-05-01-preview"), "2024-10-21");
+    assert_eq!(resolve_api_version(None, Some("  "), "2024-05-01-preview"), "2024-05-01-preview");
+    assert_eq!(resolve_api_version(None, None, "2024-05-01-preview"), "2024-05-01-preview");
+}
+
+#[test]
+fn parse_expect_json_schema_flag()
+{
+    //! Passes when `--expect-json-schema` stores the schema path.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--expect-json-schema", "schemas/doc.json", "test/example.py"]))
+        .unwrap();
+    assert_eq!(parsed.expect_json_schema.as_deref(), Some("schemas/doc.json"));
+}
+
+#[test]
+fn parse_model_flag()
+{
+    //! Passes when `--model` stores the model name and requires a value.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--model", "gpt-4o-mini", "test/example.py"])).unwrap();
+    assert_eq!(parsed.model.as_deref(), Some("gpt-4o-mini"));
+
+    assert!(parse_cli_args(&make_args(&["doxcer", "test/example.py", "--model"])).is_err());
+}
+
+#[test]
+fn resolve_model_prefers_cli_over_config()
+{
+    //! Passes when `--model` overrides `AI_MODEL` and a blank value falls back to it.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(resolve_model(Some("gpt-4o-mini"), "gpt-4o"), "gpt-4o-mini");
+    assert_eq!(resolve_model(Some("  "), "gpt-4o"), "gpt-4o");
+    assert_eq!(resolve_model(None, " gpt-4o "), "gpt-4o");
+}
+
+#[test]
+fn parse_lang_flag()
+{
+    //! Passes when `--lang` stores the language code.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--lang", "nl", "test/example.py"])).unwrap();
+    assert_eq!(parsed.lang.as_deref(), Some("nl"));
+}
+
+#[test]
+fn language_instruction_maps_known_codes_and_passes_through_unknown()
+{
+    //! Passes when known codes map to a language name, unknown values pass through and blanks are ignored.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(language_instruction("nl"), Some("Write the documentation in Dutch.".to_string()));
+    assert_eq!(language_instruction(" EN "), Some("Write the documentation in English.".to_string()));
+    assert_eq!(language_instruction("Frisian"), Some("Write the documentation in Frisian.".to_string()));
+    assert_eq!(language_instruction("  "), None);
+}
+
+#[test]
+fn parse_retry_flags()
+{
+    //! Passes when retry flags are stored and an attempt count of 0 or a negative delay is rejected.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&[
+        "doxcer",
+        "--retry-attempts",
+        "5",
+        "--retry-delay",
+        "0",
+        "--akv-retry-attempts",
+        "2",
+        "test/example.py",
+    ]))
+    .unwrap();
+    assert_eq!(parsed.retry_attempts, Some(5));
+    assert_eq!(parsed.retry_delay_ms, Some(0));
+    assert_eq!(parsed.akv_retry_attempts, Some(2));
+
+    let err = parse_cli_args(&make_args(&["doxcer", "--retry-attempts", "0", "test/example.py"])).unwrap_err();
+    assert!(err.to_string().contains("must be at least 1"));
+    assert!(parse_cli_args(&make_args(&["doxcer", "--retry-delay", "-5", "test/example.py"])).is_err());
+    assert!(parse_cli_args(&make_args(&["doxcer", "--akv-retry-attempts", "0", "test/example.py"])).is_err());
+}
+
+#[test]
+fn check_definition_environment_requires_allow_prod_for_prod()
+{
+    //! Passes when only `prod` without `--allow-prod` blocks the definitions query.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let cases = [
+        ("", false, true),
+        ("dev", false, true),
+        ("dev", true, true),
+        ("prod", false, false),
+        (" PROD ", false, false),
+        ("prod", true, true),
+    ];
+
+    for (environment, allow_prod, allowed) in cases
+    {
+        assert_eq!(check_definition_environment(environment, allow_prod).is_ok(), allowed, "{:?}", (environment, allow_prod));
+    }
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--allow-prod", "test/example.py"])).unwrap();
+    assert!(parsed.allow_prod);
+}
+
+#[test]
+fn resolve_rate_limit_delay_prefers_cli_then_env()
+{
+    //! Passes when `--rate-limit-delay` wins over `DOXCER_RATE_LIMIT_DELAY_MS`, which defaults to no delay.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--rate-limit-delay", "1500", "notebooks/"])).unwrap();
+    assert_eq!(parsed.rate_limit_delay_ms, Some(1500));
+
+    assert_eq!(resolve_rate_limit_delay(Some(1500), Some("200")), Ok(1500));
+    assert_eq!(resolve_rate_limit_delay(None, Some(" 200 ")), Ok(200));
+    assert_eq!(resolve_rate_limit_delay(None, None), Ok(0));
+    assert!(resolve_rate_limit_delay(None, Some("soon")).is_err());
+}
+
+#[test]
+fn check_input_size_rejects_only_sizes_above_limit()
+{
+    //! Passes when sizes up to the limit are accepted, larger sizes rejected and no limit accepts anything.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert!(check_input_size(1023, Some(1024)).is_ok());
+    assert!(check_input_size(1024, Some(1024)).is_ok());
+    let err = check_input_size(1025, Some(1024)).unwrap_err();
+    assert!(err.contains("1025 bytes") && err.contains("--max-input-bytes limit of 1024 bytes"));
+    assert!(check_input_size(u64::MAX, None).is_ok());
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--max-input-bytes", "1024", "test/example.py"])).unwrap();
+    assert_eq!(parsed.max_input_bytes, Some(1024));
+}
+
+#[test]
+fn resolve_akv_options_prefers_cli_then_env_then_defaults()
+{
+    //! Passes when Key Vault attempts and timeout come from the CLI, then `AKV_RETRY_ATTEMPTS` /
+    //! `AKV_TIMEOUT_SECS`, then the defaults, and invalid or zero env values are rejected.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let defaults = resolve_akv_options(None, None, None, Some("  ")).unwrap();
+    assert_eq!(defaults, KeyVaultOptions::default());
+    assert_eq!(defaults.retry_policy.attempts, 3);
+    assert_eq!(defaults.timeout, Duration::from_secs(30));
+
+    let from_env = resolve_akv_options(None, None, Some("5"), Some("10")).unwrap();
+    assert_eq!(from_env.retry_policy.attempts, 5);
+    assert_eq!(from_env.timeout, Duration::from_secs(10));
+
+    let from_cli = resolve_akv_options(Some(2), Some(45), Some("5"), Some("10")).unwrap();
+    assert_eq!(from_cli.retry_policy.attempts, 2);
+    assert_eq!(from_cli.timeout, Duration::from_secs(45));
+
+    assert!(resolve_akv_options(None, None, Some("0"), None).unwrap_err().contains("AKV_RETRY_ATTEMPTS"));
+    assert!(resolve_akv_options(None, None, None, Some("soon")).unwrap_err().contains("AKV_TIMEOUT_SECS"));
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--akv-timeout", "15", "test/example.py"])).unwrap();
+    assert_eq!(parsed.akv_timeout_secs, Some(15));
+    assert!(parse_cli_args(&make_args(&["doxcer", "--akv-timeout", "0", "test/example.py"])).is_err());
+}
+
+#[test]
+fn resolve_definition_timeout_prefers_cli_then_env()
+{
+    //! Passes when `--definition-timeout` wins over `DEFINITION_TIMEOUT_SECS` and no value keeps the driver default.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--definition-timeout", "30", "test/example.py"])).unwrap();
+    assert_eq!(parsed.definition_timeout_secs, Some(30));
+
+    assert_eq!(resolve_definition_timeout(Some(30), Some("5")), Ok(Some(30)));
+    assert_eq!(resolve_definition_timeout(None, Some(" 0 ")), Ok(Some(0)));
+    assert_eq!(resolve_definition_timeout(None, Some("")), Ok(None));
+    assert_eq!(resolve_definition_timeout(None, None), Ok(None));
+    assert!(resolve_definition_timeout(None, Some("never")).is_err());
+}
+
+#[test]
+fn build_definition_prefix_substitutes_placeholders()
+{
+    //! Passes when `{workspace}` and `{stem}` are substituted and a blank template falls back to the stem.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let vars = [("stem", "fct_daily_sales"), ("workspace", "finance")];
+
+    assert_eq!(build_definition_prefix("{workspace}/{stem}", &vars), "finance/fct_daily_sales");
+    assert_eq!(build_definition_prefix("", &vars), "fct_daily_sales");
+    assert_eq!(build_definition_prefix("  ", &vars), "fct_daily_sales");
+}
+
+#[test]
+fn build_definition_prefix_warns_for_missing_variable()
+{
+    //! Passes when a placeholder without a value is kept literally and a warning is logged.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let warnings_before = crate::logger::LOGGER.warning_count();
+
+    let prefix = build_definition_prefix("{workspace}/{stem}", &[("stem", "fct_daily_sales"), ("workspace", "")]);
+
+    assert_eq!(prefix, "{workspace}/fct_daily_sales");
+    assert!(crate::logger::LOGGER.warning_count() > warnings_before);
+}
+
+#[test]
+fn save_outcome_exit_code_fails_only_on_write_failure()
+{
+    //! Passes when a failed write exits non-zero while a successful write and `--stdout-only` exit `0`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let written: std::io::Result<()> = Ok(());
+    let read_only: std::io::Result<()> = Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "read-only"));
+
+    assert_eq!(save_outcome(Some(&written)), GenerateOutcome::Saved);
+    assert_eq!(save_outcome(Some(&read_only)), GenerateOutcome::WriteFailed);
+    assert_eq!(save_outcome(None), GenerateOutcome::Printed);
+
+    assert_eq!(outcome_exit_code(save_outcome(Some(&written))), 0);
+    assert_ne!(outcome_exit_code(save_outcome(Some(&read_only))), 0);
+    assert_eq!(outcome_exit_code(save_outcome(None)), 0);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--stdout-only", "test/example.py"])).unwrap();
+    assert!(parsed.stdout_only);
+}
+
+#[test]
+fn parse_frontmatter_field_flags()
+{
+    //! Passes when `--frontmatter-field` values are collected in order and malformed values are rejected.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&[
+        "doxcer",
+        "--frontmatter",
+        "--frontmatter-field",
+        "TEAM=DataEngineering",
+        "--frontmatter-field",
+        "VERSION=2",
+        "test/example.py",
+    ]))
+    .unwrap();
+
+    assert!(parsed.frontmatter);
+    assert_eq!(
+        parsed.frontmatter_fields,
+        vec![
+            ("TEAM".to_string(), "DataEngineering".to_string()),
+            ("VERSION".to_string(), "2".to_string()),
+        ]
+    );
+    assert!(parse_cli_args(&make_args(&["doxcer", "--frontmatter-field", "TEAM", "test/example.py"])).is_err());
+}
+
+#[test]
+fn parse_frontmatter_field_rejects_keys_that_break_yaml()
+{
+    //! Passes when keys with `:`, line breaks, `#`, spaces or a leading `-` are rejected and plain keys are kept.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    for field in ["a:b=1", "a\nb=1", "#team=1", "my team=1", "-team=1"]
+    {
+        let err = parse_cli_args(&make_args(&["doxcer", "--frontmatter-field", field, "test/example.py"])).unwrap_err();
+        assert!(err.to_string().contains("--frontmatter-field"), "accepted {:?}", field);
+    }
+
+    assert!(is_frontmatter_key("owner.team_name-2"));
+}
+
+#[test]
+fn prepend_frontmatter_adds_fields_and_quotes_values_with_spaces()
+{
+    //! Passes when reserved and custom fields are written in order and values with spaces are quoted.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let result = prepend_frontmatter(
+        "# Doc",
+        &[
+            ("title", "fct_sales".to_string()),
+            ("generated_at", "2026-10-16 12:00:00".to_string()),
+        ],
+        &[
+            ("TEAM".to_string(), "Data Engineering".to_string()),
+            ("VERSION".to_string(), "2".to_string()),
+        ],
+    );
+
+    assert_eq!(
+        result,
+        "---\ntitle: fct_sales\ngenerated_at: \"2026-10-16 12:00:00\"\nTEAM: \"Data Engineering\"\nVERSION: 2\n---\n\n# Doc"
+    );
+}
+
+#[test]
+fn prepend_frontmatter_reserved_override_warns_and_uses_value()
+{
+    //! Passes when a custom field named like a reserved field replaces its value and logs a warning.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let warnings_before = crate::logger::LOGGER.warning_count();
+
+    let result = prepend_frontmatter(
+        "body",
+        &[("title", "fct_sales".to_string()), ("source", "a.py".to_string())],
+        &[("title".to_string(), "Sales".to_string())],
+    );
+
+    assert_eq!(result, "---\ntitle: Sales\nsource: a.py\n---\n\nbody");
+    assert!(crate::logger::LOGGER.warning_count() > warnings_before);
+}
+
+#[test]
+fn git_head_content_runs_git_show_with_repo_relative_path()
+{
+    //! Passes when `git show HEAD:./<path>` is called with a path relative to the doxcer root (not the git
+    //! top level) and failures fall back to `None`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let repo_root = Path::new("/repo");
+    let output_path = Path::new("/repo/docs/newly-documented/Sales.md");
+    let calls = std::cell::RefCell::new(Vec::new());
+
+    let previous = git_head_content(repo_root, output_path, |dir: &Path, args: &[&str]|
+    {
+        calls.borrow_mut().push((dir.to_path_buf(), args.join(" ")));
+        Ok("# Sales\n".to_string())
+    });
+    assert_eq!(previous.as_deref(), Some("# Sales\n"));
+    assert_eq!(
+        calls.borrow().as_slice(),
+        &[(PathBuf::from("/repo"), "show HEAD:./docs/newly-documented/Sales.md".to_string())]
+    );
+
+    let missing = git_head_content(repo_root, output_path, |_: &Path, _: &[&str]|
+    {
+        Err("fatal: path 'docs/newly-documented/Sales.md' does not exist in 'HEAD'".to_string())
+    });
+    assert_eq!(missing, None);
+
+    let outside = git_head_content(repo_root, Path::new("/elsewhere/Sales.md"), |_: &Path, _: &[&str]|
+    {
+        panic!("git must not run for paths outside the repository")
+    });
+    assert_eq!(outside, None);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--diff-against-head", "test/example.py"])).unwrap();
+    assert!(parsed.diff_against_head);
+}
+
+#[test]
+fn split_cells_splits_on_cell_markers_and_drops_header()
+{
+    //! Passes when each `# CELL` starts a new cell, marker lines are removed and the header before the first marker is dropped.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = "# Fabric notebook source\n# METADATA x\n# CELL ****\nimport pandas as pd\n# CELL ****\nprint(1)\nprint(2)";
+    assert_eq!(
+        split_cells(source, &["#"]),
+        vec!["import pandas as pd".to_string(), "print(1)\nprint(2)".to_string()]
+    );
+    assert_eq!(
+        split_cells("-- CELL\nSELECT 1\n  -- CELL 2\nSELECT 2", &["--"]),
+        vec!["SELECT 1".to_string(), "SELECT 2".to_string()]
+    );
+}
+
+#[test]
+fn truncate_large_cells_cuts_only_oversized_cells()
+{
+    //! Passes when cells over the limit are cut at a line boundary with a truncation marker,
+    //! small cells and cell markers are kept and the marker follows the language comment prefix.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let big_literal = (0..50).map(|i| format!("    {},", i)).collect::<Vec<_>>().join("\n");
+    let source = format!("# CELL\nx = 1\n# CELL\ndata = [\n{}\n]\n# CELL\nprint(x)", big_literal);
+
+    let truncated = truncate_large_cells(&source, 40, &["#"]);
+    let lines: Vec<&str> = truncated.lines().collect();
+
+    assert_eq!(&lines[..3], &["# CELL", "x = 1", "# CELL"]);
+    assert_eq!(lines[3], "data = [");
+    assert!(truncated.contains("# ... [truncated] ...\n# CELL\nprint(x)"));
+    assert!(!truncated.contains("49,"));
+    assert_eq!(truncated.matches("[truncated]").count(), 1);
+
+    assert_eq!(truncate_large_cells(&source, 10_000, &["#"]), source);
+    assert_eq!(truncate_large_cells("-- CELL\nSELECT 1234567890", 5, &["--"]), "-- CELL\nSELEC\n-- ... [truncated] ...");
+}
+
+#[test]
+fn filter_cells_by_regex_keeps_matching_cells()
+{
+    //! Passes when only cells matching the pattern are kept with their markers and the header before the first marker.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = "# Fabric notebook source\n# CELL\ndf = spark.table('sales_orders')\n# CELL\nprint(1)\n# CELL\nsales_orders.show()";
+
+    assert_eq!(
+        filter_cells_by_regex(source, r"sales_orders", &["#"]).unwrap(),
+        "# Fabric notebook source\n# CELL\ndf = spark.table('sales_orders')\n# CELL\nsales_orders.show()"
+    );
+    assert_eq!(filter_cells_by_regex(source, r"(?i)^PRINT\(", &["#"]).unwrap(), "# Fabric notebook source\n# CELL\nprint(1)");
+    assert_eq!(filter_cells_by_regex("-- CELL\nSELECT 1", "SELECT", &["--"]).unwrap(), "-- CELL\nSELECT 1");
+    assert_eq!(filter_cells_by_regex("SELECT 1\nFROM t", "FROM", &["--"]).unwrap(), "SELECT 1\nFROM t");
+}
+
+#[test]
+fn filter_cells_by_regex_drops_non_matching_cells_and_rejects_invalid_patterns()
+{
+    //! Passes when no cell matches (only the header is left, a notebook without markers becomes empty)
+    //! and an invalid pattern is an `[ERR]` naming the pattern; `--filter-cells` is parsed.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = "# Fabric notebook source\n# CELL\nx = 1\n# CELL\nprint(x)";
+    assert_eq!(filter_cells_by_regex(source, "customers", &["#"]).unwrap(), "# Fabric notebook source");
+    assert_eq!(filter_cells_by_regex("SELECT 1", "customers", &["--"]).unwrap(), "");
+
+    let err = filter_cells_by_regex(source, "sales_(orders", &["#"]).unwrap_err();
+    assert!(err.starts_with("[ERR] - Invalid --filter-cells pattern 'sales_(orders'"));
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--filter-cells", "sales_orders", "test/example.py"])).unwrap();
+    assert_eq!(parsed.filter_cells.as_deref(), Some("sales_orders"));
+    assert_eq!(parse_cli_args(&make_args(&["doxcer", "test/example.py"])).unwrap().filter_cells, None);
+}
+
+#[test]
+fn max_cell_chars_accepts_only_positive_numbers()
+{
+    //! Passes when `MAX_CELL_CHARS` resolves to a positive limit and blank or invalid values disable the cap.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(max_cell_chars(Some(" 2000 ")), Some(2000));
+    assert_eq!(max_cell_chars(None), None);
+    assert_eq!(max_cell_chars(Some("  ")), None);
+    assert_eq!(max_cell_chars(Some("0")), None);
+    assert_eq!(max_cell_chars(Some("lots")), None);
+}
+
+#[test]
+fn split_cells_returns_whole_source_without_cell_markers()
+{
+    //! Passes when a notebook without cell markers is a single cell, including lines that only resemble a marker.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = "import pandas as pd\n#CELL is not a marker\nprint(1)";
+    assert_eq!(split_cells(source, &["#"]), vec![source.to_string()]);
+    assert_eq!(split_cells("", &["#"]), vec![String::new()]);
+}
+
+#[test]
+fn cell_output_stem_nests_zero_padded_cell_files_under_the_stem()
+{
+    //! Passes when cell stems are `<stem>/cell-NN` with two-digit numbers and wider numbers kept whole.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(cell_output_stem("Sales", 1), "Sales/cell-01");
+    assert_eq!(cell_output_stem("Sales", 12), "Sales/cell-12");
+    assert_eq!(cell_output_stem("Sales", 120), "Sales/cell-120");
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--per-cell", "test/example.py"])).unwrap();
+    assert!(parsed.per_cell);
+}
+
+#[test]
+fn parse_absolute_doxcer_path_trims_surrounding_whitespace()
+{
+    //! Passes when whitespace around the key, `=` and value is ignored and comment lines are skipped.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let content = "# System paths\n\n   ABSOLUTE_DOXCER_PATH =   /opt/doxcer   \n";
+    assert_eq!(parse_absolute_doxcer_path(content), Some(PathBuf::from("/opt/doxcer")));
+    assert_eq!(parse_absolute_doxcer_path("ABSOLUTE_DOXCER_PATH=   \nOTHER=/x"), None);
+}
+
+#[test]
+fn parse_absolute_doxcer_path_strips_inline_comments()
+{
+    //! Passes when everything after an unquoted `#` is dropped and a quoted `#` is kept.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(
+        parse_absolute_doxcer_path("ABSOLUTE_DOXCER_PATH=/opt/doxcer  # repo checkout"),
+        Some(PathBuf::from("/opt/doxcer"))
+    );
+    assert_eq!(
+        parse_absolute_doxcer_path("ABSOLUTE_DOXCER_PATH=\"/opt/team #2/doxcer\" # quoted"),
+        Some(PathBuf::from("/opt/team #2/doxcer"))
+    );
+    assert_eq!(parse_absolute_doxcer_path("ABSOLUTE_DOXCER_PATH=# not set yet"), None);
+    assert_eq!(strip_inline_comment("'a#b' # c"), "'a#b' ");
+}
+
+#[test]
+fn parse_absolute_doxcer_path_handles_windows_line_endings()
+{
+    //! Passes when `\r\n` line endings leave no carriage return in the value.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let content = "# System paths\r\nABSOLUTE_DOXCER_PATH=C:\\repos\\doxcer\r\nOTHER=1\r\n";
+    assert_eq!(parse_absolute_doxcer_path(content), Some(PathBuf::from("C:\\repos\\doxcer")));
+}
+
+#[test]
+fn parse_absolute_doxcer_path_handles_combined_edge_cases()
+{
+    //! Passes when whitespace, quotes, an inline comment and `\r\n` line endings appear together in a file.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let dir = TestDir::new("system-env");
+    let path = dir.join("system.env");
+    std::fs::write(
+        &path,
+        "# ABSOLUTE_DOXCER_PATH=/commented/out\r\n\r\n  ABSOLUTE_DOXCER_PATH = 'D:\\work\\doxcer'   # laptop \r\n",
+    )
+    .unwrap();
+
+    assert_eq!(parse_system_env_absolute_path(&path), Some(PathBuf::from("D:\\work\\doxcer")));
+    assert_eq!(parse_system_env_absolute_path(&dir.join("missing.env")), None);
+}
+
+#[test]
+fn changelog_entry_uses_date_name_and_profile()
+{
+    //! Passes when the changelog line keeps only the date of the timestamp and names the doc and profile,
+    //! and `--changelog`/`--force` are parsed.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(
+        changelog_entry("2026-10-16 14:05:00", "Sales", "python"),
+        "- 2026-10-16 regenerated Sales (python)"
+    );
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--changelog", "docs/CHANGELOG.md", "--force", "test/example.py"])).unwrap();
+    assert_eq!(parsed.changelog.as_deref(), Some("docs/CHANGELOG.md"));
+    assert!(parsed.force);
+}
+
+#[test]
+fn parse_definition_format_template_flag_unescapes_newlines()
+{
+    //! Passes when `--definition-format-template` is stored with a literal `\n` turned into a line break.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&[
+        "doxcer",
+        "--definition-format-template",
+        "Column Dictionary:\\n{definitions}",
+        "test/example.py",
+    ]))
+    .unwrap();
+    assert_eq!(parsed.definition_format_template.as_deref(), Some("Column Dictionary:\n{definitions}"));
+}
+
+#[test]
+fn chat_request_serializes_seed_only_when_set()
+{
+    //! Passes when `seed` is left out of the request JSON when unset and sent as a number when set.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let without = serde_json::to_value(ChatRequest { model: "gpt-4o".to_string(), messages: Vec::new(), seed: None }).unwrap();
+    assert!(without.get("seed").is_none());
+
+    let with = serde_json::to_value(ChatRequest { model: "gpt-4o".to_string(), messages: Vec::new(), seed: Some(42) }).unwrap();
+    assert_eq!(with["seed"], serde_json::json!(42));
+}
+
+#[test]
+fn parse_seed_flag_and_resolve_precedence()
+{
+    //! Passes when `--seed` is parsed, takes precedence over `AI_SEED`, and an invalid `AI_SEED` is ignored.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--seed", "42", "test/example.py"])).unwrap();
+    assert_eq!(parsed.seed, Some(42));
+    assert!(parse_cli_args(&make_args(&["doxcer", "--seed", "abc", "test/example.py"])).is_err());
+
+    assert_eq!(resolve_seed(Some(42), Some("7")), Some(42));
+    assert_eq!(resolve_seed(None, Some(" 7 ")), Some(7));
+    assert_eq!(resolve_seed(None, Some("")), None);
+    assert_eq!(resolve_seed(None, Some("seven")), None);
+    assert_eq!(resolve_seed(None, None), None);
+}
+
+#[test]
+fn parse_odbc_encrypt_flag_and_resolve_precedence()
+{
+    //! Passes when `--odbc-encrypt` is validated, takes precedence over `ODBC_ENCRYPT`, and `DEFINITION_ENCRYPT`
+    //! is the fallback.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::fetch_definitions::OdbcEncrypt;
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--odbc-encrypt", "strict", "test/example.py"])).unwrap();
+    assert_eq!(parsed.odbc_encrypt, Some(OdbcEncrypt::Strict));
+    let err = parse_cli_args(&make_args(&["doxcer", "--odbc-encrypt", "maybe", "test/example.py"])).unwrap_err();
+    assert!(err.to_string().contains("Expected yes, no or strict"));
+
+    assert_eq!(resolve_odbc_encrypt(Some(OdbcEncrypt::No), Some("strict"), true), Ok(OdbcEncrypt::No));
+    assert_eq!(resolve_odbc_encrypt(None, Some("strict"), true), Ok(OdbcEncrypt::Strict));
+    assert_eq!(resolve_odbc_encrypt(None, Some(""), true), Ok(OdbcEncrypt::Yes));
+    assert_eq!(resolve_odbc_encrypt(None, None, false), Ok(OdbcEncrypt::No));
+    assert!(resolve_odbc_encrypt(None, Some("optional"), true).is_err());
+}
+
+#[test]
+fn parse_echo_limit_flag()
+{
+    //! Passes when `--echo-limit` is parsed, defaults to no echo and a non-numeric limit is rejected.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "test/example.py"])).unwrap();
+    assert_eq!(parsed.echo_limit, None);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--echo-limit", "500", "test/example.py"])).unwrap();
+    assert_eq!(parsed.echo_limit, Some(500));
+
+    assert!(parse_cli_args(&make_args(&["doxcer", "--echo-limit", "all", "test/example.py"])).is_err());
+}
+
+#[test]
+fn parse_odbc_trust_cert_flag_and_resolve_precedence()
+{
+    //! Passes when `--odbc-trust-cert` takes a boolean, overrides `ODBC_TRUST_SERVER_CERTIFICATE`,
+    //! and `DEFINITION_TRUST_CERT` is the fallback.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--odbc-trust-cert", "false", "test/example.py"])).unwrap();
+    assert_eq!(parsed.odbc_trust_cert, Some(false));
+    assert!(parse_cli_args(&make_args(&["doxcer", "--odbc-trust-cert", "maybe", "test/example.py"])).is_err());
+
+    assert_eq!(resolve_odbc_trust_cert(Some(false), Some("true"), true), Ok(false));
+    assert_eq!(resolve_odbc_trust_cert(None, Some("no"), true), Ok(false));
+    assert_eq!(resolve_odbc_trust_cert(None, Some(" "), true), Ok(true));
+    assert_eq!(resolve_odbc_trust_cert(None, None, false), Ok(false));
+    assert!(resolve_odbc_trust_cert(None, Some("sometimes"), true).is_err());
+}
+
+#[test]
+fn load_remote_context_caches_and_falls_back_to_cache()
+{
+    //! Passes when a fetched context is returned and cached, a failed fetch uses the cached copy,
+    //! and a failed fetch without a cache returns `None`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let dir = TestDir::new("remote-context");
+    let cache_path = dir.join(".context_cache.md");
+    let url = "https://guidelines.example.com/context.md";
+
+    assert_eq!(load_remote_context(url, &cache_path, |_| Err("timed out".to_string())), None);
+
+    let fetched = load_remote_context(url, &cache_path, |fetched_url|
+    {
+        assert_eq!(fetched_url, url);
+        Ok("# Guidelines v2".to_string())
+    });
+    assert_eq!(fetched.as_deref(), Some("# Guidelines v2"));
+    assert_eq!(std::fs::read_to_string(&cache_path).unwrap(), "# Guidelines v2");
+
+    let fallback = load_remote_context(url, &cache_path, |_| Err("status 503 Service Unavailable".to_string()));
+    assert_eq!(fallback.as_deref(), Some("# Guidelines v2"));
+}
+
+#[test]
+fn append_source_section_fences_source_with_language()
+{
+    //! Passes when the source is appended under `## Source` in a fence tagged with the language,
+    //! and `--include-source` / `--include-raw-notebook` are parsed.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(
+        append_source_section("# Sales\n\nLoads orders.\n", "df = load()\n", "python"),
+        "# Sales\n\nLoads orders.\n\n## Source\n\n```python\ndf = load()\n```\n"
+    );
+    assert!(append_source_section("# Orders", "SELECT 1", "sql").contains("\n```sql\nSELECT 1\n```\n"));
+
+    for flag in ["--include-source", "--include-raw-notebook"]
+    {
+        assert!(parse_cli_args(&make_args(&["doxcer", flag, "test/example.py"])).unwrap().include_source);
+    }
+}
+
+#[test]
+fn append_source_section_lengthens_fence_for_backticks_in_source()
+{
+    //! Passes when a source containing a triple-backtick run is wrapped in a four-backtick fence.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = "doc = \"\"\"\n```sql\nSELECT 1\n```\n\"\"\"";
+    let content = append_source_section("# Sales", source, "python");
+
+    assert!(content.contains("\n````python\ndoc = "));
+    assert!(content.ends_with("```\n\"\"\"\n````\n"));
+}
+
+#[test]
+fn parse_output_encoding_flag()
+{
+    //! Passes when `--output-encoding` defaults to UTF-8, accepts `utf16le` and rejects unknown encodings.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::io_utils::OutputEncoding;
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "test/example.py"])).unwrap();
+    assert_eq!(parsed.output_encoding, OutputEncoding::Utf8);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--output-encoding", "utf16le", "test/example.py"])).unwrap();
+    assert_eq!(parsed.output_encoding, OutputEncoding::Utf16Le);
+
+    assert!(parse_cli_args(&make_args(&["doxcer", "--output-encoding", "ascii", "test/example.py"])).is_err());
+}
+
+#[test]
+fn find_repo_root_path_contains_project_markers()
+{
+    //! Passes when resolved repo root contains expected marker files.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let root = find_repo_root_path();
+    assert!(has_repo_markers(&root));
+}
+
+#[test]
+fn find_repo_root_in_ancestors_stops_after_max_depth()
+{
+    //! Passes when the repo root is found within `max_depth` directories and `None` is returned once the limit is reached.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let root = TestDir::new("ancestor-depth");
+    let start = root.join("a").join("b").join("c");
+    std::fs::create_dir_all(&start).unwrap();
+    std::fs::write(root.join("doxcer.toml"), "").unwrap();
+
+    assert_eq!(find_repo_root_in_ancestors(&start, 4), Some(root.to_path_buf()));
+    assert_eq!(find_repo_root_in_ancestors(&start, 3), None);
+    assert_eq!(find_repo_root_in_ancestors(&start, 0), None);
+    assert_eq!(find_repo_root_in_ancestors(&root, MAX_ANCESTOR_DEPTH), Some(root.to_path_buf()));
+}
+
+#[test]
+fn has_repo_markers_accepts_doxcer_toml()
+{
+    //! Passes when a directory with only `doxcer.toml` counts as a repo root and an empty one does not.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let dir = TestDir::new("markers");
+    assert!(!has_repo_markers(&dir));
+
+    std::fs::write(dir.join("doxcer.toml"), "").unwrap();
+    assert!(has_repo_markers(&dir));
+}
+
+#[test]
+fn parse_env_file_flags()
+{
+    //! Passes when `--env-file` is repeatable in order and `--strict-env` is parsed.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&[
+        "doxcer",
+        "--env-file",
+        "a.env",
+        "test/example.py",
+        "--env-file",
+        "b.env",
+        "--strict-env",
+    ]))
+    .unwrap();
+
+    assert_eq!(parsed.env_files, vec!["a.env".to_string(), "b.env".to_string()]);
+    assert!(parsed.strict_env);
+
+    let err = parse_cli_args(&make_args(&["doxcer", "test/example.py", "--env-file"])).unwrap_err();
+    assert!(err.to_string().contains("Missing value for '--env-file'"));
+}
+
+#[test]
+fn load_extra_env_files_applies_later_files_last_and_handles_missing_paths()
+{
+    //! Passes when env files are loaded last-to-first (so later files win with dotenvy), missing paths
+    //! respect `strict` and loader errors name the file.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let dir = TestDir::new("env-file");
+    let first = dir.join("first.env");
+    let second = dir.join("second.env");
+    fs::write(&first, "DOXCER_TEST_EXTRA_ENV=first\n").unwrap();
+    fs::write(&second, "DOXCER_TEST_EXTRA_ENV=second\n").unwrap();
+    let missing = dir.join("missing.env").display().to_string();
+
+    let files = vec![first.display().to_string(), missing.clone(), second.display().to_string()];
+    let mut loaded: Vec<PathBuf> = Vec::new();
+    let result = load_extra_env_files(&files, false, |path|
+    {
+        loaded.push(path.to_path_buf());
+        Ok(())
+    });
+    assert_eq!(result, Ok(2));
+    assert_eq!(loaded, vec![second.clone(), first.clone()]);
+
+    let err = load_extra_env_files(&[missing], true, |_| Ok(())).unwrap_err();
+    assert!(err.contains("does not exist"));
+
+    let err = load_extra_env_files(&[first.display().to_string()], false, |_| Err("bad line 1".to_string())).unwrap_err();
+    assert!(err.contains("first.env") && err.contains("bad line 1"));
+}
+
+#[test]
+fn list_env_vars_shows_env_file_values_over_config()
+{
+    //! Passes when an `--env-file` value is the one listed, because it is loaded before the `config/` files
+    //! and a dotenv load never overwrites a variable that is already set.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let dir = TestDir::new("list-env-vars");
+    let override_file = dir.join("override.env");
+    fs::write(&override_file, "AI_MODEL=override-model\n").unwrap();
+
+    let env = std::cell::RefCell::new(HashMap::new());
+    let table = list_env_vars(
+        &[override_file.display().to_string()],
+        false,
+        |_|
+        {
+            env.borrow_mut().entry("AI_MODEL".to_string()).or_insert_with(|| "override-model".to_string());
+            Ok(())
+        },
+        ||
+        {
+            env.borrow_mut().entry("AI_MODEL".to_string()).or_insert_with(|| "config-model".to_string());
+        },
+        |name| env.borrow().get(name).cloned(),
+    )
+    .unwrap();
+
+    let model_line = table.lines().find(|line| line.starts_with("AI_MODEL ")).unwrap();
+    assert!(model_line.ends_with("override-model"));
+    assert!(!table.contains("config-model"));
+}
+
+#[test]
+fn find_env_paths_returns_expected_files_in_order()
+{
+    //! Passes when env paths are returned in the expected order.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let env_paths = find_env_paths(None);
+    let names: Vec<String> = env_paths
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+
+    assert_eq!(
+        names,
+        vec![
+            "system.env".to_string(),
+            "definitions.env".to_string(),
+            "azure_key_vault.env".to_string(),
+            "ai_model.env".to_string()
+        ]
+    );
+}
+
+#[test]
+fn find_env_paths_uses_config_dir_override()
+{
+    //! Passes when `DOXCER_CONFIG_DIR` replaces `config/` for every default env file.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let config_dir = std::env::temp_dir().join("doxcer-config");
+
+    let env_paths = find_env_paths(Some(&config_dir));
+
+    assert_eq!(env_paths.len(), 4);
+    assert!(env_paths.iter().all(|path| path.parent() == Some(config_dir.as_path())));
+    assert_eq!(env_paths[0], config_dir.join("system.env"));
+}
+
+#[test]
+fn config_dir_override_requires_an_absolute_path()
+{
+    //! Passes when an absolute `DOXCER_CONFIG_DIR` is used, a blank value is ignored and a relative value is rejected.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let config_dir = std::env::temp_dir().join("doxcer-config");
+    let raw = config_dir.display().to_string();
+
+    assert_eq!(config_dir_override(Some(&raw)).unwrap(), Some(config_dir));
+    assert_eq!(config_dir_override(None).unwrap(), None);
+    assert_eq!(config_dir_override(Some("  ")).unwrap(), None);
+
+    let err = config_dir_override(Some("ci/config")).unwrap_err();
+    assert!(err.starts_with("[ERR] - DOXCER_CONFIG_DIR 'ci/config' must be an absolute path"));
+}
+
+#[test]
+fn parse_env_file_list_resolves_relative_and_absolute_paths()
+{
+    //! Passes when `DOXCER_ENV_FILES` entries keep their order, skip blanks and resolve against the repo root.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let repo_root = std::env::temp_dir().join("doxcer-repo");
+    let absolute = std::env::temp_dir().join("shared").join("ai_model.env");
+    let raw = format!(" config/system.env , ,deploy/prod.env,{}", absolute.display());
+
+    assert_eq!(
+        parse_env_file_list(&raw, &repo_root),
+        vec![
+            repo_root.join("config/system.env"),
+            repo_root.join("deploy/prod.env"),
+            absolute,
+        ]
+    );
+    assert!(parse_env_file_list(" , ", &repo_root).is_empty());
+}
+
+#[test]
+fn load_prompt_template_finds_existing_profile_prompt()
+{
+    //! Passes when a profile with a template resolves to that template.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let path = load_prompt_template(&PromptProfile::Fabric, None, false).unwrap().0;
+    assert_eq!(path.file_name().unwrap().to_string_lossy(), "fabric_prompt.md");
+    assert!(path.exists());
+}
+
+#[test]
+fn load_prompt_template_reads_profile_without_base()
+{
+    //! Passes when a profile without a base profile loads its own template unchanged.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let (path, content) = load_prompt_template(&PromptProfile::Fabric, None, false).unwrap();
+    assert_eq!(path.file_name().unwrap().to_string_lossy(), "fabric_prompt.md");
+    assert_eq!(content, load_template(&path).unwrap());
+}
+
+#[test]
+fn load_prompt_template_extends_fabric_for_lakehouse()
+{
+    //! Passes when the Lakehouse profile sends the Fabric template, a `---` line and its own template.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(prompt_profile_spec(PromptProfile::Lakehouse).base_profile, Some(PromptProfile::Fabric));
+
+    let (path, content) = load_prompt_template(&PromptProfile::Lakehouse, None, false).unwrap();
+    assert_eq!(path.file_name().unwrap().to_string_lossy(), "lakehouse_prompt.md");
+
+    let (fabric_path, fabric_content) = load_prompt_template(&PromptProfile::Fabric, None, false).unwrap();
+    assert_eq!(fabric_path.file_name().unwrap().to_string_lossy(), "fabric_prompt.md");
+    assert_eq!(content, combine_inherited_template(&fabric_content, &load_template(&path).unwrap()));
+    assert!(content.contains("\n---\n<!-- LAKEHOUSE TEMPLATE"));
+}
+
+#[test]
+fn load_prompt_template_uses_base_alone_without_child_template()
+{
+    //! Passes when a child profile without its own template falls back to the base template, and a child
+    //! template in the same directory is appended to the base.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let template_dir = TestDir::new("inherited-templates");
+    fs::write(template_dir.join("fabric_prompt.md"), "Fabric base.").unwrap();
+    let template_dirs = vec![template_dir.to_path_buf()];
+
+    let (path, content) = load_prompt_template_from(&PromptProfile::Lakehouse, &template_dirs, false).unwrap();
+    assert_eq!(path, template_dir.join("fabric_prompt.md"));
+    assert_eq!(content, "Fabric base.");
+
+    fs::write(template_dir.join("lakehouse_prompt.md"), "Lakehouse additions.").unwrap();
+    let (path, content) = load_prompt_template_from(&PromptProfile::Lakehouse, &template_dirs, false).unwrap();
+    assert_eq!(path, template_dir.join("lakehouse_prompt.md"));
+    assert_eq!(content, "Fabric base.\n---\nLakehouse additions.");
+}
+
+#[test]
+fn combine_inherited_template_appends_child_after_separator()
+{
+    //! Passes when the child template follows the base template after a `---` line.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(
+        combine_inherited_template("# Fabric\n\nBase rules.\n", "Lakehouse rules."),
+        "# Fabric\n\nBase rules.\n---\nLakehouse rules."
+    );
+}
+
+#[test]
+fn prompt_profile_base_chains_terminate()
+{
+    //! Passes when following `base_profile` from any profile ends within the number of profiles (no cycles).
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    for spec in PROMPT_PROFILE_SPECS
+    {
+        let mut current = spec.base_profile;
+        let mut steps = 0;
+        while let Some(base) = current
+        {
+            steps += 1;
+            assert!(steps <= PROMPT_PROFILE_SPECS.len(), "base_profile cycle starting at '{}'", spec.name);
+            current = prompt_profile_spec(base).base_profile;
+        }
+    }
+}
+
+#[test]
+fn parse_no_template_fallback_flag()
+{
+    //! Passes when `--no-template-fallback` is parsed alongside a selector and path.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--no-template-fallback", "-fabric", "test/example.py"]))
+        .unwrap();
+    assert!(parsed.no_template_fallback);
+    assert_eq!(parsed.profile, PromptProfile::Fabric);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "test/example.py"])).unwrap();
+    assert!(!parsed.no_template_fallback);
+}
+
+#[test]
+fn resolve_prompt_path_falls_back_or_fails_for_missing_template()
+{
+    //! Passes when a missing template falls back only while fallback is allowed.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let template_dirs = vec![find_repo_root_path().join("templates")];
+
+    let fallback = resolve_prompt_path(&template_dirs, "does_not_exist", true).unwrap();
+    assert_eq!(fallback.file_name().unwrap().to_string_lossy(), "default_prompt.md");
+
+    match resolve_prompt_path(&template_dirs, "does_not_exist", false)
+    {
+        Err(DoxcerError::TemplateNotFound(path)) =>
+        {
+            assert_eq!(path.file_name().unwrap().to_string_lossy(), "does_not_exist_prompt.md");
+        }
+        other => panic!("expected TemplateNotFound, got {:?}", other),
+    }
+}
+
+#[test]
+fn load_prompt_template_prefers_override_template_dir()
+{
+    //! Passes when a template in the override directory wins over the repo template,
+    //! and profiles missing from the override still resolve to the repo template.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let override_dir = TestDir::new("template-dir");
+    fs::write(override_dir.join("fabric_prompt.md"), "curated fabric prompt").unwrap();
+
+    let path = load_prompt_template(&PromptProfile::Fabric, Some(&override_dir), false).unwrap().0;
+    assert_eq!(path, override_dir.join("fabric_prompt.md"));
+
+    let path = load_prompt_template(&PromptProfile::Synapse, Some(&override_dir), false).unwrap().0;
+    assert_eq!(path, find_repo_root_path().join("templates").join("synapse_prompt.md"));
+}
+
+#[test]
+fn parse_profile_template_dir_flag()
+{
+    //! Passes when `--profile-template-dir` stores the provided directory.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--profile-template-dir", "//share/templates", "test/example.py"]))
+        .unwrap();
+    assert_eq!(parsed.profile_template_dir.as_deref(), Some("//share/templates"));
+}
+
+#[test]
+fn load_prompt_template_finds_azureml_prompt()
+{
+    //! Passes when the AzureML profile resolves to its own template without fallback.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let path = load_prompt_template(&PromptProfile::AzureML, None, false).unwrap().0;
+    assert_eq!(path.file_name().unwrap().to_string_lossy(), "azureml_prompt.md");
+}
+
+#[test]
+fn profile_from_extension_maps_known_extensions()
+{
+    //! Passes when Power BI and Databricks extensions map to their profiles, with or without a dot and in any case.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(profile_from_extension("pbip"), Some(PromptProfile::PowerBi));
+    assert_eq!(profile_from_extension(".PBIR"), Some(PromptProfile::PowerBi));
+    assert_eq!(profile_from_extension("tmdl"), Some(PromptProfile::PowerBi));
+    assert_eq!(profile_from_extension("dbc"), Some(PromptProfile::Databricks));
+}
+
+#[test]
+fn profile_from_extension_ignores_unmapped_extensions()
+{
+    //! Passes when notebook and unknown extensions have no mapped profile.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(profile_from_extension("py"), None);
+    assert_eq!(profile_from_extension("sql"), None);
+    assert_eq!(profile_from_extension(""), None);
+}
+
+#[test]
+fn parse_extension_profiles_reads_mappings_and_skips_invalid_entries()
+{
+    //! Passes when `ext=profile` entries map by profile name or selector and invalid entries are skipped.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let mappings = parse_extension_profiles(".DBC=databricks; ipynb=-fabric, bad, x=unknown");
+
+    assert_eq!(
+        mappings,
+        vec![
+            ("dbc".to_string(), PromptProfile::Databricks),
+            ("ipynb".to_string(), PromptProfile::Fabric),
+        ]
+    );
+}
+
+#[test]
+fn resolve_active_profile_prefers_selector_over_extension()
+{
+    //! Passes when a mapped extension selects its profile unless an explicit selector is given.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let mapped = parse_cli_args(&make_args(&["doxcer", "reports/sales.pbip"])).unwrap();
+    assert_eq!(resolve_active_profile(&mapped, "{}"), PromptProfile::PowerBi);
+
+    let explicit = parse_cli_args(&make_args(&["doxcer", "-fabric", "reports/sales.pbip"])).unwrap();
+    assert_eq!(resolve_active_profile(&explicit, "{}"), PromptProfile::Fabric);
+
+    let default = parse_cli_args(&make_args(&["doxcer", "-default", "reports/sales.pbip"])).unwrap();
+    assert_eq!(resolve_active_profile(&default, "{}"), PromptProfile::Default);
+}
+
+#[test]
+fn resolve_active_profile_uses_detection_only_without_selector()
+{
+    //! Passes when `--auto-profile` detects a profile only when no selector was given.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = "import boto3\ndf = spark.read.parquet('s3://bucket/raw')";
+
+    let auto = parse_cli_args(&make_args(&["doxcer", "--auto-profile", "test/example.py"])).unwrap();
+    assert_eq!(resolve_active_profile(&auto, source), PromptProfile::Aws);
+    assert_eq!(resolve_active_profile(&auto, "print('hello')"), PromptProfile::Default);
+
+    let explicit = parse_cli_args(&make_args(&["doxcer", "--auto-profile", "-fabric", "test/example.py"])).unwrap();
+    assert_eq!(resolve_active_profile(&explicit, source), PromptProfile::Fabric);
+
+    let default = parse_cli_args(&make_args(&["doxcer", "--auto-profile", "-default", "test/example.py"])).unwrap();
+    assert_eq!(resolve_active_profile(&default, source), PromptProfile::Default);
+
+    let disabled = parse_cli_args(&make_args(&["doxcer", "test/example.py"])).unwrap();
+    assert_eq!(resolve_active_profile(&disabled, source), PromptProfile::Default);
+}
+
+#[test]
+fn mock_response_runs_the_response_path_without_a_client()
+{
+    //! Passes when `--mock-response` is parsed and a loaded mock response goes through the content checks
+    //! (empty response, fence unwrapping, minimum length) without sending a request.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let dir = TestDir::new("mock-response");
+    let mock_path = dir.join("response.json");
+    std::fs::write(
+        &mock_path,
+        r#"{"choices":[{"message":{"role":"assistant","content":"```markdown\n# Sales\n\nLoads sales data.\n```"}}]}"#,
+    )
+    .unwrap();
+    let empty_path = dir.join("empty.json");
+    std::fs::write(&empty_path, r#"{"choices":[{"message":{"role":"assistant","content":"  "}}]}"#).unwrap();
+    let invalid_path = dir.join("invalid.json");
+    std::fs::write(&invalid_path, "not json").unwrap();
+
+    let mock_arg = mock_path.to_string_lossy().to_string();
+    let cli_args = parse_cli_args(&make_args(&["doxcer", "--mock-response", &mock_arg, "--stdout-only", "test/example.py"])).unwrap();
+    assert_eq!(cli_args.mock_response.as_deref(), Some(mock_arg.as_str()));
+
+    let notebook = make_notebook("Sales.Notebook/notebook-content.py", "print(1)");
+    let retry_policy = RetryPolicy { attempts: 1, delay_ms: 0 };
+    let client = Client::new();
+    let job = DocumentationJob
+    {
+        cli_args: &cli_args,
+        profile: PromptProfile::Default,
+        definitions: "",
+        prompt_template: "",
+        prompt_path: Path::new("prompt.md"),
+        template_version: None,
+        context_content: "",
+        prompt_layout: None,
+        output_schema: None,
+        reference_notebooks: &[],
+        compare_notebook: None,
+        api_retry_policy: &retry_policy,
+        client: &client,
+        akv_options: KeyVaultOptions::default(),
+        min_output_chars: 0,
+    };
+    let mut stats = RunStats::default();
+    let mut trace = RunTrace::default();
+
+    let chat_response = load_mock_response(&mock_path).unwrap();
+    let outcome = handle_chat_response(&job, &notebook, &chat_response, "2026-10-16 12:00:00", None, &mut stats, &mut trace);
+    assert_eq!(outcome, GenerateOutcome::Printed);
+    assert!(trace.response_chars.is_some());
+
+    let short_job = DocumentationJob { min_output_chars: 1000, ..job };
+    let outcome = handle_chat_response(&short_job, &notebook, &chat_response, "2026-10-16 12:00:00", None, &mut stats, &mut trace);
+    assert_eq!(outcome, GenerateOutcome::BelowMinimum);
+
+    let empty_response = load_mock_response(&empty_path).unwrap();
+    let outcome = handle_chat_response(&short_job, &notebook, &empty_response, "2026-10-16 12:00:00", None, &mut stats, &mut trace);
+    assert_eq!(outcome, GenerateOutcome::EmptyResponse);
+
+    let err = load_mock_response(&invalid_path).err().unwrap();
+    assert!(err.contains("not a valid chat response"));
+    assert!(load_mock_response(&dir.join("missing.json")).is_err());
+}
+
+#[test]
+fn find_context_and_docs_paths_point_to_expected_locations()
+{
+    //! Passes when context and docs paths resolve to expected names.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let context_path = find_context_path();
+    assert_eq!(context_path.file_name().unwrap().to_string_lossy(), "context.md");
+    assert!(context_path.exists());
+
+    let docs_path = find_docs_path();
+    assert_eq!(docs_path.file_name().unwrap().to_string_lossy(), "newly-documented");
+    assert_eq!(
+        docs_path.parent().and_then(|p| p.file_name()).unwrap().to_string_lossy(),
+        "docs"
+    );
+}
+
+
+// ----------------------------
+// notebook.rs
+// ----------------------------
+
+fn make_notebook(path: &str, source: &str) -> crate::notebook::Notebook
+{
+    //! Builds an in-memory notebook with the default cleaning rules.
+
+    let cleaner = crate::notebook::NotebookCleaner::new(PromptProfile::Default);
+    crate::notebook::Notebook::from_source(Path::new(path), source.to_string(), &cleaner)
+}
+
+#[test]
+fn notebook_from_source_cleans_and_derives_output_names()
+{
+    //! Passes when the notebook keeps the raw source, cleans metadata and derives names from the path.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = "# METADATA x\nimport pandas as pd\n\n\n# CELL\nprint(1)";
+    let notebook = make_notebook("Sales.Notebook/notebook-content.py", source);
+
+    assert_eq!(notebook.original_source, source);
+    assert_eq!(notebook.cleaned_source, "import pandas as pd\n\nprint(1)");
+    assert_eq!(notebook.output_stem, "Sales");
+    assert_eq!(notebook.output_display_name, "Sales.py");
+
+    let renamed = notebook.with_output_name(Some("sales_report"));
+    assert_eq!(renamed.output_stem, "sales_report");
+    assert_eq!(renamed.output_display_name, "sales_report.py");
+}
+
+#[test]
+fn notebook_load_reads_file_and_reports_missing_file()
+{
+    //! Passes when `Notebook::load` cleans a file from disk and returns an `[ERR]` for a missing file.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let dir = TestDir::new("notebook-load");
+    let path = dir.join("orders.sql");
+    std::fs::write(&path, "-- METADATA x\nSELECT 1").unwrap();
+    let cleaner = crate::notebook::NotebookCleaner::new(PromptProfile::Default);
+
+    let notebook = crate::notebook::Notebook::load(&path, &cleaner).unwrap();
+    assert_eq!(notebook.cleaned_source, "SELECT 1");
+    assert_eq!(notebook.file_name(), "orders.sql");
+
+    let err = crate::notebook::Notebook::load(&dir.join("missing.sql"), &cleaner).unwrap_err();
+    assert!(err.starts_with("[ERR] - Failed to read file"));
+}
+
+#[test]
+fn notebook_cells_are_cleaned_and_named_per_cell()
+{
+    //! Passes when cells are cleaned separately, empty cells are skipped and the rest are numbered
+    //! `cell-01`, `cell-02`, ...; a notebook without markers becomes a single `cell-01`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let cleaner = crate::notebook::NotebookCleaner::new(PromptProfile::Default);
+    let source = "# Fabric notebook source\n# CELL\nimport pandas as pd\n# METADATA x\n# CELL\n\n# CELL\nprint(1)";
+    let notebook = make_notebook("Sales.Notebook/notebook-content.py", source);
+
+    let cells = notebook.cells(&cleaner);
+    let stems: Vec<&str> = cells.iter().map(|cell| cell.output_stem.as_str()).collect();
+    assert_eq!(stems, vec!["Sales/cell-01", "Sales/cell-02"]);
+    assert_eq!(cells[0].cleaned_source, "import pandas as pd");
+    assert_eq!(cells[1].cleaned_source, "print(1)");
+    assert_eq!(cells[1].output_display_name, "Sales.py (cell 2)");
+
+    let single = make_notebook("orders.py", "print(1)").cells(&cleaner);
+    assert_eq!(single.len(), 1);
+    assert_eq!(single[0].output_stem, "orders/cell-01");
+    assert_eq!(single[0].cleaned_source, "print(1)");
+}
+
+
+// ----------------------------
+// notebook_formats.rs
+// ----------------------------
+
+#[test]
+fn strip_adf_metadata_removes_top_level_service_keys()
+{
+    //! Passes when `etag`, `lastModifiedAt`, `id` and `type` are removed only at the top level
+    //! and the documentation-relevant keys are kept.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = r#"{
+        "id": "/subscriptions/x",
+        "type": "Microsoft.DataFactory/factories/pipelines",
+        "name": "pl",
+        "etag": "abc",
+        "lastModifiedAt": "2026-01-01",
+        "properties": {
+            "activities": [{ "name": "Copy", "type": "Copy" }],
+            "parameters": { "run_date": { "type": "string" } },
+            "variables": {},
+            "annotations": ["finance"]
+        }
+    }"#;
+    let stripped = crate::notebook_formats::strip_adf_metadata(source).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&stripped).unwrap();
+
+    for key in crate::notebook_formats::ADF_METADATA_KEYS
+    {
+        assert!(value.get(key).is_none(), "{}", key);
+    }
+    assert_eq!(value["name"], "pl");
+    assert_eq!(value["properties"]["activities"][0]["type"], "Copy");
+    assert_eq!(value["properties"]["parameters"]["run_date"]["type"], "string");
+    assert_eq!(value["properties"]["annotations"][0], "finance");
+    assert!(stripped.contains("\n  \"name\": \"pl\""));
+}
+
+#[test]
+fn strip_adf_metadata_rejects_invalid_json()
+{
+    //! Passes when invalid JSON returns an error.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let err = crate::notebook_formats::strip_adf_metadata("{ not json").unwrap_err();
+    assert!(err.contains("Failed to parse Data Factory JSON"));
+}
+
+#[test]
+fn adf_json_flag_enables_stripping_for_json_inputs_only()
+{
+    //! Passes when `--adf-json` strips `.json` inputs regardless of profile and leaves them untouched without the flag.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = r#"{"id":"x","name":"pl"}"#;
+    let cleaner = |adf_json| crate::notebook::NotebookCleaner::new(PromptProfile::Default).with_adf_json(adf_json);
+
+    let stripped = cleaner(true).clean(source, Path::new("pipeline-content.json"));
+    assert!(!stripped.contains("\"id\""));
+    assert!(stripped.contains("\"name\": \"pl\""));
+
+    assert_eq!(cleaner(false).clean(source, Path::new("pipeline-content.json")), source);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--adf-json", "pipeline-content.json"])).unwrap();
+    assert!(parsed.adf_json);
+}
+
+#[test]
+fn concat_part_files_joins_parts_in_lexical_order()
+{
+    //! Passes when part files are joined in file name order regardless of creation order,
+    //! a part without a trailing newline is separated from the next one, the size sums the parts and the directory
+    //! loads as one notebook.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let root = TestDir::new("part-files");
+    let dir = root.join("Sales");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("part-02.py"), "write()\n").unwrap();
+    std::fs::write(dir.join("part-00.py"), "import pandas as pd").unwrap();
+    std::fs::write(dir.join("part-01.py"), "load()\n").unwrap();
+    std::fs::write(dir.join(".DS_Store"), "").unwrap();
+
+    assert!(crate::notebook_formats::is_part_file_dir(&dir));
+    assert_eq!(
+        crate::notebook_formats::concat_part_files(&dir).unwrap(),
+        "import pandas as pd\nload()\nwrite()\n"
+    );
+    assert_eq!(crate::notebook_formats::part_files_size(&dir), Some(34));
+
+    let notebook = crate::notebook::Notebook::load(&dir, &crate::notebook::NotebookCleaner::new(PromptProfile::Default)).unwrap();
+    assert_eq!(notebook.output_stem, "Sales");
+    assert!(notebook.cleaned_source.starts_with("import pandas as pd\nload()"));
+}
+
+#[test]
+fn part_file_dir_is_distinct_from_fabric_and_batch_dirs()
+{
+    //! Passes when a Fabric `.Notebook` folder or a folder mixing parts with other files is not a part file
+    //! directory, and a batch directory collects a part file directory as one input.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let root = TestDir::new("part-dirs");
+    let fabric = root.join("Sales.Notebook");
+    let mixed = root.join("mixed");
+    let parts = root.join("orders");
+    for dir in [&fabric, &mixed, &parts]
+    {
+        std::fs::create_dir_all(dir).unwrap();
+    }
+    std::fs::write(fabric.join("notebook-content.py"), "print(1)").unwrap();
+    std::fs::write(mixed.join("part-00.py"), "print(1)").unwrap();
+    std::fs::write(mixed.join("helpers.py"), "print(2)").unwrap();
+    std::fs::write(parts.join("part-00.py"), "print(1)").unwrap();
+    std::fs::write(parts.join("part-01.py"), "print(2)").unwrap();
+
+    assert!(!crate::notebook_formats::is_part_file_dir(&fabric));
+    assert!(!crate::notebook_formats::is_part_file_dir(&mixed));
+    assert!(crate::notebook_formats::concat_part_files(&mixed).is_err());
+    assert_eq!(crate::notebook_formats::part_files_size(&mixed), None);
+    assert!(!crate::notebook_formats::is_part_file_dir(&parts.join("part-00.py")));
+
+    let inputs = crate::batch::collect_batch_inputs(&root).unwrap().notebooks;
+    assert!(inputs.contains(&parts));
+    assert!(!inputs.contains(&parts.join("part-00.py")));
+    assert!(inputs.contains(&fabric.join("notebook-content.py")));
+}
+
+
+// ----------------------------
+// notebook_analysis.rs
+// ----------------------------
+
+#[test]
+fn detect_notebook_platform_recognizes_each_platform()
+{
+    //! Passes when each platform fixture resolves to its profile.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::notebook_analysis::detect_notebook_platform;
+
+    let databricks = "df = spark.sql(\"SELECT * FROM silver.orders\")\ndisplay(df)";
+    let synapse = "%%sql\nSELECT * FROM dbo.orders\ndf = spark.read.synapsesql(\"pool.dbo.orders\")";
+    let fabric = "import msal\nimport sempy.fabric as fabric\nworkspace = fabric.get_workspace_id()";
+    let aws = "import boto3\ns3 = boto3.client('s3')\npath = 's3://raw/orders/'";
+    let gcp = "from google.cloud import bigquery\nrows = bq.query(\"SELECT 1\")";
+
+    assert_eq!(detect_notebook_platform(databricks), Some(PromptProfile::Databricks));
+    assert_eq!(detect_notebook_platform(synapse), Some(PromptProfile::Synapse));
+    assert_eq!(detect_notebook_platform(fabric), Some(PromptProfile::Fabric));
+    assert_eq!(detect_notebook_platform(aws), Some(PromptProfile::Aws));
+    assert_eq!(detect_notebook_platform(gcp), Some(PromptProfile::Gcp));
+}
+
+#[test]
+fn detect_notebook_platform_returns_none_without_or_with_tied_matches()
+{
+    //! Passes when no pattern matches or two platforms tie.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::notebook_analysis::detect_notebook_platform;
+
+    assert_eq!(detect_notebook_platform("import pandas as pd\nprint(pd.__version__)"), None);
+    assert_eq!(detect_notebook_platform("import boto3\nfrom google.cloud import storage"), None);
+}
+
+#[test]
+fn detect_language_recognises_clearly_python_notebook()
+{
+    //! Passes when Python content without a language extension is detected as Python.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::notebook_analysis::{detect_language, Language};
+
+    let source = "import pandas as pd\nfrom pyspark.sql import functions as F\n\ndef load(path):\n    return pd.read_csv(path)\nprint(load('x'))";
+    assert_eq!(detect_language(source, Path::new("lab/experiment.ipynb")), Language::Python);
+    assert_eq!(Language::Python.comment_prefixes(), &["#"]);
+}
+
+#[test]
+fn detect_language_recognises_clearly_sql_notebook()
+{
+    //! Passes when SQL content is detected as SQL and a `.sql` extension wins over content.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::notebook_analysis::{detect_language, Language};
+
+    let source = "-- METADATA ********************\nSELECT order_id, amount\nFROM dbo.orders\nWHERE amount > 0";
+    assert_eq!(detect_language(source, Path::new("Orders.Notebook/notebook-content")), Language::Sql);
+    assert_eq!(detect_language("print(1)", Path::new("gold_model.sql")), Language::Sql);
+    assert_eq!(Language::Sql.comment_prefixes(), &["--"]);
+}
+
+#[test]
+fn detect_language_handles_mixed_content_notebook()
+{
+    //! Passes when the dominant language wins and an even mix falls back to `Unknown`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::notebook_analysis::{detect_language, Language};
+
+    let mostly_python = "import pandas as pd\nimport numpy as np\nprint(pd.__version__)\n%%sql\nSELECT 1";
+    assert_eq!(detect_language(mostly_python, Path::new("mixed.ipynb")), Language::Python);
+
+    let even_mix = "import pandas as pd\nSELECT * FROM sales\nval total = 1";
+    assert_eq!(detect_language(even_mix, Path::new("mixed.ipynb")), Language::Unknown);
+    assert_eq!(Language::Unknown.comment_prefixes(), &["#", "--", "//"]);
+}
+
+#[test]
+fn extract_imports_collects_unique_import_lines()
+{
+    //! Passes when `import` and `from ... import` lines are trimmed and deduplicated in order,
+    //! and SQL `FROM` clauses are ignored.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let source = "import pandas as pd\nfrom pyspark.sql import functions as F\n\ndf = spark.sql('SELECT * FROM sales')\n\
+                  def load():\n    import json\n    return json\nimport pandas as pd\nFROM dbo.orders\nimported = 1";
+
+    assert_eq!(
+        crate::notebook_analysis::extract_imports(source),
+        vec!["import pandas as pd", "from pyspark.sql import functions as F", "import json"]
+    );
+    assert!(crate::notebook_analysis::extract_imports("print(1)").is_empty());
+}
+
+
+// ----------------------------
+// prompt_builder.rs
+// ----------------------------
+
+#[test]
+fn prompt_builder_builds_single_source_prompt()
+{
+    //! Passes when the default prompt keeps the date, filename, definitions, template and code sections.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let prompt = crate::prompt_builder::PromptBuilder::new("2026-10-16 09:00:00", &make_notebook("orders.py", "print(1)"))
+        .with_definitions("| col |")
+        .with_template("# Template")
+        .build();
+
+    assert_eq!(
+        prompt,
+        "Current date time: 2026-10-16 09:00:00\n\nNotebook filename: orders.py\n\nDefinitions: | col |\n\nDocumentation template: # Template\n\nCode: print(1)"
+    );
+}
+
+#[test]
+fn prompt_builder_imports_section_precedes_code()
+{
+    //! Passes when the imports are listed in an `Imports:` section before the code and an empty list adds no section.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let notebook = make_notebook("orders.py", "import pandas as pd\nprint(1)");
+    let imports = vec!["import pandas as pd".to_string(), "import json".to_string()];
+
+    let prompt = crate::prompt_builder::PromptBuilder::new("2026-10-16 09:00:00", &notebook)
+        .with_imports(&imports)
+        .build();
+    assert!(prompt.contains("\n\nImports:\nimport pandas as pd\nimport json\n\nCode: import pandas as pd"));
+
+    let without = crate::prompt_builder::PromptBuilder::new("2026-10-16 09:00:00", &notebook)
+        .with_imports(&[])
+        .build();
+    assert!(!without.contains("Imports:"));
+}
+
+#[test]
+fn prompt_builder_definition_template_replaces_section_header()
+{
+    //! Passes when a custom definition template replaces the default `Definitions:` header.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let prompt = crate::prompt_builder::PromptBuilder::new("2026-10-16 09:00:00", &make_notebook("orders.py", "print(1)"))
+        .with_definitions("| col |")
+        .with_definition_template("Column Dictionary:\n{definitions}")
+        .build();
+
+    assert!(prompt.contains("\n\nColumn Dictionary:\n| col |\n\n"));
+    assert!(!prompt.contains("Definitions:"));
+}
+
+#[test]
+fn prompt_builder_compare_source_adds_new_and_old_code_sections()
+{
+    //! Passes when the compare prompt asks for changes and includes new code before old code.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let prompt = crate::prompt_builder::PromptBuilder::new("2026-10-16 09:00:00", &make_notebook("orders.py", "print('new')"))
+        .with_template("# Template")
+        .with_compare_source(&make_notebook("orders_v1.py", "print('old')"))
+        .build();
+
+    let new_at = prompt.find("New Code: print('new')").unwrap();
+    let old_at = prompt.find("Old Code: print('old')").unwrap();
+    assert!(new_at < old_at);
+    assert!(prompt.contains("what changed from the old version to the new version"));
+    assert!(!prompt.contains("\n\nCode: "));
+}
+
+#[test]
+fn prompt_builder_section_filter_adds_instruction()
+{
+    //! Passes when the section filter adds the "Generate only" instruction before the code.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let prompt = crate::prompt_builder::PromptBuilder::new("2026-10-16 09:00:00", &make_notebook("orders.py", "print(1)"))
+        .with_template("# Template")
+        .with_section_filter("Parameters")
+        .build();
+
+    let instruction_at = prompt
+        .find("Generate only the Parameters section of the documentation.")
+        .unwrap();
+    assert!(instruction_at < prompt.find("Code: print(1)").unwrap());
+}
+
+#[test]
+fn prompt_builder_reference_sections_are_labelled_context()
+{
+    //! Passes when each reference gets a labelled context section and the subject stays under `Code:`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let prompt = crate::prompt_builder::PromptBuilder::new("2026-10-16 09:00:00", &make_notebook("orders.py", "print(ENV)"))
+        .with_reference(&make_notebook("shared/config.py", "ENV = 'prd'"))
+        .with_reference(&make_notebook("shared/params.py", "DAYS = 7"))
+        .build();
+
+    assert_eq!(prompt.matches("Reference (not the subject)").count(), 2);
+    assert!(prompt.contains("Reference (not the subject) config.py"));
+    assert!(prompt.contains("ENV = 'prd'"));
+    assert!(prompt.ends_with("Code: print(ENV)"));
+}
+
+#[test]
+fn assemble_prompt_default_layout_matches_builder_output()
+{
+    //! Passes when the default layout drops absent sections, keeps blank lines inside values,
+    //! and `templates/prompt_layout.md` holds the same layout.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::prompt_builder::{assemble_prompt, DEFAULT_PROMPT_LAYOUT};
+
+    let prompt = assemble_prompt(
+        DEFAULT_PROMPT_LAYOUT,
+        &[
+            ("date", Some("2026-10-16 09:00:00")),
+            ("filename", Some("orders.py")),
+            ("definitions", Some("")),
+            ("definition_section", Some("Definitions: ")),
+            ("template", Some("# Template")),
+            ("section", None),
+            ("references", None),
+            ("imports", None),
+            ("instructions", None),
+            ("code", Some("import os\n\n\nprint(1)")),
+            ("new_code", None),
+            ("old_code", None),
+        ],
+    );
+    assert_eq!(
+        prompt,
+        "Current date time: 2026-10-16 09:00:00\n\nNotebook filename: orders.py\n\nDefinitions: \n\nDocumentation template: # Template\n\nCode: import os\n\n\nprint(1)"
+    );
+
+    let file_layout = std::fs::read_to_string(find_repo_root_path().join("templates").join("prompt_layout.md")).unwrap();
+    assert_eq!(file_layout.trim_end(), DEFAULT_PROMPT_LAYOUT);
+}
+
+#[test]
+fn assemble_prompt_custom_tagged_layout()
+{
+    //! Passes when a tagged layout wraps each section and whole tagged blocks of absent sections are left out.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let layout = "<notebook name=\"{filename}\">\n{code}\n</notebook>\n\n<section_filter>\n{section}\n</section_filter>\n\n<definitions>\n{definitions}\n</definitions>\n";
+
+    let prompt = crate::prompt_builder::PromptBuilder::new("2026-10-16 09:00:00", &make_notebook("orders.py", "print(1)"))
+        .with_definitions("| col |")
+        .with_layout(layout)
+        .build();
+
+    assert_eq!(
+        prompt,
+        "<notebook name=\"orders.py\">\nprint(1)\n</notebook>\n\n<definitions>\n| col |\n</definitions>"
+    );
+}
+
+
+// ----------------------------
+// output_validation.rs
+// ----------------------------
+
+fn doc_schema() -> serde_json::Value
+{
+    //! Builds a small documentation schema shared by the output validation tests.
+
+    serde_json::json!({
+        "type": "object",
+        "required": ["title", "columns"],
+        "properties": {
+            "title": { "type": "string" },
+            "columns": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": { "name": { "type": "string" }, "nullable": { "type": "boolean" } }
+                }
+            }
+        }
+    })
+}
+
+#[test]
+fn validate_against_schema_accepts_matching_document()
+{
+    //! Passes when a document with all required keys and correct types validates.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let document = serde_json::json!({
+        "title": "dim_project",
+        "columns": [{ "name": "dim_project_fk", "nullable": false }],
+        "extra": 1
+    });
+
+    assert_eq!(crate::output_validation::validate_against_schema(&document, &doc_schema()), Ok(()));
+}
+
+#[test]
+fn validate_against_schema_reports_missing_keys_and_wrong_types()
+{
+    //! Passes when every missing key and type mismatch is reported with its path.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let document = serde_json::json!({
+        "columns": [{ "nullable": "no" }, "dim_date_fk"]
+    });
+
+    let errors = crate::output_validation::validate_against_schema(&document, &doc_schema()).unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            "$: missing required key 'title'".to_string(),
+            "$.columns[0]: missing required key 'name'".to_string(),
+            "$.columns[0].nullable: expected type boolean, got string".to_string(),
+            "$.columns[1]: expected type object, got string".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn strip_json_code_fence_returns_fenced_body()
+{
+    //! Passes when a fenced JSON block is unwrapped and plain JSON is left alone.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(crate::output_validation::strip_json_code_fence("```json\n{\"a\": 1}\n```"), "{\"a\": 1}");
+    assert_eq!(crate::output_validation::strip_json_code_fence("  {\"a\": 1}\n"), "{\"a\": 1}");
+}
+
+#[test]
+fn unwrap_outer_fence_unwraps_fully_wrapped_doc()
+{
+    //! Passes when a doc wrapped in one ```` ```markdown ```` fence is unwrapped, inner language fences included.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::output_validation::unwrap_outer_fence;
+
+    assert_eq!(unwrap_outer_fence("```markdown\n# Sales\n\nLoads orders.\n```\n"), "# Sales\n\nLoads orders.");
+    assert_eq!(
+        unwrap_outer_fence("```md\n# Sales\n\n```python\nload()\n```\n\nDone.\n```"),
+        "# Sales\n\n```python\nload()\n```\n\nDone."
+    );
+    assert_eq!(unwrap_outer_fence("````\n# Sales\n```\nraw\n```\n````"), "# Sales\n```\nraw\n```");
+}
+
+#[test]
+fn unwrap_outer_fence_keeps_doc_with_inner_fences_only()
+{
+    //! Passes when fenced code among prose, or fences that start and end the doc but are separate blocks,
+    //! are left untouched.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::output_validation::unwrap_outer_fence;
+
+    let prose = "# Sales\n\n```python\nload()\n```\n\nDone.";
+    assert_eq!(unwrap_outer_fence(prose), prose);
+
+    let two_blocks = "```\nload()\n```\n\nThen:\n\n```\nsave()\n```";
+    assert_eq!(unwrap_outer_fence(two_blocks), two_blocks);
+
+    let python_only = "```python\nload()\n```";
+    assert_eq!(unwrap_outer_fence(python_only), python_only);
+}
+
+#[test]
+fn unwrap_outer_fence_keeps_doc_without_fences()
+{
+    //! Passes when a plain Markdown doc is returned unchanged.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let doc = "# Sales\n\nLoads orders.\n";
+    assert_eq!(crate::output_validation::unwrap_outer_fence(doc), doc);
+}
+
+#[test]
+fn check_required_sections_passes_when_all_headings_present()
+{
+    //! Passes when every required title appears as a `##` or `###` heading.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let required = crate::output_validation::parse_required_sections(" Overview, Inputs ,, Lineage ");
+    assert_eq!(required, vec!["Overview".to_string(), "Inputs".to_string(), "Lineage".to_string()]);
+
+    let content = "# Sales\n\n## Overview\nLoads orders.\n\n### Inputs ###\n- bronze.orders\n\n## Lineage\n";
+    assert!(crate::output_validation::check_required_sections(content, &required).is_empty());
+}
+
+#[test]
+fn check_required_sections_reports_missing_headings_in_order()
+{
+    //! Passes when titles without a heading are returned in the given order, and text or `#`/`####`
+    //! headings do not count.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let required = crate::output_validation::parse_required_sections("Overview,Inputs,Outputs,Lineage");
+    let content = "# Overview\nThe Inputs are listed below.\n\n#### Outputs\n\n## Lineage";
+
+    assert_eq!(
+        crate::output_validation::check_required_sections(content, &required),
+        vec!["Overview".to_string(), "Inputs".to_string(), "Outputs".to_string()]
+    );
+    assert!(crate::output_validation::check_required_sections(content, &[]).is_empty());
+}
+
+#[test]
+fn check_required_sections_matches_case_insensitively()
+{
+    //! Passes when headings match the required titles regardless of case.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let required = crate::output_validation::parse_required_sections("overview,DATA LINEAGE");
+    let content = "## OVERVIEW\n\n### Data Lineage";
+
+    assert!(crate::output_validation::check_required_sections(content, &required).is_empty());
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--strict", "test/example.py"])).unwrap();
+    assert!(parsed.strict);
+    assert_eq!(outcome_exit_code(GenerateOutcome::MissingSections), 6);
+}
+
+
+// ----------------------------
+// publish.rs
+// ----------------------------
+
+struct RecordingSender
+{
+    /// Type: Struct.
+    /// Input:
+    /// - HTTP status returned for every request.
+    ///
+    /// Output:
+    /// - Sender that records wiki requests instead of sending them.
+    ///
+    /// Exceptions:
+    /// - None.
+
+    status: u16,
+    requests: std::cell::RefCell<Vec<crate::publish::WikiPageRequest>>,
+}
+
+impl crate::publish::WikiSender for RecordingSender
+{
+    fn put(&self, request: &crate::publish::WikiPageRequest) -> std::result::Result<u16, String>
+    {
+        self.requests.borrow_mut().push(request.clone());
+        Ok(self.status)
+    }
+}
+
+#[test]
+fn publish_doc_puts_content_to_page_url()
+{
+    //! Passes when the page name is appended to `WIKI_API_URL` and the Markdown is sent as `content`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let sender = RecordingSender { status: 201, requests: std::cell::RefCell::new(Vec::new()) };
+
+    let result = crate::publish::publish_doc(
+        &sender,
+        "https://wiki.example.com/api/pages/",
+        "secret-token",
+        "Sales Report",
+        "# Sales",
+    );
+
+    assert_eq!(result, Ok(()));
+    let requests = sender.requests.borrow();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].url, "https://wiki.example.com/api/pages/Sales%20Report");
+    assert_eq!(requests[0].token, "secret-token");
+    assert_eq!(requests[0].body, serde_json::json!({ "content": "# Sales" }));
+}
+
+#[test]
+fn publish_doc_reports_rejected_status_and_invalid_url()
+{
+    //! Passes when a non-2xx status and an unparsable URL are returned as errors.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let sender = RecordingSender { status: 403, requests: std::cell::RefCell::new(Vec::new()) };
+
+    let rejected = crate::publish::publish_doc(&sender, "https://wiki.example.com/pages", "t", "sales", "# Sales");
+    assert!(rejected.unwrap_err().contains("HTTP status 403"));
+
+    let invalid = crate::publish::publish_doc(&sender, "not a url", "t", "sales", "# Sales");
+    assert!(invalid.unwrap_err().contains("Invalid WIKI_API_URL"));
+    assert_eq!(sender.requests.borrow().len(), 1);
+}
+
+
+// ----------------------------
+// retry.rs
+// ----------------------------
+
+#[test]
+fn resolve_retry_policy_prefers_cli_then_env_then_defaults()
+{
+    //! Passes when CLI values win over env values, which win over the defaults, and 0 attempts is rejected.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::retry::{resolve_retry_policy, RetryPolicy};
+
+    assert_eq!(resolve_retry_policy(None, None, None, None), Ok(RetryPolicy { attempts: 3, delay_ms: 1000 }));
+    assert_eq!(
+        resolve_retry_policy(Some(5), None, Some("2"), Some("250")),
+        Ok(RetryPolicy { attempts: 5, delay_ms: 250 })
+    );
+    assert!(resolve_retry_policy(None, None, Some("0"), None).is_err());
+    assert!(resolve_retry_policy(None, None, None, Some("-1")).is_err());
+}
+
+#[test]
+fn retry_policy_run_stops_on_success_or_last_attempt()
+{
+    //! Passes when retryable failures are retried up to the attempt limit and success stops early.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::retry::RetryPolicy;
+
+    let policy = RetryPolicy { attempts: 3, delay_ms: 0 };
+
+    let mut calls = 0;
+    let result: std::result::Result<u32, String> = policy.run("test", || { calls += 1; Err("busy".to_string()) }, |r| r.is_err());
+    assert_eq!(result, Err("busy".to_string()));
+    assert_eq!(calls, 3);
+
+    let mut calls = 0;
+    let result: std::result::Result<u32, String> = policy.run(
+        "test",
+        || { calls += 1; if calls < 2 { Err("busy".to_string()) } else { Ok(calls) } },
+        |r| r.is_err(),
+    );
+    assert_eq!(result, Ok(2));
+
+    assert!(crate::retry::is_retryable_status(429));
+    assert!(crate::retry::is_retryable_status(503));
+    assert!(!crate::retry::is_retryable_status(400));
+}
+
+
+// ----------------------------
+// batch.rs
+// ----------------------------
+
+fn stub_path_info(path: &Path) -> crate::batch::PathInfo
+{
+    //! Returns fixed metadata for the `sort_paths` tests.
+
+    let (size, modified_secs) = match path.to_str().unwrap()
+    {
+        "nb/a.py" => (300, Some(10)),
+        "nb/b.py" => (100, Some(30)),
+        "nb/c.py" => (200, None),
+        "nb/d.py" => (100, Some(20)),
+        _ => (0, None),
+    };
+    crate::batch::PathInfo
+    {
+        size,
+        modified: modified_secs.map(|secs| std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs)),
+    }
+}
+
+#[test]
+fn sort_paths_orders_by_size_with_name_tie_break()
+{
+    //! Passes when `size-asc`/`size-desc` sort by file size and equal sizes keep path order.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::batch::{sort_paths, BatchOrder};
+
+    let paths: Vec<PathBuf> = ["nb/c.py", "nb/a.py", "nb/d.py", "nb/b.py"].iter().map(PathBuf::from).collect();
+    let names = |sorted: Vec<PathBuf>| sorted.iter().map(|path| path.display().to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        names(sort_paths(paths.clone(), BatchOrder::SizeAsc, stub_path_info)),
+        vec!["nb/b.py", "nb/d.py", "nb/c.py", "nb/a.py"]
+    );
+    assert_eq!(
+        names(sort_paths(paths, BatchOrder::SizeDesc, stub_path_info)),
+        vec!["nb/a.py", "nb/c.py", "nb/b.py", "nb/d.py"]
+    );
+}
+
+#[test]
+fn sort_paths_orders_by_name_and_mtime()
+{
+    //! Passes when `name` sorts by path and `mtime` puts the newest file first and unknown times last.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::batch::{parse_batch_order, sort_paths, BatchOrder};
+
+    let paths: Vec<PathBuf> = ["nb/c.py", "nb/a.py", "nb/d.py", "nb/b.py"].iter().map(PathBuf::from).collect();
+    let names = |sorted: Vec<PathBuf>| sorted.iter().map(|path| path.display().to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        names(sort_paths(paths.clone(), BatchOrder::Name, stub_path_info)),
+        vec!["nb/a.py", "nb/b.py", "nb/c.py", "nb/d.py"]
+    );
+    assert_eq!(
+        names(sort_paths(paths, BatchOrder::Mtime, stub_path_info)),
+        vec!["nb/b.py", "nb/d.py", "nb/a.py", "nb/c.py"]
+    );
+
+    assert_eq!(parse_batch_order("Size-Desc"), Some(BatchOrder::SizeDesc));
+    assert_eq!(parse_batch_order("size"), None);
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--order", "mtime", "notebooks/"])).unwrap();
+    assert_eq!(parsed.batch_order, Some(BatchOrder::Mtime));
+    assert!(parse_cli_args(&make_args(&["doxcer", "--order", "random", "notebooks/"])).is_err());
+}
+
+#[test]
+fn failure_list_round_trips_and_clears()
+{
+    //! Passes when the failure list is written, read back in order, rewritten and removed when empty.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let batch_dir = TestDir::new("failures");
+    let list_path = crate::batch::failure_list_path(&batch_dir);
+
+    let failures = vec![batch_dir.join("b.py"), batch_dir.join("sub").join("a.py")];
+    crate::batch::write_failure_list(&list_path, &failures).unwrap();
+    let expected_content = format!("b.py\n{}\n", Path::new("sub").join("a.py").display());
+    assert_eq!(fs::read_to_string(&list_path).unwrap(), expected_content);
+    assert_eq!(crate::batch::read_failure_list(&list_path).unwrap(), failures);
+
+    let remaining = vec![batch_dir.join("sub").join("a.py")];
+    crate::batch::write_failure_list(&list_path, &remaining).unwrap();
+    assert_eq!(crate::batch::read_failure_list(&list_path).unwrap(), remaining);
+
+    crate::batch::write_failure_list(&list_path, &[]).unwrap();
+    assert!(!list_path.exists());
+    assert!(crate::batch::read_failure_list(&list_path).unwrap().is_empty());
+}
+
+#[test]
+fn collect_batch_inputs_reports_skipped_internal_notebooks()
+{
+    //! Passes when `_`-prefixed notebooks are returned as skipped and other internal files are ignored.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let root = TestDir::new("batch-skipped");
+    fs::write(root.join("orders.py"), "print(1)").unwrap();
+    fs::write(root.join("_draft.py"), "print(2)").unwrap();
+    fs::write(root.join(crate::batch::FAILURE_LIST_FILE), "orders.py\n").unwrap();
+
+    let collected = crate::batch::collect_batch_inputs(&root).unwrap();
+    assert_eq!(collected.notebooks, vec![root.join("orders.py")]);
+    assert_eq!(collected.skipped, vec![root.join("_draft.py")]);
+}
+
+#[test]
+fn is_batch_input_skips_internal_and_unknown_files()
+{
+    //! Passes when notebooks are accepted and the failure list or other files are skipped.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert!(crate::batch::is_batch_input(Path::new("dir/notebook-content.py")));
+    assert!(crate::batch::is_batch_input(Path::new("dir/pipeline-content.json")));
+    assert!(!crate::batch::is_batch_input(Path::new("dir/_failures.txt")));
+    assert!(!crate::batch::is_batch_input(Path::new("dir/_draft.py")));
+    assert!(!crate::batch::is_batch_input(Path::new("dir/README.md")));
+}
+
+
+#[test]
+fn progress_bar_tracks_batch_length_and_position()
+{
+    //! Passes when the `--progress` bar is sized to the batch and advances per notebook.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let bar = crate::batch::progress_bar(4);
+    bar.set_message("fct_sales");
+    bar.inc(1);
+
+    assert_eq!(bar.length(), Some(4));
+    assert_eq!(bar.position(), 1);
+    assert_eq!(bar.message(), "fct_sales");
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--progress", "notebooks/"])).unwrap();
+    assert!(parsed.progress);
+}
+
+// ----------------------------
+// logger.rs
+// ----------------------------
+
+#[test]
+fn logger_counts_warnings()
+{
+    //! Passes when each warning increments the logger's warning count.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let logger = crate::logger::Logger::new();
+    assert_eq!(logger.warning_count(), 0);
+
+    logger.warn(format_args!("first {}", 1));
+    logger.warn(format_args!("second"));
+    assert_eq!(logger.warning_count(), 2);
+}
+
+#[test]
+fn logger_prints_through_an_active_progress_bar()
+{
+    //! Passes when log lines and suspended output run while a progress bar is registered and the bar keeps
+    //! its position, and output still runs after the bar is cleared.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let logger = crate::logger::Logger::new();
+    let progress_bar = indicatif::ProgressBar::hidden();
+    progress_bar.set_length(2);
+    progress_bar.inc(1);
+    logger.set_progress_bar(Some(progress_bar.clone()));
+
+    logger.info(format_args!("processing {}", "orders.py"));
+    logger.warn(format_args!("slow response"));
+    assert_eq!(logger.suspend(|| 7), 7);
+    assert_eq!(progress_bar.position(), 1);
+    assert_eq!(logger.warning_count(), 1);
+
+    logger.set_progress_bar(None);
+    assert_eq!(logger.suspend(|| "done"), "done");
+}
+
+
+// ----------------------------
+// io_utils.rs
+// ----------------------------
+
+#[test]
+fn truncate_at_newline_keeps_short_text()
+{
+    //! Passes when text within the limit is returned unchanged.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(crate::io_utils::truncate_at_newline("| a |\n| b |\n", 12), "| a |\n| b |\n");
+}
+
+#[test]
+fn truncate_at_newline_cuts_at_last_newline_within_limit()
+{
+    //! Passes when truncation ends on the last complete line that fits.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let table = "| col |\n| --- |\n| value |\n";
+    assert_eq!(crate::io_utils::truncate_at_newline(table, 20), "| col |\n| --- |\n");
+}
+
+#[test]
+fn truncate_at_newline_counts_chars_and_hard_cuts_without_newline()
+{
+    //! Passes when limits count characters and a single long line is hard-cut.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(crate::io_utils::truncate_at_newline("ééééé", 3), "ééé");
+}
+
+#[test]
+fn truncate_for_echo_marks_shortened_text()
+{
+    //! Passes when text over the limit is cut at a character count with a `…` marker and shorter text is unchanged.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(crate::io_utils::truncate_for_echo("# Sales\n\nLoads orders.", 7), "# Sales…");
+    assert_eq!(crate::io_utils::truncate_for_echo("# Sales", 7), "# Sales");
+    assert_eq!(crate::io_utils::truncate_for_echo("ééééé", 2), "éé…");
+    assert_eq!(crate::io_utils::truncate_for_echo("abc", 0), "…");
+}
+
+#[test]
+fn substitute_placeholders_applies_the_dollar_escape_to_source_text_only()
+{
+    //! Passes when a `$` in the source escapes a placeholder, but a substituted value ending in `$` does not.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::io_utils::{substitute_placeholders, PlaceholderEscape, PlaceholderSyntax};
+
+    let syntax = PlaceholderSyntax
+    {
+        open: "${",
+        close: "}",
+        is_name: |name| !name.is_empty(),
+        escape: Some(PlaceholderEscape::Drop),
+    };
+    let resolve = |name: &str| (name == "PRICE").then(|| "5$".to_string());
+
+    let (rendered, unresolved) = substitute_placeholders("${PRICE}${PRICE} $${PRICE} ${OTHER}", &syntax, false, resolve);
+    assert_eq!(rendered, "5$5$ ${PRICE} ${OTHER}");
+    assert_eq!(unresolved, vec!["OTHER".to_string()]);
+
+    let (blanked, _) = substitute_placeholders("[${OTHER}]", &syntax, true, resolve);
+    assert_eq!(blanked, "[]");
+}
+
+#[test]
+fn line_diff_lists_removed_and_added_lines()
+{
+    //! Passes when changed lines are marked with `-`/`+`, unchanged lines are left out and equal texts give no diff.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let old = "# Sales\n\nLoads orders.\nWrites bronze.\n";
+    let new = "# Sales\n\nLoads orders and returns.\nWrites bronze.\nWrites silver.\n";
+
+    assert_eq!(
+        crate::io_utils::line_diff(old, new),
+        "- Loads orders.\n+ Loads orders and returns.\n+ Writes silver.\n"
+    );
+    assert_eq!(crate::io_utils::line_diff(old, old), "");
+    assert_eq!(crate::io_utils::line_diff("", "a"), "+ a\n");
+}
+
+#[test]
+fn encode_output_writes_utf16le_with_bom()
+{
+    //! Passes when UTF-16 LE output starts with the `FF FE` BOM and round-trips, and UTF-8 output is unchanged.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::io_utils::{encode_output, parse_output_encoding, OutputEncoding};
+
+    assert_eq!(encode_output("# Ü", OutputEncoding::Utf8), "# Ü".as_bytes());
+
+    let bytes = encode_output("# Ü", OutputEncoding::Utf16Le);
+    assert_eq!(bytes, vec![0xFF, 0xFE, b'#', 0x00, b' ', 0x00, 0xDC, 0x00]);
+    let units: Vec<u16> = bytes[2..].chunks(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+    assert_eq!(String::from_utf16(&units).unwrap(), "# Ü");
+
+    assert_eq!(parse_output_encoding("UTF-16LE"), Some(OutputEncoding::Utf16Le));
+    assert_eq!(parse_output_encoding("utf8"), Some(OutputEncoding::Utf8));
+    assert_eq!(parse_output_encoding("latin1"), None);
+}
+
+#[test]
+fn atomic_write_replaces_file_and_leaves_no_temp_file()
+{
+    //! Passes when the destination holds the new content and no temp file remains in the directory.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let dir = TestDir::new("atomic-write");
+    let path = dir.join("Sales.md");
+
+    std::fs::write(&path, "# Old").unwrap();
+    crate::io_utils::atomic_write(&path, "# New\n\nComplete document.").unwrap();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "# New\n\nComplete document.");
+    let entries: Vec<String> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(entries, vec!["Sales.md".to_string()]);
+
+    let missing_dir = dir.join("missing").join("Sales.md");
+    assert!(crate::io_utils::atomic_write(&missing_dir, "# New").is_err());
+}
+
+#[test]
+fn append_changelog_entry_creates_file_and_skips_duplicates()
+{
+    //! Passes when the changelog is created on first use, a repeated entry is not appended again
+    //! and a new entry is appended on its own line.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let dir = TestDir::new("changelog");
+    let path = dir.join("CHANGELOG.md");
+
+    crate::io_utils::append_changelog_entry(&path, "- 2026-10-16 regenerated Sales (python)").unwrap();
+    crate::io_utils::append_changelog_entry(&path, "- 2026-10-16 regenerated Sales (python)").unwrap();
+    crate::io_utils::append_changelog_entry(&path, "- 2026-10-17 regenerated Sales (python)").unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "- 2026-10-16 regenerated Sales (python)\n- 2026-10-17 regenerated Sales (python)\n"
+    );
+}
+
+#[test]
+fn append_line_always_appends_and_adds_missing_newline()
+{
+    //! Passes when `append_line` appends duplicates (the `--force` path) and separates them from a
+    //! file that does not end with a newline.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let dir = TestDir::new("append-line");
+    let path = dir.join("CHANGELOG.md");
+
+    std::fs::write(&path, "# Changelog").unwrap();
+    crate::io_utils::append_line(&path, "- entry").unwrap();
+    crate::io_utils::append_line(&path, "- entry").unwrap();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Changelog\n- entry\n- entry\n");
+}
+
+// ----------------------------
+// fetch_definitions.rs
+// ----------------------------
+
+#[test]
+fn odbc_connection_string_builder_applies_tls_settings()
+{
+    //! Passes when the default keeps `Encrypt=yes;TrustServerCertificate=yes` and overrides switch them off.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::fetch_definitions::{OdbcConnectionStringBuilder, OdbcEncrypt};
+
+    let builder = OdbcConnectionStringBuilder::new(" sql.example.com ", "defs", "client-id", "secret");
+
+    let default = builder.clone().build();
+    assert!(default.contains("Server=tcp:sql.example.com,1433;Database=defs;"));
+    assert!(default.contains("Encrypt=yes;TrustServerCertificate=yes;"));
+
+    let verified = builder.clone().with_trust_server_certificate(false).build();
+    assert!(verified.contains("Encrypt=yes;TrustServerCertificate=no;"));
+
+    let unencrypted = builder.clone().with_encrypt(OdbcEncrypt::No).build();
+    assert!(unencrypted.contains("Encrypt=no;TrustServerCertificate=yes;"));
+    assert!(unencrypted.ends_with("UID=client-id;PWD=secret;"));
+
+    let strict = builder.with_encrypt(OdbcEncrypt::Strict).build();
+    assert!(strict.contains("Encrypt=strict;TrustServerCertificate=yes;"));
+}
+
+#[test]
+fn run_blocking_returns_task_result_and_reports_panics()
+{
+    //! Passes when a blocking task's result is returned from the async wrapper and a panicking task becomes an error.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::fetch_definitions::run_blocking;
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    let value = runtime.block_on(run_blocking(|| Ok(42))).unwrap();
+    assert_eq!(value, 42);
+
+    let err = runtime.block_on(run_blocking::<u32, _>(|| panic!("ODBC driver crashed"))).unwrap_err();
+    assert!(err.to_string().contains("Definitions task did not complete"));
+}
+
+#[test]
+fn parse_odbc_encrypt_accepts_only_yes_no_strict()
+{
+    //! Passes when `yes`, `no` and `strict` parse case-insensitively and other values are rejected.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::fetch_definitions::{parse_odbc_encrypt, OdbcEncrypt};
+
+    assert_eq!(parse_odbc_encrypt("yes"), Some(OdbcEncrypt::Yes));
+    assert_eq!(parse_odbc_encrypt(" NO "), Some(OdbcEncrypt::No));
+    assert_eq!(parse_odbc_encrypt("Strict"), Some(OdbcEncrypt::Strict));
+    assert_eq!(parse_odbc_encrypt("true"), None);
+    assert_eq!(parse_odbc_encrypt(""), None);
+}
+
+#[test]
+fn find_fabric_sql_path_prefers_profile_query_and_falls_back_to_fabric()
+{
+    //! Passes when `fetch_<profile>_definitions.sql` is used when present and the Fabric query otherwise.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let repo_root = TestDir::new("sql-path");
+    let sql_dir = repo_root.join("sql");
+    fs::create_dir_all(&sql_dir).unwrap();
+    fs::write(sql_dir.join("fetch_databricks_definitions.sql"), "SELECT 1").unwrap();
+
+    let path = crate::fetch_definitions::find_fabric_sql_path(&repo_root, "databricks");
+    assert_eq!(path, sql_dir.join("fetch_databricks_definitions.sql"));
+
+    let path = crate::fetch_definitions::find_fabric_sql_path(&repo_root, "synapse");
+    assert_eq!(path, sql_dir.join("fetch_fabric_definitions.sql"));
+
+    let path = crate::fetch_definitions::find_fabric_sql_path(&repo_root, "");
+    assert_eq!(path, sql_dir.join("fetch_fabric_definitions.sql"));
+}
+
+#[test]
+fn substitute_sql_params_replaces_known_and_reports_unknown_placeholders()
+{
+    //! Passes when `{{KEY}}` placeholders are substituted and unknown ones are kept and reported once.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let params = HashMap::from([("schema_name".to_string(), "dbo".to_string())]);
+    let sql = "SELECT * FROM t WHERE [schema] = '{{schema_name}}' AND env = '{{environment}}' OR env2 = '{{environment}}' AND x = '{{ not a param }}'";
+
+    let (rendered, unresolved) = crate::fetch_definitions::substitute_sql_params(sql, &params);
+
+    assert_eq!(
+        rendered,
+        "SELECT * FROM t WHERE [schema] = 'dbo' AND env = '{{environment}}' OR env2 = '{{environment}}' AND x = '{{ not a param }}'"
+    );
+    assert_eq!(unresolved, vec!["environment".to_string()]);
+}
+
+#[test]
+fn definitions_markdown_table_returns_info_for_empty_columns()
+{
+    //! Passes when empty columns return the no-definitions info message.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let result = crate::fetch_definitions::format_definitions_as_markdown_table(&[], &[]);
+    assert_eq!(result, "[INF] - No definition rows returned.");
+}
+
+#[test]
+fn definitions_markdown_table_renders_header_and_separator()
+{
+    //! Passes when only header/separator are rendered for empty rows.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let columns = vec!["col_a".to_string(), "col_b".to_string()];
+    let rows: Vec<Vec<String>> = Vec::new();
+
+    let result = crate::fetch_definitions::format_definitions_as_markdown_table(&columns, &rows);
+    let expected = concat!("| col_a | col_b |\n", "| --- | --- |\n");
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn definitions_markdown_table_escapes_pipe_and_newline_characters()
+{
+    //! Passes when cell values are escaped/sanitized for Markdown.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let columns = vec!["name|raw".to_string(), "description".to_string()];
+    let rows = vec![vec![
+        "left|value".to_string(),
+        "line1\nline2\rline3".to_string(),
+    ]];
+
+    let result = crate::fetch_definitions::format_definitions_as_markdown_table(&columns, &rows);
+    let expected = concat!(
+        "| name\\|raw | description |\n",
+        "| --- | --- |\n",
+        "| left\\|value | line1 line2 line3 |\n"
+    );
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn definitions_markdown_table_suffixes_duplicate_column_names()
+{
+    //! Passes when duplicate headers get `_2`/`_3` suffixes and row cells stay aligned.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let columns = vec![
+        "name".to_string(),
+        "name".to_string(),
+        "name_2".to_string(),
+        "name".to_string(),
+    ];
+    let rows = vec![vec![
+        "a".to_string(),
+        "b".to_string(),
+        "c".to_string(),
+        "d".to_string(),
+    ]];
+
+    let result = crate::fetch_definitions::format_definitions_as_markdown_table(&columns, &rows);
+    let expected = concat!(
+        "| name | name_3 | name_2 | name_4 |\n",
+        "| --- | --- | --- | --- |\n",
+        "| a | b | c | d |\n"
+    );
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn definitions_markdown_table_pads_missing_cells_and_ignores_extra_cells()
+{
+    //! Passes when short rows are padded and extra row columns are ignored.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let columns = vec!["a".to_string(), "b".to_string()];
+    let rows = vec![
+        vec!["only-a".to_string()],
+        vec!["a2".to_string(), "b2".to_string(), "extra".to_string()],
+    ];
+
+    let result = crate::fetch_definitions::format_definitions_as_markdown_table(&columns, &rows);
+    let expected = concat!(
+        "| a | b |\n",
+        "| --- | --- |\n",
+        "| only-a |  |\n",
+        "| a2 | b2 |\n"
+    );
+
+    assert_eq!(result, expected);
+}
+
+
+#[test]
+fn escape_like_pattern_escapes_underscores()
+{
+    //! Passes when underscores are escaped so they match literally.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let result = crate::fetch_definitions::escape_like_pattern("fct_daily_sales");
+    assert_eq!(result, r"fct\_daily\_sales");
+}
+
+#[test]
+fn escape_like_pattern_escapes_percent_and_backslash()
+{
+    //! Passes when percent signs and backslashes are escaped and other characters are kept.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(crate::fetch_definitions::escape_like_pattern("100%"), r"100\%");
+    assert_eq!(crate::fetch_definitions::escape_like_pattern(r"a\b"), r"a\\b");
+    assert_eq!(crate::fetch_definitions::escape_like_pattern("dim-project"), "dim-project");
+}
+
+#[test]
+fn escape_like_pattern_escapes_both_brackets()
+{
+    //! Passes when `[` and `]` are both escaped so no character class can be formed.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(crate::fetch_definitions::escape_like_pattern("fct[ab]"), r"fct\[ab\]");
+    assert_eq!(crate::fetch_definitions::escape_like_pattern("x]"), r"x\]");
+}
+
+#[test]
+fn build_definition_params_follows_profile_layout()
+{
+    //! Passes when the default layout binds only the escaped LIKE pattern and the Data Factory layout
+    //! binds the workspace first, with each profile resolving to its declared layout.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::fetch_definitions::build_definition_params;
+
+    assert_eq!(
+        build_definition_params(DefinitionParamLayout::TablePrefix, "dim_project", "sales"),
+        vec![r"dim\_project%".to_string()]
+    );
+    assert_eq!(
+        build_definition_params(DefinitionParamLayout::WorkspaceAndTablePrefix, "dim_project", " sales "),
+        vec!["sales".to_string(), r"dim\_project%".to_string()]
+    );
+
+    assert_eq!(prompt_profile_spec(PromptProfile::Fabric).definition_params, DefinitionParamLayout::TablePrefix);
+    assert_eq!(
+        prompt_profile_spec(PromptProfile::DataFactory).definition_params,
+        DefinitionParamLayout::WorkspaceAndTablePrefix
+    );
+
+    // A profile with its own layout must ship its own query; the Fabric query binds a single `?`.
+    for spec in PROMPT_PROFILE_SPECS.iter().filter(|spec| spec.definition_params != DefinitionParamLayout::TablePrefix)
+    {
+        let sql_path = crate::fetch_definitions::find_fabric_sql_path(&find_repo_root_path(), spec.name);
+        assert_eq!(sql_path.file_name().unwrap().to_string_lossy(), format!("fetch_{}_definitions.sql", spec.name));
+    }
+}
+
+#[test]
+fn effective_param_layout_falls_back_without_workspace()
+{
+    //! Passes when a workspace layout with an empty `DEFINITION_WORKSPACE` falls back to the single
+    //! table prefix parameter and the default definitions query.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::fetch_definitions::{build_definition_params, effective_param_layout, find_fabric_sql_path};
+
+    let declared = prompt_profile_spec(PromptProfile::DataFactory).definition_params;
+    assert_eq!(effective_param_layout(declared, "  "), DefinitionParamLayout::TablePrefix);
+    assert_eq!(effective_param_layout(declared, "sales"), DefinitionParamLayout::WorkspaceAndTablePrefix);
+    assert_eq!(effective_param_layout(DefinitionParamLayout::TablePrefix, "sales"), DefinitionParamLayout::TablePrefix);
+
+    assert_eq!(
+        build_definition_params(effective_param_layout(declared, ""), "pl_orders", ""),
+        vec![r"pl\_orders%".to_string()]
+    );
+    let fallback_sql = find_fabric_sql_path(&find_repo_root_path(), "");
+    assert_eq!(fallback_sql.file_name().unwrap().to_string_lossy(), "fetch_fabric_definitions.sql");
+}
+
+#[test]
+fn accumulate_definition_rows_keeps_partial_result_only_when_allowed()
+{
+    //! Passes when all batches are collected, a mid-fetch error after N batches returns the rows read so far
+    //! with `allow_partial`, and fails without it or when no rows were read yet.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::errors::OdbcErrorKind;
+    use crate::fetch_definitions::{accumulate_definition_rows, DefinitionRow};
+
+    let row = |value: &str| DefinitionRow { cells: vec![Some(value.to_string())] };
+    // Yields `batches` batches of two rows each, then errors (or ends when `fail` is false).
+    let sequence = |batches: usize, fail: bool|
+    {
+        let mut fetched = 0;
+        move ||
+        {
+            if fetched == batches && fail
+            {
+                return Err(DoxcerError::Odbc
+                {
+                    kind: OdbcErrorKind::ResultFetch,
+                    message: "Failed to fetch rows".to_string(),
+                    source: None,
+                });
+            }
+            if fetched == batches
+            {
+                return Ok(None);
+            }
+            fetched += 1;
+            Ok(Some(vec![row(&format!("b{}r1", fetched)), row(&format!("b{}r2", fetched))]))
+        }
+    };
+
+    assert_eq!(accumulate_definition_rows(sequence(3, false), false).unwrap().len(), 6);
+
+    let partial = accumulate_definition_rows(sequence(2, true), true).unwrap();
+    assert_eq!(partial.len(), 4);
+    assert_eq!(partial[3], row("b2r2"));
+
+    assert!(matches!(
+        accumulate_definition_rows(sequence(2, true), false),
+        Err(DoxcerError::Odbc { kind: OdbcErrorKind::ResultFetch, .. })
+    ));
+    assert!(accumulate_definition_rows(sequence(0, true), true).is_err());
+}
+
+#[test]
+fn is_query_timeout_matches_only_hyt00()
+{
+    //! Passes when SQLSTATE `HYT00` is treated as a timeout and other diagnostics are not.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let diagnostics = |state: &[u8; 5]| odbc_api::Error::Diagnostics
+    {
+        record: odbc_api::handles::Record
+        {
+            state: odbc_api::handles::State(*state),
+            native_error: 0,
+            message: Vec::new(),
+        },
+        function: "SQLExecDirect",
+    };
+
+    assert!(crate::fetch_definitions::is_query_timeout(&diagnostics(b"HYT00")));
+    assert!(!crate::fetch_definitions::is_query_timeout(&diagnostics(b"42S02")));
+    assert!(!crate::fetch_definitions::is_query_timeout(&odbc_api::Error::NoDiagnostics { function: "SQLExecDirect" }));
+}
+
+#[test]
+fn definition_table_filter_nulls_drops_rows_with_null_cells()
+{
+    //! Passes when rows with `None` in the named column are dropped, the count is returned
+    //! and `NULL` cells in other columns are kept as empty text.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::fetch_definitions::{DefinitionRow, DefinitionTable};
+
+    let row = |cells: &[Option<&str>]| DefinitionRow
+    {
+        cells: cells.iter().map(|cell| cell.map(str::to_string)).collect(),
+    };
+    let mut table = DefinitionTable
+    {
+        columns: vec!["column_name".to_string(), "description".to_string()],
+        rows: vec![
+            row(&[Some("order_id"), Some("Order key")]),
+            row(&[Some("status"), None]),
+            row(&[None, Some("Orphan description")]),
+            row(&[Some("amount"), None]),
+        ],
+    };
+
+    assert_eq!(table.filter_nulls("column_name"), 1);
+    assert_eq!(table.rows.len(), 3);
+    assert_eq!(
+        table.text_rows()[1],
+        vec!["status".to_string(), String::new()]
+    );
+
+    assert_eq!(table.filter_nulls("DESCRIPTION"), 2);
+    assert_eq!(table.text_rows(), vec![vec!["order_id".to_string(), "Order key".to_string()]]);
+}
+
+#[test]
+fn definition_table_filter_nulls_warns_on_unknown_column()
+{
+    //! Passes when an unknown column removes nothing and logs a warning.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::fetch_definitions::{DefinitionRow, DefinitionTable};
+
+    let mut table = DefinitionTable
+    {
+        columns: vec!["column_name".to_string()],
+        rows: vec![DefinitionRow { cells: vec![None] }],
+    };
+
+    let warnings_before = crate::logger::LOGGER.warning_count();
+    assert_eq!(table.filter_nulls("missing"), 0);
+    assert_eq!(table.rows.len(), 1);
+    assert!(crate::logger::LOGGER.warning_count() > warnings_before);
+
+    let parsed = parse_cli_args(&make_args(&["doxcer", "--exclude-nulls", "description", "test/example.py"])).unwrap();
+    assert_eq!(parsed.exclude_nulls.as_deref(), Some("description"));
+}
+
+// ----------------------------
+// fetch_secrets.rs
+// ----------------------------
+
+#[test]
+fn parse_credential_kind_accepts_supported_values()
+{
+    //! Passes when supported `AKV_CREDENTIAL` values map to credential kinds.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::fetch_secrets::{parse_credential_kind, CredentialKind};
+
+    assert_eq!(parse_credential_kind("developer"), Ok(CredentialKind::Developer));
+    assert_eq!(parse_credential_kind(""), Ok(CredentialKind::Developer));
+    assert_eq!(parse_credential_kind(" Managed_Identity "), Ok(CredentialKind::ManagedIdentity));
+    assert_eq!(parse_credential_kind("default_chain"), Ok(CredentialKind::DefaultChain));
+}
+
+#[test]
+fn build_credential_rejects_unsupported_kind()
+{
+    //! Passes when an unsupported credential kind returns an error instead of a credential.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    let err = crate::fetch_secrets::build_credential("client_secret").unwrap_err();
+    assert!(err.contains("Unsupported AKV_CREDENTIAL 'client_secret'"));
+}
+
+#[test]
+fn default_chain_skips_credentials_that_fail_to_construct()
+{
+    //! Passes when a failed managed identity falls through to the next credential and only an all-failed
+    //! chain is an error.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::fetch_secrets::chain_sources;
+
+    let managed_identity_failed: Vec<Result<&str, String>> = vec![
+        Err("[ERR] - Failed to create ManagedIdentityCredential: no endpoint".to_string()),
+        Ok("developer"),
+    ];
+    assert_eq!(chain_sources(managed_identity_failed), Ok(vec!["developer"]));
+    assert_eq!(chain_sources(vec![Ok("managed_identity"), Ok("developer")]), Ok(vec!["managed_identity", "developer"]));
+
+    let err = chain_sources::<&str>(vec![Err("[ERR] - first".to_string()), Err("[ERR] - second".to_string())]).unwrap_err();
+    assert!(err.contains("first") && err.contains("second"));
+}
+
+
+// ----------------------------
+// errors.rs
+// ----------------------------
+
+#[test]
+fn doxcer_error_exposes_wrapped_errors_through_source()
+{
+    //! Passes when wrapped I/O and ODBC errors are returned by `source()`, `?` conversions keep the
+    //! original error, and variants without an underlying error have no source.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use std::error::Error;
+
+    let io_error = DoxcerError::io(
+        "Failed to read SQL file for definitions",
+        std::io::Error::new(std::io::ErrorKind::NotFound, "missing.sql"),
+    );
+    assert_eq!(io_error.to_string(), "[ERR] - Failed to read SQL file for definitions: missing.sql");
+    let source = io_error.source().expect("I/O error should have a source");
+    assert_eq!(source.downcast_ref::<std::io::Error>().unwrap().kind(), std::io::ErrorKind::NotFound);
+
+    let read_sql = || -> std::result::Result<String, DoxcerError> { Ok(fs::read_to_string("/definitely/not/here.sql")?) };
+    assert!(matches!(read_sql(), Err(DoxcerError::Io { .. })));
+
+    let connect = || -> std::result::Result<(), DoxcerError> { Err(odbc_api::Error::FailedAllocatingEnvironment)? };
+    let odbc_error = connect().unwrap_err();
+    assert!(odbc_error.to_string().starts_with("[ERR] - ODBC query error: ODBC call failed: "));
+    assert!(odbc_error.source().unwrap().downcast_ref::<odbc_api::Error>().is_some());
+
+    let timeout = DoxcerError::Odbc
+    {
+        kind: crate::errors::OdbcErrorKind::QueryExecution,
+        message: "Query timed out".to_string(),
+        source: None,
+    };
+    assert_eq!(timeout.to_string(), "[ERR] - ODBC query error: Query timed out");
+    assert!(timeout.source().is_none());
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let key_vault = crate::fetch_secrets::try_get_secret_from_key_vault_on(
+        runtime.handle(),
+        "https://example.vault.azure.net/",
+        "secret",
+        "client_secret",
+        &KeyVaultOptions { retry_policy: RetryPolicy { attempts: 1, delay_ms: 0 }, ..KeyVaultOptions::default() },
+    )
+    .unwrap_err();
+    assert!(key_vault.to_string().starts_with("[ERR] - Key Vault error: Unsupported AKV_CREDENTIAL 'client_secret'"));
+    assert!(key_vault.source().is_none());
+    assert!(DoxcerError::TemplateNotFound(PathBuf::from("x_prompt.md")).source().is_none());
+}
+
+#[test]
+fn odbc_error_kind_classifies_by_sqlstate_and_failed_step()
+{
+    //! Passes when SQLSTATE `28000` is an authentication error, class `08` a connection error,
+    //! and other states or errors without diagnostics keep the kind of the failed step.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    use crate::errors::OdbcErrorKind;
+
+    let diagnostics = |state: &[u8; 5]| odbc_api::Error::Diagnostics
+    {
+        record: odbc_api::handles::Record
+        {
+            state: odbc_api::handles::State(*state),
+            native_error: 0,
+            message: Vec::new(),
+        },
+        function: "SQLDriverConnect",
+    };
+
+    assert_eq!(OdbcErrorKind::classify(&diagnostics(b"28000"), OdbcErrorKind::Connection), OdbcErrorKind::Authentication);
+    assert_eq!(OdbcErrorKind::classify(&diagnostics(b"08001"), OdbcErrorKind::QueryExecution), OdbcErrorKind::Connection);
+    assert_eq!(OdbcErrorKind::classify(&diagnostics(b"08S01"), OdbcErrorKind::ResultFetch), OdbcErrorKind::Connection);
+    assert_eq!(OdbcErrorKind::classify(&diagnostics(b"42S02"), OdbcErrorKind::QueryExecution), OdbcErrorKind::QueryExecution);
+    assert_eq!(
+        OdbcErrorKind::classify(&odbc_api::Error::NoDiagnostics { function: "SQLFetch" }, OdbcErrorKind::ResultFetch),
+        OdbcErrorKind::ResultFetch
+    );
+
+    let auth = DoxcerError::odbc(OdbcErrorKind::Connection, "ODBC connect failed", diagnostics(b"28000"));
+    assert!(matches!(auth, DoxcerError::Odbc { kind: OdbcErrorKind::Authentication, .. }));
+    assert!(auth.to_string().starts_with("[ERR] - ODBC authentication error: ODBC connect failed"));
+}
+
+#[test]
+fn parse_error_display_matches_cli_messages()
+{
+    //! Passes when each `ParseError` formats as an `[ERR]` line naming the offending argument,
+    //! and flag value errors come back as typed variants from `parse_cli_args`.
+    //!
+    //! # Panics
+    //! - If assertions fail.
+
+    assert_eq!(ParseError::MissingExecutableName.to_string(), "[ERR] - Missing executable name.");
+    assert_eq!(parse_cli_args(&[]).unwrap_err(), ParseError::MissingExecutableName);
+    assert_eq!(
+        ParseError::ConflictingCommands { arg: "--version".to_string() }.to_string(),
+        "[ERR] - Conflicting commands: '--version' cannot be combined with another command."
+    );
+    assert_eq!(
+        ParseError::invalid_value("--order", "random", Some("name or mtime")).to_string(),
+        "[ERR] - Invalid value 'random' for '--order'. Expected name or mtime."
+    );
+    assert_eq!(
+        ParseError::invalid_value("--seed", "abc", None).to_string(),
+        "[ERR] - Invalid value 'abc' for '--seed'."
+    );
+
+    assert_eq!(
+        parse_cli_args(&make_args(&["doxcer", "test/example.py", "--model"])).unwrap_err(),
+        ParseError::MissingValue { flag: "--model".to_string() }
+    );
+    assert_eq!(
+        parse_cli_args(&make_args(&["doxcer", "--akv-timeout", "0", "test/example.py"])).unwrap_err(),
+        ParseError::BelowMinimum { flag: "--akv-timeout".to_string(), minimum: 1 }
+    );
+    assert_eq!(
+        parse_cli_args(&make_args(&["doxcer", "--no-such-flag", "test/example.py"])).unwrap_err().to_string(),
+        "[ERR] - Unknown flag '--no-such-flag'. Run doxcer --help for the supported options."
+    );
+}